@@ -1,3 +1,4 @@
+#[allow(clippy::collapsible_if)]
 fn main() {
     // Only embed resources on Windows builds
     if std::env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("windows") {
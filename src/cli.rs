@@ -0,0 +1,209 @@
+use clap::Parser;
+
+use polycue::color::{ColorSpace, PoolSource};
+use crate::gui::SliderConfig;
+use polycue::io::{ImageFormat, ManifestFormat};
+
+/// Command line options. Every field is optional; anything left unset keeps the
+/// `SliderConfig` default, so the GUI can be launched pre-configured.
+#[derive(Debug, Default, Parser)]
+#[command(name = "polycue", version, about = "Poly Cue fiducial tag generator")]
+pub struct Cli {
+    /// Number of tags to generate
+    #[arg(long, value_parser = parse_count)]
+    pub count: Option<usize>,
+
+    /// Polygon side count (3-12)
+    #[arg(long, value_parser = parse_sides)]
+    pub sides: Option<usize>,
+
+    /// Fixed ΔE threshold instead of the automatic maximum
+    #[arg(long, value_parser = parse_threshold)]
+    pub threshold: Option<f32>,
+
+    /// ΔE below which a warning is printed that colors may be hard to distinguish
+    #[arg(long, value_parser = parse_min_usable_threshold)]
+    pub min_usable_threshold: Option<f32>,
+
+    /// Minimum Lab lightness range within each tag
+    #[arg(long, value_parser = parse_lightness_spread)]
+    pub min_lightness_spread: Option<f32>,
+
+    /// Width/height of saved images in pixels
+    #[arg(long, value_parser = parse_save_size)]
+    pub save_size: Option<u32>,
+
+    /// Width/height the previews are rendered at, in pixels
+    #[arg(long, value_parser = parse_preview_res)]
+    pub preview_res: Option<u32>,
+
+    /// Draw the solid center dot (true/false)
+    #[arg(long)]
+    pub center_dot: Option<bool>,
+
+    /// Center dot size as a percentage of the tag width
+    #[arg(long, value_parser = parse_center_dot_size)]
+    pub center_dot_size: Option<f32>,
+
+    /// Draw the gradient dot (true/false)
+    #[arg(long)]
+    pub gradient_dot: Option<bool>,
+
+    /// Gradient dot size as a percentage of the tag width
+    #[arg(long, value_parser = parse_gradient_dot_size)]
+    pub gradient_dot_size: Option<f32>,
+
+    /// Root directory for timestamped output folders
+    #[arg(long)]
+    pub output_dir: Option<String>,
+
+    /// Image format for saved tags
+    #[arg(long, value_enum)]
+    pub format: Option<ImageFormat>,
+
+    /// File format of the manifest
+    #[arg(long, value_enum)]
+    pub manifest_format: Option<ManifestFormat>,
+
+    /// CSV file receiving per-stage timings when profiling is on
+    #[arg(long)]
+    pub profile_path: Option<String>,
+
+    /// Color space in which ΔE is measured
+    #[arg(long, value_enum)]
+    pub color_space: Option<ColorSpace>,
+
+    /// Built-in candidate pool
+    #[arg(long, value_enum)]
+    pub pool: Option<PoolSource>,
+
+    /// Use only neutral grays, so tags differ purely in lightness (same as `--pool grayscale`)
+    #[arg(long)]
+    pub grayscale: bool,
+
+    /// Seed for color selection and grouping
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Embed each tag's manifest data in its PNG (iTXt chunk "polycue")
+    #[arg(long)]
+    pub embed_metadata: bool,
+
+    /// Generate and save without opening the window
+    #[arg(long)]
+    pub headless: bool,
+}
+
+fn parse_in_range<T>(s: &str, min: T, max: T) -> Result<T, String>
+where
+    T: std::str::FromStr + PartialOrd + std::fmt::Display + Copy,
+{
+    let v: T = s.parse().map_err(|_| format!("`{}` is not a valid number", s))?;
+    if v < min || v > max {
+        return Err(format!("{} is outside the allowed range {}..={}", v, min, max));
+    }
+    Ok(v)
+}
+
+fn parse_count(s: &str) -> Result<usize, String> {
+    parse_in_range(s, SliderConfig::COUNT_MIN as usize, SliderConfig::COUNT_MAX as usize)
+}
+
+fn parse_sides(s: &str) -> Result<usize, String> {
+    parse_in_range(s, SliderConfig::SIDES_MIN as usize, SliderConfig::SIDES_MAX as usize)
+}
+
+fn parse_threshold(s: &str) -> Result<f32, String> {
+    parse_in_range(s, 0.0, 400.0)
+}
+
+fn parse_min_usable_threshold(s: &str) -> Result<f32, String> {
+    parse_in_range(s, SliderConfig::MIN_USABLE_THRESHOLD_MIN, SliderConfig::MIN_USABLE_THRESHOLD_MAX)
+}
+
+fn parse_lightness_spread(s: &str) -> Result<f32, String> {
+    parse_in_range(s, SliderConfig::LIGHTNESS_SPREAD_MIN, SliderConfig::LIGHTNESS_SPREAD_MAX)
+}
+
+fn parse_save_size(s: &str) -> Result<u32, String> {
+    parse_in_range(s, SliderConfig::SAVE_SIZE_MIN, SliderConfig::SAVE_SIZE_MAX)
+}
+
+fn parse_preview_res(s: &str) -> Result<u32, String> {
+    parse_in_range(s, SliderConfig::RESOLUTION_MIN as u32, SliderConfig::RESOLUTION_MAX as u32)
+}
+
+fn parse_center_dot_size(s: &str) -> Result<f32, String> {
+    parse_in_range(s, SliderConfig::CENTER_DOT_MIN, SliderConfig::CENTER_DOT_MAX)
+}
+
+fn parse_gradient_dot_size(s: &str) -> Result<f32, String> {
+    parse_in_range(s, SliderConfig::GRADIENT_DOT_MIN, SliderConfig::GRADIENT_DOT_MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        Cli::try_parse_from(std::iter::once("polycue").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn no_arguments_leave_every_setting_unset() {
+        let cli = parse(&[]).unwrap();
+        assert_eq!(cli.count, None);
+        assert_eq!(cli.sides, None);
+        assert_eq!(cli.seed, None);
+        assert!(!cli.headless);
+    }
+
+    #[test]
+    fn valid_options_are_parsed() {
+        let cli = parse(&[
+            "--count", "12", "--sides", "5", "--threshold", "18.5", "--save-size", "512",
+            "--center-dot", "false", "--gradient-dot-size", "30", "--output-dir", "out",
+            "--format", "tiff", "--manifest-format", "yaml", "--color-space", "oklab",
+            "--pool", "web-safe", "--seed", "42",
+        ])
+        .unwrap();
+        assert_eq!(cli.count, Some(12));
+        assert_eq!(cli.sides, Some(5));
+        assert_eq!(cli.threshold, Some(18.5));
+        assert_eq!(cli.save_size, Some(512));
+        assert_eq!(cli.center_dot, Some(false));
+        assert_eq!(cli.gradient_dot_size, Some(30.0));
+        assert_eq!(cli.output_dir.as_deref(), Some("out"));
+        assert_eq!(cli.format, Some(ImageFormat::Tiff));
+        assert_eq!(cli.manifest_format, Some(ManifestFormat::Yaml));
+        assert_eq!(cli.color_space, Some(ColorSpace::Oklab));
+        assert_eq!(cli.pool, Some(PoolSource::WebSafe));
+        assert_eq!(cli.seed, Some(42));
+    }
+
+    #[test]
+    fn out_of_range_values_are_rejected() {
+        for args in [
+            ["--sides", "2"],
+            ["--sides", "13"],
+            ["--count", "0"],
+            ["--save-size", "1"],
+            ["--center-dot-size", "150"],
+            ["--gradient-dot-size", "0"],
+            ["--threshold", "500"],
+        ] {
+            let err = parse(&args).expect_err(&format!("{:?} should be rejected", args));
+            assert_eq!(err.kind(), clap::error::ErrorKind::ValueValidation, "{:?}", args);
+        }
+        assert!(parse(&["--count", "ten"]).is_err());
+        assert!(parse(&["--format", "gif"]).is_err());
+    }
+
+    #[test]
+    fn headless_flag_is_recognized() {
+        let cli = parse(&["--headless", "--count", "3"]).unwrap();
+        assert!(cli.headless);
+        assert_eq!(cli.count, Some(3));
+        assert!(parse(&["--headless", "yes"]).is_err());
+    }
+}
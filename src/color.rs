@@ -1,16 +1,26 @@
-use palette::{rgb::Srgb, FromColor, Lab};
-use image::Rgb;
-use rand::{seq::SliceRandom, thread_rng, Rng};
+use palette::{rgb::Srgb, FromColor, Lab, LinSrgb, Oklab};
+use image::{DynamicImage, Rgb};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
 
-/// CIE76 distance calculation for perceptually uniform color differences
+/// Euclidean color difference: CIE76 on Lab, or ΔEOK on `ColorSpace::Oklab` coordinates
 pub fn delta_e(a: Lab, b: Lab) -> f32 {
-    let dl = a.l - b.l;
+    delta_e_with(a, b, 1.0)
+}
+
+/// `delta_e` with the lightness difference scaled by `lightness_weight`. Below 1.0 the
+/// distance leans on chroma and hue, so it is no longer plain CIE76 or ΔEOK.
+pub fn delta_e_with(a: Lab, b: Lab, lightness_weight: f32) -> f32 {
+    let dl = (a.l - b.l) * lightness_weight;
     let da = a.a - b.a;
     let db = a.b - b.b;
     (dl * dl + da * da + db * db).sqrt()
 }
 
-/// Convert sRGB u8 values to CIE Lab color space
+/// Convert sRGB u8 values to CIE Lab color space. The values are decoded with the sRGB
+/// transfer function and converted through linear RGB with palette's defaults, so Lab is
+/// relative to the D65 white point (sRGB red is about L 53.24, a 80.09, b 67.20). Tools that
+/// use a D50 Lab, as ICC profiles do, report slightly different values.
 pub fn srgb_u8_to_lab(rgb: Rgb<u8>) -> Lab {
     let srgb_f = Srgb::new(
         rgb[0] as f32 / 255.0,
@@ -20,13 +30,200 @@ pub fn srgb_u8_to_lab(rgb: Rgb<u8>) -> Lab {
     Lab::from_color(srgb_f.into_linear())
 }
 
+/// Convert u8 values that are already linear RGB (no sRGB transfer function) to D65 CIE Lab
+pub fn linear_u8_to_lab(rgb: Rgb<u8>) -> Lab {
+    let lin = LinSrgb::new(
+        rgb[0] as f32 / 255.0,
+        rgb[1] as f32 / 255.0,
+        rgb[2] as f32 / 255.0,
+    );
+    Lab::from_color(lin)
+}
+
+/// Convert sRGB u8 values to Oklab
+pub fn srgb_u8_to_oklab(rgb: Rgb<u8>) -> Oklab {
+    let srgb_f = Srgb::new(
+        rgb[0] as f32 / 255.0,
+        rgb[1] as f32 / 255.0,
+        rgb[2] as f32 / 255.0,
+    );
+    Oklab::from_color(srgb_f.into_linear())
+}
+
+/// Color space in which ΔE distances are measured during selection and grouping
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorSpace {
+    /// CIE Lab with CIE76 distance
+    #[default]
+    Lab,
+    /// Oklab, more uniform in hue (notably across blues)
+    Oklab,
+    /// CIE Lab with CIE76 distance, reading the colors as linear RGB instead of sRGB
+    #[serde(rename = "linear-lab")]
+    LinearLab,
+}
+
+impl ColorSpace {
+    pub const ALL: [ColorSpace; 3] = [ColorSpace::Lab, ColorSpace::Oklab, ColorSpace::LinearLab];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ColorSpace::Lab => "CIE Lab",
+            ColorSpace::Oklab => "Oklab",
+            ColorSpace::LinearLab => "CIE Lab (linear input)",
+        }
+    }
+
+    /// Name of the distance `delta_e` computes on this space's coordinates
+    pub fn delta_e_formula(self) -> &'static str {
+        match self {
+            ColorSpace::Lab | ColorSpace::LinearLab => "CIE76",
+            ColorSpace::Oklab => "ΔEOK ×100",
+        }
+    }
+
+    /// Coordinates used for distances in this space, carried in a `Lab` so the selection
+    /// and grouping code stays space-agnostic. Oklab is scaled by 100 to put its ΔE on
+    /// roughly the same numeric range as CIE76.
+    pub fn coords(self, rgb: Rgb<u8>) -> Lab {
+        match self {
+            ColorSpace::Lab => srgb_u8_to_lab(rgb),
+            ColorSpace::Oklab => {
+                let ok = srgb_u8_to_oklab(rgb);
+                Lab::new(ok.l * 100.0, ok.a * 100.0, ok.b * 100.0)
+            }
+            ColorSpace::LinearLab => linear_u8_to_lab(rgb),
+        }
+    }
+
+    /// `coords` with L scaled by `lightness_weight`, so plain `delta_e` on them matches
+    /// `delta_e_with` and the matrix-based selection code needs no extra parameter
+    pub fn weighted_coords(self, rgb: Rgb<u8>, lightness_weight: f32) -> Lab {
+        let c = self.coords(rgb);
+        Lab::new(c.l * lightness_weight, c.a, c.b)
+    }
+}
+
+/// Convert CIE Lab back to sRGB u8, clamping out-of-gamut values
+pub fn lab_to_srgb_u8(lab: Lab) -> Rgb<u8> {
+    let srgb = Srgb::from_linear(LinSrgb::from_color(lab));
+    let to_u8 = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    Rgb([to_u8(srgb.red), to_u8(srgb.green), to_u8(srgb.blue)])
+}
+
+/// Mean of `colors` in CIE Lab, back in sRGB: a single color that stands for a whole tag
+pub fn signature_color(colors: &[Rgb<u8>]) -> Rgb<u8> {
+    let n = colors.len().max(1) as f32;
+    let (l, a, b) = colors.iter().map(|&c| srgb_u8_to_lab(c)).fold((0.0, 0.0, 0.0), |s, c| (s.0 + c.l, s.1 + c.a, s.2 + c.b));
+    lab_to_srgb_u8(Lab::new(l / n, a / n, b / n))
+}
+
+/// Extract up to `k` representative colors from an image using k-means in Lab space.
+/// Large images are downscaled first so sampling cost stays bounded.
+pub fn palette_from_image(img: &DynamicImage, k: usize) -> Vec<Rgb<u8>> {
+    const MAX_SAMPLE_DIM: u32 = 256;
+    const MAX_ITERS: usize = 25;
+
+    let small = if img.width() > MAX_SAMPLE_DIM || img.height() > MAX_SAMPLE_DIM {
+        img.thumbnail(MAX_SAMPLE_DIM, MAX_SAMPLE_DIM)
+    } else {
+        img.clone()
+    };
+    let samples: Vec<Lab> = small.to_rgb8().pixels().copied().map(srgb_u8_to_lab).collect();
+    if samples.is_empty() || k == 0 {
+        return Vec::new();
+    }
+    let k = k.min(samples.len());
+
+    // Farthest-point initialization: deterministic and spreads the seeds across the gamut
+    let mut centers: Vec<Lab> = vec![samples[0]];
+    let mut nearest: Vec<f32> = samples.iter().map(|&s| delta_e(s, samples[0])).collect();
+    while centers.len() < k {
+        let (far_idx, far_d) = nearest
+            .iter()
+            .enumerate()
+            .fold((0, -1.0f32), |best, (i, &d)| if d > best.1 { (i, d) } else { best });
+        if far_d <= 0.0 {
+            break; // fewer distinct colors than k
+        }
+        let c = samples[far_idx];
+        centers.push(c);
+        for (n, &s) in nearest.iter_mut().zip(&samples) {
+            *n = n.min(delta_e(s, c));
+        }
+    }
+
+    // Lloyd iterations
+    let mut assignment = vec![usize::MAX; samples.len()];
+    for _ in 0..MAX_ITERS {
+        let mut changed = false;
+        for (i, &s) in samples.iter().enumerate() {
+            let mut best = 0;
+            let mut best_d = f32::INFINITY;
+            for (ci, &c) in centers.iter().enumerate() {
+                let d = delta_e(s, c);
+                if d < best_d {
+                    best_d = d;
+                    best = ci;
+                }
+            }
+            if assignment[i] != best {
+                assignment[i] = best;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+        let mut sums = vec![(0.0f32, 0.0f32, 0.0f32, 0usize); centers.len()];
+        for (&a, &s) in assignment.iter().zip(&samples) {
+            let e = &mut sums[a];
+            e.0 += s.l;
+            e.1 += s.a;
+            e.2 += s.b;
+            e.3 += 1;
+        }
+        for (c, &(l, a, b, n)) in centers.iter_mut().zip(&sums) {
+            if n > 0 {
+                let n = n as f32;
+                *c = Lab::new(l / n, a / n, b / n);
+            }
+        }
+    }
+
+    let mut palette: Vec<Rgb<u8>> = Vec::with_capacity(centers.len());
+    for c in centers.into_iter().map(lab_to_srgb_u8) {
+        if !palette.contains(&c) {
+            palette.push(c);
+        }
+    }
+    palette
+}
+
+/// Drop colors closer than `min_delta` (CIE76 ΔE in Lab) to an earlier kept color, so
+/// near-identical imported colors take up one candidate. Exact repeats always go; the first
+/// of each cluster is kept and order is preserved.
+pub fn merge_near_duplicates(colors: &[Rgb<u8>], min_delta: f32) -> Vec<Rgb<u8>> {
+    let mut kept: Vec<(Rgb<u8>, Lab)> = Vec::with_capacity(colors.len());
+    for &c in colors {
+        let lab = srgb_u8_to_lab(c);
+        if kept.iter().all(|&(k, k_lab)| k != c && delta_e(lab, k_lab) >= min_delta) {
+            kept.push((c, lab));
+        }
+    }
+    kept.into_iter().map(|(c, _)| c).collect()
+}
+
+/// Channel levels of the coarse sRGB grid
+pub const GRID_LEVELS: [u8; 6] = [16, 64, 112, 160, 208, 255];
+
 /// Generate a coarse grid of sRGB colors (6 levels per channel = 216 candidates)
 pub fn candidate_srgb_grid() -> Vec<Rgb<u8>> {
-    let levels: [u8; 6] = [16, 64, 112, 160, 208, 255];
     let mut v = Vec::with_capacity(216);
-    for &r in &levels {
-        for &g in &levels {
-            for &b in &levels {
+    for &r in &GRID_LEVELS {
+        for &g in &GRID_LEVELS {
+            for &b in &GRID_LEVELS {
                 v.push(Rgb([r, g, b]));
             }
         }
@@ -34,6 +231,86 @@ pub fn candidate_srgb_grid() -> Vec<Rgb<u8>> {
     v
 }
 
+/// Move each channel to the nearest `GRID_LEVELS` value (the lower one on a tie)
+pub fn snap_to_grid(c: Rgb<u8>) -> Rgb<u8> {
+    Rgb(c.0.map(|v| *GRID_LEVELS.iter().min_by_key(|&&l| (l as i16 - v as i16).abs()).unwrap_or(&v)))
+}
+
+/// Generate every neutral gray (256 candidates), so ΔE reduces to the lightness difference
+pub fn candidate_grayscale_ramp() -> Vec<Rgb<u8>> {
+    (0..=255u8).map(|v| Rgb([v, v, v])).collect()
+}
+
+/// Channel levels of the 216 web-safe colors
+const WEB_SAFE_LEVELS: [u8; 6] = [0x00, 0x33, 0x66, 0x99, 0xCC, 0xFF];
+
+/// Material Design swatches: shades 50 and 100-900 of each of the 19 hues
+const MATERIAL_SWATCHES: [u32; 190] = [
+    0xFFEBEE, 0xFFCDD2, 0xEF9A9A, 0xE57373, 0xEF5350, 0xF44336, 0xE53935, 0xD32F2F, 0xC62828, 0xB71C1C, // red
+    0xFCE4EC, 0xF8BBD0, 0xF48FB1, 0xF06292, 0xEC407A, 0xE91E63, 0xD81B60, 0xC2185B, 0xAD1457, 0x880E4F, // pink
+    0xF3E5F5, 0xE1BEE7, 0xCE93D8, 0xBA68C8, 0xAB47BC, 0x9C27B0, 0x8E24AA, 0x7B1FA2, 0x6A1B9A, 0x4A148C, // purple
+    0xEDE7F6, 0xD1C4E9, 0xB39DDB, 0x9575CD, 0x7E57C2, 0x673AB7, 0x5E35B1, 0x512DA8, 0x4527A0, 0x311B92, // deep purple
+    0xE8EAF6, 0xC5CAE9, 0x9FA8DA, 0x7986CB, 0x5C6BC0, 0x3F51B5, 0x3949AB, 0x303F9F, 0x283593, 0x1A237E, // indigo
+    0xE3F2FD, 0xBBDEFB, 0x90CAF9, 0x64B5F6, 0x42A5F5, 0x2196F3, 0x1E88E5, 0x1976D2, 0x1565C0, 0x0D47A1, // blue
+    0xE1F5FE, 0xB3E5FC, 0x81D4FA, 0x4FC3F7, 0x29B6F6, 0x03A9F4, 0x039BE5, 0x0288D1, 0x0277BD, 0x01579B, // light blue
+    0xE0F7FA, 0xB2EBF2, 0x80DEEA, 0x4DD0E1, 0x26C6DA, 0x00BCD4, 0x00ACC1, 0x0097A7, 0x00838F, 0x006064, // cyan
+    0xE0F2F1, 0xB2DFDB, 0x80CBC4, 0x4DB6AC, 0x26A69A, 0x009688, 0x00897B, 0x00796B, 0x00695C, 0x004D40, // teal
+    0xE8F5E9, 0xC8E6C9, 0xA5D6A7, 0x81C784, 0x66BB6A, 0x4CAF50, 0x43A047, 0x388E3C, 0x2E7D32, 0x1B5E20, // green
+    0xF1F8E9, 0xDCEDC8, 0xC5E1A5, 0xAED581, 0x9CCC65, 0x8BC34A, 0x7CB342, 0x689F38, 0x558B2F, 0x33691E, // light green
+    0xF9FBE7, 0xF0F4C3, 0xE6EE9C, 0xDCE775, 0xD4E157, 0xCDDC39, 0xC0CA33, 0xAFB42B, 0x9E9D24, 0x827717, // lime
+    0xFFFDE7, 0xFFF9C4, 0xFFF59D, 0xFFF176, 0xFFEE58, 0xFFEB3B, 0xFDD835, 0xFBC02D, 0xF9A825, 0xF57F17, // yellow
+    0xFFF8E1, 0xFFECB3, 0xFFE082, 0xFFD54F, 0xFFCA28, 0xFFC107, 0xFFB300, 0xFFA000, 0xFF8F00, 0xFF6F00, // amber
+    0xFFF3E0, 0xFFE0B2, 0xFFCC80, 0xFFB74D, 0xFFA726, 0xFF9800, 0xFB8C00, 0xF57C00, 0xEF6C00, 0xE65100, // orange
+    0xFBE9E7, 0xFFCCBC, 0xFFAB91, 0xFF8A65, 0xFF7043, 0xFF5722, 0xF4511E, 0xE64A19, 0xD84315, 0xBF360C, // deep orange
+    0xEFEBE9, 0xD7CCC8, 0xBCAAA4, 0xA1887F, 0x8D6E63, 0x795548, 0x6D4C41, 0x5D4037, 0x4E342E, 0x3E2723, // brown
+    0xFAFAFA, 0xF5F5F5, 0xEEEEEE, 0xE0E0E0, 0xBDBDBD, 0x9E9E9E, 0x757575, 0x616161, 0x424242, 0x212121, // grey
+    0xECEFF1, 0xCFD8DC, 0xB0BEC5, 0x90A4AE, 0x78909C, 0x607D8B, 0x546E7A, 0x455A64, 0x37474F, 0x263238, // blue grey
+];
+
+/// Built-in candidate pools
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum PoolSource {
+    /// Coarse sRGB grid (see `candidate_srgb_grid`)
+    #[default]
+    FullGrid,
+    /// The 216 web-safe colors
+    WebSafe,
+    /// Material Design swatches
+    Material,
+    /// Every neutral gray, for lightness-only tags
+    Grayscale,
+}
+
+impl PoolSource {
+    pub const ALL: [PoolSource; 4] = [PoolSource::FullGrid, PoolSource::WebSafe, PoolSource::Material, PoolSource::Grayscale];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PoolSource::FullGrid => "Full grid",
+            PoolSource::WebSafe => "Web-safe",
+            PoolSource::Material => "Material",
+            PoolSource::Grayscale => "Grayscale",
+        }
+    }
+
+    /// Candidate colors of this pool, before the lightness window is applied
+    pub fn colors(self) -> Vec<Rgb<u8>> {
+        match self {
+            PoolSource::FullGrid => candidate_srgb_grid(),
+            PoolSource::WebSafe => WEB_SAFE_LEVELS
+                .iter()
+                .flat_map(|&r| WEB_SAFE_LEVELS.iter().flat_map(move |&g| WEB_SAFE_LEVELS.iter().map(move |&b| Rgb([r, g, b]))))
+                .collect(),
+            PoolSource::Material => MATERIAL_SWATCHES
+                .iter()
+                .map(|&hex| Rgb([(hex >> 16) as u8, (hex >> 8) as u8, hex as u8]))
+                .collect(),
+            PoolSource::Grayscale => candidate_grayscale_ramp(),
+        }
+    }
+}
+
 /// Pick distinct colors based on strict threshold requirements
 pub fn pick_distinct_strict(
     labs: &[Lab],
@@ -54,39 +331,127 @@ pub fn pick_distinct_strict(
     picked_idx
 }
 
-/// Compute the maximum feasible color separation threshold for a given set
+/// Order the pool by greedy farthest-point traversal: start at the color farthest from
+/// the pool's mean, then repeatedly take the color farthest from everything taken so far.
+/// Deterministic, and any prefix is a well-spread set.
+pub fn farthest_point_order(labs: &[Lab]) -> Vec<usize> {
+    let n = labs.len();
+    if n == 0 {
+        return Vec::new();
+    }
+    let (sl, sa, sb) = labs.iter().fold((0.0, 0.0, 0.0), |(l, a, b), c| (l + c.l, a + c.a, b + c.b));
+    let mean = Lab::new(sl / n as f32, sa / n as f32, sb / n as f32);
+    let first = (0..n)
+        .max_by(|&i, &j| delta_e(labs[i], mean).total_cmp(&delta_e(labs[j], mean)))
+        .unwrap_or(0);
+    let mut order = Vec::with_capacity(n);
+    let mut nearest = vec![f32::INFINITY; n];
+    let mut next = first;
+    for _ in 0..n {
+        order.push(next);
+        nearest[next] = f32::NEG_INFINITY;
+        for (i, d) in nearest.iter_mut().enumerate() {
+            if *d != f32::NEG_INFINITY {
+                *d = d.min(delta_e(labs[i], labs[next]));
+            }
+        }
+        next = (0..n).max_by(|&i, &j| nearest[i].total_cmp(&nearest[j])).unwrap_or(0);
+    }
+    order
+}
+
+/// Order the pool by descending chroma (distance from the neutral axis), ties by index
+pub fn chroma_order(labs: &[Lab]) -> Vec<usize> {
+    let chroma = |c: Lab| c.a.hypot(c.b);
+    let mut order: Vec<usize> = (0..labs.len()).collect();
+    order.sort_by(|&i, &j| chroma(labs[j]).total_cmp(&chroma(labs[i])));
+    order
+}
+
+/// Shuffled orders tried per binary-search step after the farthest-point order
+const THRESHOLD_RETRIES: u64 = 4;
+
+/// Binary-search settings for `compute_max_threshold_and_colors_from_pool`.
+/// Each iteration halves the bracket, so precision is (hi - lo) / 2^iterations.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThresholdSearch {
+    pub iterations: usize,
+    /// Lower bound of the search (default 0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lo: Option<f32>,
+    /// Upper bound of the search (default: the pool's max pairwise ΔE)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hi: Option<f32>,
+    /// Try the most saturated candidates first, so near-grays are only picked when needed
+    pub prefer_vivid: bool,
+}
+
+impl Default for ThresholdSearch {
+    fn default() -> Self {
+        ThresholdSearch { iterations: 14, lo: None, hi: None, prefer_vivid: false }
+    }
+}
+
+/// Compute the maximum feasible color separation threshold for a given set.
+/// `dm` is the pool's cached `pairwise_delta_matrix`; when given, it supplies the exact
+/// max pairwise ΔE as the default upper bound instead of a random sample.
 pub fn compute_max_threshold_and_colors_from_pool(
     filtered: &[Rgb<u8>],
     labs: &[Lab],
     total: usize,
+    search: &ThresholdSearch,
+    dm: Option<&[f32]>,
+    rng: &mut impl Rng,
 ) -> (f32, Vec<Rgb<u8>>) {
-    let mut rng = thread_rng();
-    
-    // Determine upper bound by sampling for max pairwise ΔE
-    let mut max_d = 0.0f32;
-    for _ in 0..512 {
-        let i = rng.gen_range(0..labs.len());
-        let j = rng.gen_range(0..labs.len());
-        if i == j { continue; }
-        let d = delta_e(labs[i], labs[j]);
-        if d > max_d { max_d = d; }
-    }
+    let max_d = match dm {
+        Some(dm) => dm.iter().copied().fold(0.0f32, f32::max),
+        None => {
+            // Determine upper bound by sampling for max pairwise ΔE
+            let mut max_d = 0.0f32;
+            for _ in 0..512 {
+                let i = rng.gen_range(0..labs.len());
+                let j = rng.gen_range(0..labs.len());
+                if i == j { continue; }
+                let d = delta_e(labs[i], labs[j]);
+                if d > max_d { max_d = d; }
+            }
+            max_d
+        }
+    };
     
-    let mut lo = 0.0f32;
-    let mut hi = max_d;
+    let mut lo = search.lo.unwrap_or(0.0).max(0.0);
+    let mut hi = search.hi.unwrap_or(max_d).max(lo);
     let mut best_thr = 0.0f32;
     let mut best_idxs: Vec<usize> = Vec::new();
+    // Deterministic orders come first: by chroma when vivid colors are preferred, then
+    // farthest-point, which keeps the threshold high if the chroma order falls short
+    let mut fixed_orders = Vec::with_capacity(2);
+    if search.prefer_vivid {
+        fixed_orders.push(chroma_order(&labs[..filtered.len()]));
+    }
+    fixed_orders.push(farthest_point_order(&labs[..filtered.len()]));
+    let fixed = fixed_orders.len() as u64;
+    // Retry shuffles get their own seeds off one base, so they stay independent of each
+    // other yet reproducible from the caller's rng
+    let base_seed: u64 = rng.gen();
 
     // Binary search for highest feasible threshold
-    for _ in 0..14 {
+    for step in 0..search.iterations as u64 {
         let mid = (lo + hi) * 0.5;
         let mut feasible = false;
         let mut attempt_best: Vec<usize> = Vec::new();
         
-        // Try a few shuffled orders per threshold
-        for _ in 0..4 {
-            let mut order: Vec<usize> = (0..filtered.len()).collect();
-            order.shuffle(&mut rng);
+        // The deterministic orders, then a few shuffled orders
+        for attempt in 0..fixed + THRESHOLD_RETRIES {
+            let order = if attempt < fixed {
+                fixed_orders[attempt as usize].clone()
+            } else {
+                let mut order: Vec<usize> = (0..filtered.len()).collect();
+                let seed = base_seed.wrapping_add(step * THRESHOLD_RETRIES + attempt - fixed + 1);
+                order.shuffle(&mut StdRng::seed_from_u64(seed));
+                order
+            };
             let picked = pick_distinct_strict(labs, &order, mid, total);
             if picked.len() >= total {
                 feasible = true;
@@ -107,7 +472,7 @@ pub fn compute_max_threshold_and_colors_from_pool(
     // Build color list from best indices
     if best_idxs.len() < total {
         let mut order: Vec<usize> = (0..filtered.len()).collect();
-        order.shuffle(&mut rng);
+        order.shuffle(rng);
         best_idxs = pick_distinct_strict(labs, &order, best_thr, total);
     }
     
@@ -116,6 +481,166 @@ pub fn compute_max_threshold_and_colors_from_pool(
     (best_thr, colors)
 }
 
+/// Tags with their orientation wedge (if any) removed, for checks that should only see
+/// the colors that tell tags apart
+pub fn distinguishing_colors(tags: &[Vec<Rgb<u8>>], orientation_wedge: Option<usize>) -> Vec<Vec<Rgb<u8>>> {
+    tags.iter()
+        .map(|tag| tag.iter().enumerate().filter(|&(w, _)| Some(w) != orientation_wedge).map(|(_, &c)| c).collect())
+        .collect()
+}
+
+/// Find colors that appear in more than one tag.
+/// Returns each reused color with the (0-based) indices of the tags containing it.
+pub fn find_reused_colors(tags: &[Vec<Rgb<u8>>]) -> Vec<(Rgb<u8>, Vec<usize>)> {
+    let mut seen: Vec<(Rgb<u8>, Vec<usize>)> = Vec::new();
+    for (t, colors) in tags.iter().enumerate() {
+        for &c in colors {
+            match seen.iter_mut().find(|(sc, _)| *sc == c) {
+                Some((_, idxs)) => {
+                    if !idxs.contains(&t) {
+                        idxs.push(t);
+                    }
+                }
+                None => seen.push((c, vec![t])),
+            }
+        }
+    }
+    seen.retain(|(_, idxs)| idxs.len() > 1);
+    seen
+}
+
+/// Largest color count for which `tag_distance` searches every assignment exactly.
+/// The search is O(n!) in the worst case (7! = 5040, pruned in practice); larger tags use greedy matching.
+pub const TAG_DISTANCE_EXACT_MAX: usize = 7;
+
+/// Mean ΔE between two tags' colors under the best one-to-one matching, ignoring wedge order.
+/// Tags of different sizes only match as many colors as the smaller one has.
+pub fn tag_distance(a: &[Lab], b: &[Lab]) -> f32 {
+    let (a, b) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    let n = a.len();
+    if n == 0 {
+        return 0.0;
+    }
+    let m = b.len();
+    let cost: Vec<f32> = a.iter().flat_map(|&x| b.iter().map(move |&y| delta_e(x, y))).collect();
+
+    if m <= TAG_DISTANCE_EXACT_MAX {
+        // Branch and bound over assignments of a's colors to distinct b colors
+        fn search(cost: &[f32], m: usize, row: usize, n: usize, used: u32, partial: f32, best: &mut f32) {
+            if partial >= *best {
+                return;
+            }
+            if row == n {
+                *best = partial;
+                return;
+            }
+            for col in 0..m {
+                if used & (1 << col) == 0 {
+                    search(cost, m, row + 1, n, used | (1 << col), partial + cost[row * m + col], best);
+                }
+            }
+        }
+        let mut best = f32::INFINITY;
+        search(&cost, m, 0, n, 0, 0.0, &mut best);
+        best / n as f32
+    } else {
+        // Greedy: repeatedly take the closest remaining pair
+        let mut pairs: Vec<(f32, usize, usize)> = (0..n)
+            .flat_map(|i| (0..m).map(move |j| (i, j)))
+            .map(|(i, j)| (cost[i * m + j], i, j))
+            .collect();
+        pairs.sort_by(|x, y| x.0.partial_cmp(&y.0).unwrap_or(std::cmp::Ordering::Equal));
+        let mut used_a = vec![false; n];
+        let mut used_b = vec![false; m];
+        let mut sum = 0.0;
+        for (d, i, j) in pairs {
+            if !used_a[i] && !used_b[j] {
+                used_a[i] = true;
+                used_b[j] = true;
+                sum += d;
+            }
+        }
+        sum / n as f32
+    }
+}
+
+/// Tag pairs (0-based, i < j) whose `tag_distance` in `space`, lightness weighted, is below
+/// `min_distance`, closest first
+pub fn find_similar_tags(tags: &[Vec<Rgb<u8>>], min_distance: f32, space: ColorSpace, lightness_weight: f32) -> Vec<(usize, usize, f32)> {
+    let labs: Vec<Vec<Lab>> = tags
+        .iter()
+        .map(|t| t.iter().map(|&c| space.weighted_coords(c, lightness_weight)).collect())
+        .collect();
+    let mut pairs = Vec::new();
+    for i in 0..labs.len() {
+        for j in (i + 1)..labs.len() {
+            let d = tag_distance(&labs[i], &labs[j]);
+            if d < min_distance {
+                pairs.push((i, j, d));
+            }
+        }
+    }
+    pairs.sort_by(|x, y| x.2.partial_cmp(&y.2).unwrap_or(std::cmp::Ordering::Equal));
+    pairs
+}
+
+/// Approximate CIE Lab (D65) values for a set of common Pantone solid coated colors,
+/// derived from their published sRGB equivalents. This is a convenience lookup for
+/// print buyers, not a licensed Pantone match: coated swatches on press can differ by
+/// several ΔE, so always confirm against a physical guide.
+const PANTONE_COATED: &[(&str, f32, f32, f32)] = &[
+    ("PANTONE Yellow C", 88.3, -5.4, 88.1),
+    ("PANTONE Yellow 012 C", 86.9, -1.9, 87.1),
+    ("PANTONE 109 C", 85.4, 1.1, 86.1),
+    ("PANTONE 116 C", 84.4, 3.2, 85.4),
+    ("PANTONE 123 C", 83.1, 6.9, 77.3),
+    ("PANTONE 151 C", 67.5, 41.8, 74.3),
+    ("PANTONE 165 C", 62.6, 54.9, 64.5),
+    ("PANTONE 1505 C", 62.8, 53.7, 71.6),
+    ("PANTONE Orange 021 C", 58.8, 63.6, 69.4),
+    ("PANTONE Warm Red C", 56.4, 68.0, 46.7),
+    ("PANTONE Red 032 C", 53.1, 69.8, 39.2),
+    ("PANTONE 185 C", 47.8, 74.2, 44.7),
+    ("PANTONE 186 C", 42.5, 65.9, 35.7),
+    ("PANTONE 485 C", 47.7, 65.4, 51.4),
+    ("PANTONE 199 C", 44.8, 70.8, 36.6),
+    ("PANTONE Rubine Red C", 44.0, 71.0, 11.8),
+    ("PANTONE Rhodamine Red C", 49.8, 80.4, -18.5),
+    ("PANTONE Purple C", 46.6, 71.4, -44.7),
+    ("PANTONE 267 C", 30.1, 49.8, -55.5),
+    ("PANTONE 2685 C", 15.1, 45.7, -52.1),
+    ("PANTONE Violet C", 22.0, 56.3, -65.1),
+    ("PANTONE Blue 072 C", 19.0, 54.0, -74.0),
+    ("PANTONE Reflex Blue C", 17.0, 42.0, -63.6),
+    ("PANTONE 2728 C", 34.2, 29.0, -65.7),
+    ("PANTONE 286 C", 26.5, 31.7, -62.2),
+    ("PANTONE 287 C", 23.2, 23.5, -52.2),
+    ("PANTONE 293 C", 29.5, 26.9, -60.2),
+    ("PANTONE 300 C", 40.4, 12.4, -53.9),
+    ("PANTONE 3005 C", 48.7, 2.9, -49.7),
+    ("PANTONE Process Blue C", 53.0, -4.8, -44.0),
+    ("PANTONE 306 C", 68.4, -22.5, -33.2),
+    ("PANTONE Green C", 62.3, -46.7, 9.7),
+    ("PANTONE 354 C", 63.2, -61.6, 46.0),
+    ("PANTONE 355 C", 54.1, -54.0, 38.9),
+    ("PANTONE 347 C", 55.6, -53.7, 35.2),
+    ("PANTONE 368 C", 70.1, -44.9, 65.2),
+    ("PANTONE 375 C", 79.1, -44.8, 77.9),
+    ("PANTONE Cool Gray 5 C", 72.8, -0.7, -0.2),
+    ("PANTONE Cool Gray 11 C", 36.4, -0.3, -2.7),
+    ("PANTONE Black C", 16.9, 1.2, 2.6),
+];
+
+/// Closest entry of the built-in Pantone table to `lab` and its CIE76 ΔE (an approximation,
+/// see `PANTONE_COATED`)
+pub fn nearest_pantone(lab: Lab) -> (&'static str, f32) {
+    PANTONE_COATED
+        .iter()
+        .map(|&(name, l, a, b)| (name, delta_e(lab, Lab::new(l, a, b))))
+        .min_by(|x, y| x.1.total_cmp(&y.1))
+        .expect("Pantone table is not empty")
+}
+
 /// Compute pairwise distance matrix for Lab colors
 pub fn pairwise_delta_matrix(labs: &[Lab]) -> Vec<f32> {
     let n = labs.len();
@@ -130,6 +655,23 @@ pub fn pairwise_delta_matrix(labs: &[Lab]) -> Vec<f32> {
     dm
 }
 
+/// Bucket the upper triangle of an `n`×`n` distance matrix into `bins` equal
+/// bins spanning 0..=max. Returns the bin width and the per-bin counts.
+pub fn delta_histogram(dm: &[f32], n: usize, bins: usize) -> (f32, Vec<u32>) {
+    let mut counts = vec![0u32; bins.max(1)];
+    let max = (0..n)
+        .flat_map(|i| ((i + 1)..n).map(move |j| dm[i * n + j]))
+        .fold(0.0f32, f32::max);
+    let width = if max > 0.0 { max / counts.len() as f32 } else { 1.0 };
+    for i in 0..n {
+        for j in (i + 1)..n {
+            let b = ((dm[i * n + j] / width) as usize).min(counts.len() - 1);
+            counts[b] += 1;
+        }
+    }
+    (width, counts)
+}
+
 /// Find minimum distance within a group using the distance matrix
 pub fn group_min(dm: &[f32], n: usize, group: &[usize]) -> f32 {
     let mut min_d = f32::INFINITY;
@@ -144,21 +686,114 @@ pub fn group_min(dm: &[f32], n: usize, group: &[usize]) -> f32 {
     min_d
 }
 
+/// How each tag's colors are ordered around the wedges after grouping
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WedgeOrder {
+    /// Keep the order produced by grouping
+    None,
+    /// Alternate bright and dark (with one seam for odd side counts)
+    #[default]
+    BrightDark,
+    /// Ascending Lab hue angle, so the wedges form a color wheel
+    HueWheel,
+    /// The cyclic order with the largest minimum ΔE between neighboring wedges
+    MaxAdjacent,
+}
+
+impl WedgeOrder {
+    pub const ALL: [WedgeOrder; 4] = [WedgeOrder::None, WedgeOrder::BrightDark, WedgeOrder::HueWheel, WedgeOrder::MaxAdjacent];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            WedgeOrder::None => "None",
+            WedgeOrder::BrightDark => "Bright/dark",
+            WedgeOrder::HueWheel => "Hue wheel",
+            WedgeOrder::MaxAdjacent => "Max adjacent ΔE",
+        }
+    }
+}
+
+/// Order in which tags are listed in the grid (and, optionally, numbered when saved)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TagSort {
+    /// The order the tags were generated in
+    #[default]
+    Generation,
+    /// Ascending Lab hue angle of each tag's mean color
+    Hue,
+    /// Ascending Lab lightness of each tag's mean color, dark first
+    Lightness,
+}
+
+impl TagSort {
+    pub const ALL: [TagSort; 3] = [TagSort::Generation, TagSort::Hue, TagSort::Lightness];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TagSort::Generation => "Generation",
+            TagSort::Hue => "Mean hue",
+            TagSort::Lightness => "Mean lightness",
+        }
+    }
+
+    /// Tag indices in this order. The sort is stable, so ties keep generation order.
+    pub fn order(self, tags: &[Vec<Rgb<u8>>]) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..tags.len()).collect();
+        let key = |tag: &[Rgb<u8>]| {
+            let mean = srgb_u8_to_lab(signature_color(tag));
+            match self {
+                TagSort::Generation => 0.0,
+                TagSort::Hue => lab_hue(mean),
+                TagSort::Lightness => mean.l,
+            }
+        };
+        if self != TagSort::Generation {
+            let keys: Vec<f32> = tags.iter().map(|t| key(t)).collect();
+            order.sort_by(|&i, &j| keys[i].total_cmp(&keys[j]));
+        }
+        order
+    }
+}
+
+/// Lab hue angle `atan2(b, a)` in radians, normalized to [0, 2π)
+pub fn lab_hue(lab: Lab) -> f32 {
+    lab.b.atan2(lab.a).rem_euclid(std::f32::consts::TAU)
+}
+
+/// Reorder colors by ascending Lab hue angle, starting at the +a (reddish) axis
+pub fn reorder_by_hue(colors: &mut [Rgb<u8>]) {
+    colors.sort_by(|&x, &y| {
+        lab_hue(srgb_u8_to_lab(x))
+            .partial_cmp(&lab_hue(srgb_u8_to_lab(y)))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
+
+/// Colors sorted brightest first by Lab L. Ties are broken by larger a*, then larger b*,
+/// then larger RGB bytes, so the order depends only on the set of colors, never on their
+/// input order.
+fn sorted_brightest_first(colors: &[Rgb<u8>]) -> Vec<(Rgb<u8>, Lab)> {
+    let mut with_l: Vec<(Rgb<u8>, Lab)> = colors.iter().map(|&c| (c, srgb_u8_to_lab(c))).collect();
+    with_l.sort_by(|(x, lx), (y, ly)| {
+        ly.l.total_cmp(&lx.l)
+            .then(ly.a.total_cmp(&lx.a))
+            .then(ly.b.total_cmp(&lx.b))
+            .then(y.0.cmp(&x.0))
+    });
+    with_l
+}
+
 /// Reorder colors to alternate bright and dark for maximum adjacent contrast
 pub fn reorder_bright_dark_alternating(colors: &mut Vec<Rgb<u8>>) {
     let n = colors.len();
-    if n < 2 || n % 2 != 0 {
+    if n < 2 || !n.is_multiple_of(2) {
         return;
     }
     
-    let mut with_l: Vec<(Rgb<u8>, f32)> = colors
-        .iter()
-        .copied()
-        .map(|c| (c, srgb_u8_to_lab(c).l))
-        .collect();
-    
-    // Sort by brightness (Lab L) descending: brightest first
-    with_l.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    // Brightest first, in a total order (see `sorted_brightest_first`)
+    let with_l = sorted_brightest_first(colors);
     
     let half = n / 2;
     let brights: Vec<Rgb<u8>> = with_l[..half].iter().map(|(c, _)| *c).collect();
@@ -171,3 +806,171 @@ pub fn reorder_bright_dark_alternating(colors: &mut Vec<Rgb<u8>>) {
     }
     *colors = reordered;
 }
+
+/// Lab lightness range (max L minus min L) of a tag's colors
+pub fn lightness_spread(colors: &[Rgb<u8>]) -> f32 {
+    let (lo, hi) = colors
+        .iter()
+        .map(|&c| srgb_u8_to_lab(c).l)
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), l| (lo.min(l), hi.max(l)));
+    if hi >= lo { hi - lo } else { 0.0 }
+}
+
+/// Smallest lightness difference between neighbors around the ring (last wraps to first)
+pub fn min_adjacent_delta_l(colors: &[Rgb<u8>]) -> f32 {
+    let n = colors.len();
+    if n < 2 {
+        return 0.0;
+    }
+    let l: Vec<f32> = colors.iter().map(|&c| srgb_u8_to_lab(c).l).collect();
+    (0..n)
+        .map(|i| (l[i] - l[(i + 1) % n]).abs())
+        .fold(f32::INFINITY, f32::min)
+}
+
+/// Smallest lightness-weighted ΔE in `space` between any two colors of one tag
+/// (infinite below two colors)
+pub fn min_pairwise_delta_e(colors: &[Rgb<u8>], space: ColorSpace, lightness_weight: f32) -> f32 {
+    let coords: Vec<Lab> = colors.iter().map(|&c| space.weighted_coords(c, lightness_weight)).collect();
+    let n = coords.len();
+    (0..n)
+        .flat_map(|i| ((i + 1)..n).map(move |j| (i, j)))
+        .map(|(i, j)| delta_e(coords[i], coords[j]))
+        .fold(f32::INFINITY, f32::min)
+}
+
+/// Smallest ΔE in `space` between neighbors around the ring (last wraps to first)
+pub fn min_adjacent_delta_e(colors: &[Rgb<u8>], space: ColorSpace) -> f32 {
+    let n = colors.len();
+    if n < 2 {
+        return 0.0;
+    }
+    let coords: Vec<Lab> = colors.iter().map(|&c| space.coords(c)).collect();
+    (0..n)
+        .map(|i| delta_e(coords[i], coords[(i + 1) % n]))
+        .fold(f32::INFINITY, f32::min)
+}
+
+/// Largest side count `reorder_max_adjacent` solves exactly ((n-1)! orders per tag)
+pub const MAX_ADJACENT_EXACT_SIDES: usize = 8;
+
+/// Reorder colors so the smallest ΔE between neighboring wedges is as large as possible.
+/// Up to `MAX_ADJACENT_EXACT_SIDES` colors every cyclic order is tried (the first color stays
+/// put, since rotations are equivalent). Larger tags start from a greedy chain, each wedge
+/// followed by the remaining color farthest from it, from every starting color, and are then
+/// improved by segment reversals. The original order is kept unless beaten.
+pub fn reorder_max_adjacent(colors: &mut [Rgb<u8>], space: ColorSpace) {
+    let n = colors.len();
+    if n < 3 {
+        return;
+    }
+    let coords: Vec<Lab> = colors.iter().map(|&c| space.coords(c)).collect();
+    let ring_min = |order: &[usize]| {
+        (0..n)
+            .map(|i| delta_e(coords[order[i]], coords[order[(i + 1) % n]]))
+            .fold(f32::INFINITY, f32::min)
+    };
+
+    let mut best: Vec<usize> = (0..n).collect();
+    let mut best_min = ring_min(&best);
+    if n <= MAX_ADJACENT_EXACT_SIDES {
+        // Heap's algorithm over positions 1..n
+        let mut order = best.clone();
+        let mut c = vec![0usize; n];
+        let mut i = 1;
+        while i < n - 1 {
+            if c[i] < i {
+                if i.is_multiple_of(2) {
+                    order.swap(1, i + 1);
+                } else {
+                    order.swap(c[i] + 1, i + 1);
+                }
+                let m = ring_min(&order);
+                if m > best_min {
+                    best_min = m;
+                    best.clone_from(&order);
+                }
+                c[i] += 1;
+                i = 1;
+            } else {
+                c[i] = 0;
+                i += 1;
+            }
+        }
+    } else {
+        // Neighbor ΔEs smallest first: comparing these lexicographically also rewards
+        // raising the second-weakest edge when the weakest cannot move
+        let ring_edges = |order: &[usize]| {
+            let mut e: Vec<f32> = (0..n).map(|i| delta_e(coords[order[i]], coords[order[(i + 1) % n]])).collect();
+            e.sort_by(f32::total_cmp);
+            e
+        };
+        let better = |a: &[f32], b: &[f32]| a.iter().zip(b).find(|(x, y)| x != y).is_some_and(|(x, y)| x > y);
+        let mut best_edges = ring_edges(&best);
+        for start in 0..n {
+            let mut order = vec![start];
+            let mut left: Vec<usize> = (0..n).filter(|&k| k != start).collect();
+            while !left.is_empty() {
+                let last = coords[*order.last().unwrap()];
+                let (k, _) = left
+                    .iter()
+                    .enumerate()
+                    .max_by(|(_, &a), (_, &b)| delta_e(last, coords[a]).total_cmp(&delta_e(last, coords[b])))
+                    .unwrap();
+                order.push(left.remove(k));
+            }
+            let mut edges = ring_edges(&order);
+            let mut improved = true;
+            while improved {
+                improved = false;
+                for i in 1..n - 1 {
+                    for j in i + 1..n {
+                        order[i..=j].reverse();
+                        let e = ring_edges(&order);
+                        if better(&e, &edges) {
+                            edges = e;
+                            improved = true;
+                        } else {
+                            order[i..=j].reverse();
+                        }
+                    }
+                }
+            }
+            if better(&edges, &best_edges) {
+                best_edges = edges;
+                best = order;
+            }
+        }
+    }
+    let original = colors.to_vec();
+    for (slot, &k) in colors.iter_mut().zip(&best) {
+        *slot = original[k];
+    }
+}
+
+/// Odd-count counterpart of `reorder_bright_dark_alternating`. A perfect alternation is
+/// impossible around an odd ring, so the brighter half (plus the middle color) is interleaved
+/// with the darker half, leaving one bright-bright seam between the brightest color and the
+/// dimmest of the bright ones. The original order is kept if it already has a larger minimum
+/// adjacent |ΔL|.
+pub fn reorder_bright_dark_odd(colors: &mut Vec<Rgb<u8>>) {
+    let n = colors.len();
+    if n < 3 || n.is_multiple_of(2) {
+        return;
+    }
+
+    let with_l = sorted_brightest_first(colors);
+
+    let brights = n.div_ceil(2);
+    let mut reordered: Vec<Rgb<u8>> = Vec::with_capacity(n);
+    for i in 0..brights {
+        reordered.push(with_l[i].0);
+        if brights + i < n {
+            reordered.push(with_l[brights + i].0);
+        }
+    }
+
+    if min_adjacent_delta_l(&reordered) > min_adjacent_delta_l(colors) {
+        *colors = reordered;
+    }
+}
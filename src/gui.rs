@@ -2,14 +2,25 @@ use eframe::egui::{self, ColorImage, Context, TextureHandle, TextureOptions};
 use image::{DynamicImage, Rgb};
 use image::imageops::FilterType;
 use palette::Lab;
-use std::time::{Duration, Instant};
-use std::sync::mpsc;
-use std::thread;
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+// `std::time::Instant` panics in the browser; this is the same type on native builds
+use web_time::{Duration, Instant};
 use rayon::prelude::*;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 
-use crate::color::{candidate_srgb_grid, srgb_u8_to_lab, compute_max_threshold_and_colors_from_pool, reorder_bright_dark_alternating};
-use crate::render::{group_colors_into_groups_monte_carlo, draw_marker_polygon};
-use crate::io::{save_all, save_all_together};
+use polycue::color::{ColorSpace, PoolSource, ThresholdSearch, chroma_order, delta_e, distinguishing_colors, merge_near_duplicates, find_reused_colors, find_similar_tags, lightness_spread, min_pairwise_delta_e, palette_from_image, srgb_u8_to_lab, compute_max_threshold_and_colors_from_pool, delta_histogram, pairwise_delta_matrix, pick_distinct_strict, reorder_bright_dark_alternating, reorder_bright_dark_odd, reorder_by_hue, reorder_max_adjacent, snap_to_grid, TagSort, WedgeOrder};
+use polycue::render::{group_colors_into_groups_monte_carlo, downscale_linear, draw_marker_polygon, CenterShape, MarkerShape, MarkerStyle};
+use polycue::io::{append_profile_row, create_timestamped_dir, ensure_out_dir, save_all, save_ase, save_delta_heatmap, save_gpl, save_legend, save_all_to_dir, save_all_together, save_distance_chart, build_combined_image, resume_save_to_dir, scan_output, load_palette, reveal_in_file_manager, save_atlas, save_rotating_gif, save_single_image, save_wedge_spec, verify_output, GenerationSettings, ImageFormat, ManifestFormat, PALETTE_EXTENSIONS, ProfileRow, SaveOptions, SAVE_CANCELLED};
+#[cfg(not(target_arch = "wasm32"))]
+use polycue::io::copy_image_to_clipboard;
+#[cfg(target_arch = "wasm32")]
+use polycue::io::{download_all, download_all_together};
+use crate::cli::Cli;
 
 // ============================================================================
 // SLIDER CONFIGURATION - Easily adjust all UI control ranges and defaults here
@@ -25,8 +36,15 @@ impl SliderConfig {
     
     // Polygon Sides Slider
     pub const SIDES_MIN: i32 = 3;
-    pub const SIDES_MAX: i32 = 6;
+    pub const SIDES_MAX: i32 = 12;
     pub const SIDES_DEFAULT: usize = 4;
+    pub const MIXED_SIDES_DEFAULT: bool = false;
+    pub const SIDES_PATTERN_DEFAULT: &'static str = "3, 4";
+    pub const FIT_PALETTE_DEFAULT: bool = false;
+    // Sector count for the circular marker shape
+    pub const SECTORS_MIN: i32 = 3;
+    pub const SECTORS_MAX: i32 = 16;
+    pub const SECTORS_DEFAULT: usize = 8;
     
     // Center Dot Size Slider (percentage)
     pub const CENTER_DOT_MIN: f32 = 1.0;
@@ -34,11 +52,56 @@ impl SliderConfig {
     pub const CENTER_DOT_STEP: f64 = 1.0;
     pub const CENTER_DOT_DEFAULT: f32 = 35.0;
     
+    // Center Ring Inner Radius Slider (percentage of the center dot radius)
+    pub const CENTER_RING_ENABLED_DEFAULT: bool = false;
+    pub const CENTER_RING_INNER_MIN: f32 = 5.0;
+    pub const CENTER_RING_INNER_MAX: f32 = 95.0;
+    pub const CENTER_RING_INNER_DEFAULT: f32 = 50.0;
+    pub const CENTER_POLYGON_ENABLED_DEFAULT: bool = false;
+    pub const CENTER_POLYGON_SIDES_DEFAULT: usize = 4;
+    
     // Gradient Dot Size Slider (percentage)
     pub const GRADIENT_DOT_MIN: f32 = 1.0;
     pub const GRADIENT_DOT_MAX: f32 = 100.0;
     pub const GRADIENT_DOT_STEP: f64 = 1.0;
     pub const GRADIENT_DOT_DEFAULT: f32 = 35.0;
+    // Gradient dot falloff (Gaussian sigma as a fraction of the dot radius)
+    pub const GRADIENT_DOT_SIGMA_MIN: f32 = 0.1;
+    pub const GRADIENT_DOT_SIGMA_MAX: f32 = 2.0;
+    pub const GRADIENT_DOT_SIGMA_DEFAULT: f32 = 0.7;
+    
+    // Margin between polygon and canvas edge (fraction of the shorter side)
+    pub const MARGIN_MIN: f32 = 0.0;
+    pub const MARGIN_MAX: f32 = 0.4;
+    pub const MARGIN_DEFAULT: f32 = 0.08;
+    // Background gap along each spoke, in pixels at the save resolution
+    pub const WEDGE_GAP_MIN: u32 = 0;
+    pub const WEDGE_GAP_MAX: u32 = 40;
+    pub const WEDGE_GAP_DEFAULT: u32 = 0;
+    // Fewest pixels a wedge keeps on tags up to `MIN_WEDGE_CANVAS_PX`, so thin slivers do not drop out
+    pub const MIN_WEDGE_PX_MIN: u32 = 0;
+    pub const MIN_WEDGE_PX_MAX: u32 = 16;
+    pub const MIN_WEDGE_PX_DEFAULT: u32 = 1;
+    // Bounding ring width in pixels at the save resolution
+    pub const BOUNDING_RING_ENABLED_DEFAULT: bool = false;
+    pub const BOUNDING_RING_MIN: u32 = 1;
+    pub const BOUNDING_RING_MAX: u32 = 40;
+    pub const BOUNDING_RING_DEFAULT: u32 = 6;
+    // Signature band under the polygon edges, in pixels at the save size
+    pub const SIGNATURE_BAND_ENABLED_DEFAULT: bool = false;
+    pub const SIGNATURE_BAND_MIN: u32 = 1;
+    pub const SIGNATURE_BAND_MAX: u32 = 80;
+    pub const SIGNATURE_BAND_DEFAULT: u32 = 12;
+    pub const RANDOM_ROTATION_DEFAULT: bool = false;
+    pub const WEDGE_GRADIENT_DEFAULT: bool = false;
+    pub const DITHER_DEFAULT: bool = false;
+    pub const DITHER_LEVELS_MIN: u8 = 2;
+    pub const DITHER_LEVELS_MAX: u8 = 16;
+    pub const DITHER_LEVELS_DEFAULT: u8 = 4;
+    pub const WEDGE_ORDER_DEFAULT: WedgeOrder = WedgeOrder::BrightDark;
+    pub const TAG_SORT_DEFAULT: TagSort = TagSort::Generation;
+    pub const SORT_SAVES_DEFAULT: bool = false;
+    pub const ORIENTATION_WEDGE_DEFAULT: bool = false;
     
     // Tag Resolution Slider
     pub const RESOLUTION_MIN: f32 = 2.0;
@@ -56,16 +119,128 @@ impl SliderConfig {
     pub const SAVE_SIZE_MIN: u32 = 2;
     pub const SAVE_SIZE_MAX: u32 = 8192;
     pub const TILE_WIDTH_DEFAULT: f32 = 256.0;
+    // Tag inspector: window width, wedge and pool swatch sizes, pool list height
+    pub const INSPECTOR_WIDTH: f32 = 320.0;
+    pub const INSPECTOR_WEDGE_SWATCH: f32 = 32.0;
+    pub const INSPECTOR_POOL_SWATCH: f32 = 16.0;
+    pub const INSPECTOR_POOL_HEIGHT: f32 = 180.0;
+    // Full-resolution view: initial window size
+    pub const ZOOM_WINDOW_SIZE: f32 = 640.0;
+    pub const ZOOM_SCALE_MIN: f32 = 0.1;
+    pub const ZOOM_SCALE_MAX: f32 = 8.0;
+    // Seed comparison: initial window size and tile size
+    pub const COMPARE_WINDOW_SIZE: f32 = 720.0;
+    pub const COMPARE_TILE_SIZE: f32 = 96.0;
+    // Panel resizes smaller than this are ignored; larger ones regenerate once the drag settles
+    pub const RESIZE_HYSTERESIS_PX: f32 = 8.0;
+    pub const RESIZE_SETTLE_MS: u64 = 250;
+    // How often a paused blur worker checks whether the window is focused again
+    pub const BLUR_PAUSE_POLL_MS: u64 = 100;
+    // Distance-simulation blurs: number of levels and the strongest sigma (fraction of width)
+    pub const BLUR_LEVELS_MIN: usize = 1;
+    pub const BLUR_LEVELS_MAX: usize = 12;
+    pub const BLUR_LEVELS_DEFAULT: usize = 6;
+    pub const BLUR_MAX_SIGMA_MIN: f32 = 0.02;
+    pub const BLUR_MAX_SIGMA_MAX: f32 = 0.5;
+    pub const BLUR_MAX_SIGMA_DEFAULT: f32 = 0.30;
     pub const CENTER_DOT_ENABLED_DEFAULT: bool = true;
     pub const GRADIENT_DOT_ENABLED_DEFAULT: bool = true;
-    pub const PROFILING_DEFAULT: bool = true;
+    pub const PROFILING_DEFAULT: ProfileLevel = ProfileLevel::Summary;
+    // Worker threads for rendering and selection (0 lets rayon pick one per core)
+    pub const THREAD_COUNT_MAX: usize = 64;
+    pub const THREAD_COUNT_DEFAULT: usize = 0;
+    // How long a status message stays visible
+    pub const STATUS_SECONDS: f32 = 6.0;
     pub const DEFER_HIGH_RES_DEFAULT: bool = true;
+    pub const EXPORT_16BIT_DEFAULT: bool = false;
+    // Spacing between tiles of the combined sheet, in pixels
+    pub const GUTTER_MAX: u32 = 400;
+    pub const GUTTER_DEFAULT: u32 = 0;
+    pub const TILE_LABELS_DEFAULT: bool = false;
+    pub const SPIN_FRAMES_MIN: usize = 4;
+    pub const SPIN_FRAMES_MAX: usize = 120;
+    pub const SPIN_FRAMES_DEFAULT: usize = 36;
+    pub const SPIN_GIF_SIZE: u32 = 400;
+    pub const PRINT_DPI_MIN: f32 = 72.0;
+    pub const PRINT_DPI_MAX: f32 = 2400.0;
+    pub const PRINT_DPI_DEFAULT: f32 = 300.0;
+    pub const BLEED_MM_MAX: f32 = 20.0;
+    pub const BLEED_MM_DEFAULT: f32 = 0.0;
+    pub const TRIM_MARKS_DEFAULT: bool = true;
+    pub const REGISTRATION_DOTS_DEFAULT: bool = false;
+    pub const EMBED_METADATA_DEFAULT: bool = false;
+    pub const WEDGE_LAYERS_DEFAULT: bool = false;
+    pub const CATEGORY_FOLDERS_DEFAULT: bool = false;
+    // Category chip in the corner of a grid tile: text size and padding
+    pub const CATEGORY_CHIP_FONT: f32 = 10.0;
+    pub const CATEGORY_CHIP_PAD: f32 = 2.0;
+    pub const MULTI_SIZES_DEFAULT: &'static str = "256, 1024, 1600";
+    // Capacity gauge next to the Tags slider: width, and the fill fractions where it turns amber and red
+    pub const CAPACITY_GAUGE_WIDTH: f32 = 90.0;
+    pub const CAPACITY_TIGHT: f32 = 0.75;
+    pub const CAPACITY_FULL: f32 = 0.95;
+    pub const DELTA_HISTOGRAM_BINS: usize = 40;
+    pub const DELTA_HISTOGRAM_HEIGHT: f32 = 120.0;
+    pub const FIDUCIAL_BORDER_DEFAULT: bool = false;
+    pub const QUIET_ZONE_MIN: f32 = 0.0;
+    pub const QUIET_ZONE_MAX: f32 = 0.25;
+    pub const QUIET_ZONE_DEFAULT: f32 = 0.0;
 
     // Serial number overlay
     pub const SERIAL_NUMBERS_DEFAULT: bool = false;
     pub const SERIAL_H_ALIGN_DEFAULT: f32 = 0.9;
     pub const SERIAL_V_ALIGN_DEFAULT: f32 = 0.82;
     pub const SERIAL_BORDER_DEFAULT: bool = true;
+
+    // Tag distance below which two tags are flagged as confusable
+    pub const TAG_DISTANCE_MIN: f32 = 0.0;
+    pub const TAG_DISTANCE_MAX: f32 = 60.0;
+    pub const TAG_DISTANCE_DEFAULT: f32 = 20.0;
+
+    // ΔE threshold below which tag colors are reported as hard to tell apart
+    pub const MIN_USABLE_THRESHOLD_MIN: f32 = 0.0;
+    pub const MIN_USABLE_THRESHOLD_MAX: f32 = 60.0;
+    pub const MIN_USABLE_THRESHOLD_DEFAULT: f32 = 15.0;
+    pub const HOLD_THRESHOLD_FLOOR_DEFAULT: bool = false;
+
+    // Minimum Lab lightness range within a tag (0 disables the constraint)
+    pub const LIGHTNESS_SPREAD_MIN: f32 = 0.0;
+    pub const LIGHTNESS_SPREAD_MAX: f32 = 80.0;
+    pub const LIGHTNESS_SPREAD_DEFAULT: f32 = 0.0;
+
+    // Threshold binary search
+    pub const SEARCH_ITERATIONS_MIN: i32 = 4;
+    pub const SEARCH_ITERATIONS_MAX: i32 = 30;
+
+    // Image-sampled palette size (k-means clusters)
+    pub const IMAGE_PALETTE_K_MIN: i32 = 6;
+    pub const IMAGE_PALETTE_K_MAX: i32 = 128;
+    pub const IMAGE_PALETTE_K_DEFAULT: usize = 32;
+    // Imported colors closer than this ΔE are merged into one candidate (0 keeps all)
+    pub const POOL_MERGE_DELTA_MIN: f32 = 0.0;
+    pub const POOL_MERGE_DELTA_MAX: f32 = 10.0;
+    pub const POOL_MERGE_DELTA_DEFAULT: f32 = 2.0;
+    // Scale on the lightness term of ΔE for selection and grouping (1.0 is the plain metric)
+    pub const LIGHTNESS_WEIGHT_MIN: f32 = 0.1;
+    pub const LIGHTNESS_WEIGHT_MAX: f32 = 2.0;
+    pub const LIGHTNESS_WEIGHT_DEFAULT: f32 = 1.0;
+
+    // Output
+    pub const OUTPUT_DIR_DEFAULT: &'static str = "output";
+    pub const PROFILE_PATH_DEFAULT: &'static str = "output/profile.csv";
+    pub const IMAGE_FORMAT_DEFAULT: ImageFormat = ImageFormat::Png;
+    pub const MANIFEST_FORMAT_DEFAULT: ManifestFormat = ManifestFormat::Json;
+
+    // Candidate colors outside this Lab L window are dropped from the pool
+    pub const POOL_LIGHTNESS_MIN: f32 = 20.0;
+    pub const POOL_LIGHTNESS_MAX: f32 = 90.0;
+    // Monte Carlo swap attempts when grouping colors into tags
+    pub const GROUPING_ITERATIONS: usize = 2000;
+
+    // Color space for ΔE during selection and grouping
+    pub const COLOR_SPACE_DEFAULT: ColorSpace = ColorSpace::Lab;
+    pub const POOL_SOURCE_DEFAULT: PoolSource = PoolSource::FullGrid;
+    pub const SNAP_TO_GRID_DEFAULT: bool = false;
 }
 
 // ============================================================================
@@ -76,23 +251,119 @@ pub enum RegenKind {
     ImagesOnly,
 }
 
+/// How much timing output goes to the console
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ProfileLevel {
+    Off,
+    /// Full regenerations, pool rebuilds and saves, with their stage timings
+    Summary,
+    /// Also every preview rebuild, including the frequent images-only ones
+    Verbose,
+}
+
+impl ProfileLevel {
+    pub const ALL: [ProfileLevel; 3] = [ProfileLevel::Off, ProfileLevel::Summary, ProfileLevel::Verbose];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ProfileLevel::Off => "off",
+            ProfileLevel::Summary => "summary",
+            ProfileLevel::Verbose => "verbose",
+        }
+    }
+}
+
 pub struct AppState {
     pub count: usize,
     pub threshold: f32,
     pub sides: usize,
+    // Polygon, or a circle of `sector_count` sectors
+    pub shape: MarkerShape,
+    pub sector_count: usize,
+    // Polygon tags take their side counts round-robin from `sides_pattern` instead of `sides`
+    pub mixed_sides: bool,
+    pub sides_pattern_text: String,
+    pub sides_pattern: Vec<usize>,
+    // Polygon tags share out the whole pool, so side counts vary to use every color
+    pub fit_palette: bool,
     pub tags: Vec<Vec<Rgb<u8>>>,
     pub textures: Vec<TextureHandle>,
+    // Content hash of each preview texture, to skip re-rendering unchanged tiles
+    pub texture_keys: Vec<u64>,
     pub save_size: (u32, u32),
     pub high_res: Vec<DynamicImage>,
     pub preview_max_width: u32,
     pub columns: usize,
     pub center_dot: bool,
     pub center_dot_size_pct: f32,
+    pub center_dot_color: egui::Color32,
+    pub center_ring: bool,
+    pub center_ring_inner_pct: f32,
+    // Draw the center dot as a polygon of `center_polygon_sides` instead of a circle
+    pub center_polygon: bool,
+    pub center_polygon_sides: usize,
     pub gradient_dot: bool,
     pub gradient_dot_size_pct: f32,
+    pub gradient_dot_color: egui::Color32,
+    pub gradient_dot_sigma_pct: f32,
+    pub margin_pct: f32,
+    pub wedge_gap_px: u32,
+    pub min_wedge_px: u32,
+    // Thin ring just outside the polygon's vertices, for detectors that look for a circle first
+    pub bounding_ring: bool,
+    pub bounding_ring_px: u32,
+    pub bounding_ring_color: egui::Color32,
+    // Solid band outside the polygon edges: its width, and the fixed color used instead of
+    // each tag's mean color when `signature_band_auto` is off
+    pub signature_band: bool,
+    pub signature_band_px: u32,
+    pub signature_band_auto: bool,
+    pub signature_band_color: egui::Color32,
+    pub wedge_gradient: bool,
+    // Ordered dithering of wedge fills for devices with few printable levels per channel
+    pub dither: bool,
+    pub dither_levels: u8,
+    // Give each tag its own seeded rotation (kept within one wedge so wedge order is unchanged)
+    pub random_rotation: bool,
+    // Post-grouping ordering of each tag's wedges
+    pub wedge_order: WedgeOrder,
+    // Grid order of the tags, and whether saves number them in that order too
+    pub tag_sort: TagSort,
+    pub sort_saves: bool,
+    // Tag indices in `tag_sort` order, refreshed with the tag stats
+    pub tag_order: Vec<usize>,
+    // Reserve wedge 0 of every tag for `orientation_color`, so detectors can recover rotation
+    pub orientation_wedge: bool,
+    pub orientation_color: egui::Color32,
+    // Grouping penalizes tags whose Lab lightness range is below this
+    pub min_lightness_spread: f32,
+    // Tags (0-based) that still ended up below min_lightness_spread
+    pub flat_tags: Vec<usize>,
+    pub tag_rotations: Vec<f32>,
+    // Inventory category of each tag, kept by index across regenerations
+    pub tag_categories: Vec<Option<String>>,
+    // Black frame + corner anchors around each marker for standard fiducial pipelines
+    pub fiducial_border: bool,
+    // White margin around each tag as a fraction of its shorter side, white whatever the background
+    pub quiet_zone_pct: f32,
     
-    // Maximum possible count based on available colors
+    // Maximum possible count based on available colors, and the `selection_key` it was computed for
     pub max_possible_count: usize,
+    pub max_possible_count_key: u64,
+    // Estimated max ΔE per tag count, valid while the pool and search settings match the key
+    pub threshold_estimates: HashMap<usize, f32>,
+    pub threshold_estimates_key: u64,
+
+    // Colors that ended up in more than one tag (with 0-based tag indices)
+    pub reused_colors: Vec<(Rgb<u8>, Vec<usize>)>,
+    // Tag pairs (0-based) whose overall color match is closer than similar_tag_distance
+    pub similar_tags: Vec<(usize, usize, f32)>,
+    pub similar_tag_distance: f32,
+    // Warn when the threshold falls below this; optionally cap the count so it can't
+    pub min_usable_threshold: f32,
+    pub hold_threshold_floor: bool,
+    // Pairwise ΔE histogram over all selected colors: (bin width, counts)
+    pub delta_histogram: (f32, Vec<u32>),
     
     // Debounced regeneration
     pub pending_regen: Option<RegenKind>,
@@ -100,7 +371,25 @@ pub struct AppState {
     
     // Cached candidate pool to speed up full regenerations
     pub candidate_pool: Vec<Rgb<u8>>,
+    // Pool coordinates in `color_space`, lightness weighted (see `ColorSpace::weighted_coords`)
     pub candidate_labs: Vec<Lab>,
+    pub color_space: ColorSpace,
+    // Scale on ΔL in every selection-side ΔE; thresholds are in these weighted units
+    pub lightness_weight: f32,
+    // Built-in pool the candidates come from (unless sampled from an image or palette)
+    pub pool_source: PoolSource,
+    // Move selected colors to the nearest grid level per channel, keeping the originals
+    // (parallel to `tags`, empty when off) for the manifest
+    pub snap_to_grid: bool,
+    pub unsnapped_tags: Vec<Vec<Rgb<u8>>>,
+    // Pairwise ΔE matrix of the pool (exact upper bound for the threshold search)
+    pub candidate_dm: Vec<f32>,
+    // Threshold binary-search precision and bounds
+    pub threshold_search: ThresholdSearch,
+    // Number of k-means clusters when sampling the pool from an image
+    pub image_palette_k: usize,
+    // Near-duplicate merge distance for imported palettes and sampled images
+    pub pool_merge_delta: f32,
     
     // Right panel preview caches
     pub right_mono_textures: Vec<TextureHandle>,
@@ -112,12 +401,61 @@ pub struct AppState {
     
     // Track panel width for resize detection
     pub last_panel_width: f32,
+    // When the latest panel resize settles; pushed back while the splitter is dragged
+    pub resize_settle_at: Option<Instant>,
     
-    // Verbose timing logs toggle
-    pub profiling: bool,
+    // Console timing logs
+    pub profiling: ProfileLevel,
+    // Worker threads requested for `thread_pool` (0 = auto)
+    pub thread_count: usize,
+    // Dedicated pool the parallel render and selection loops run in, built up front
+    pub thread_pool: Arc<rayon::ThreadPool>,
+    // CSV file that receives one row of stage timings per profiled regenerate
+    pub profile_path: String,
     
     // If true, skip high-res render on interactive changes; only render on Save
     pub defer_high_res: bool,
+    // Save 16-bit-per-channel images when the format allows it
+    pub export_16bit: bool,
+    // Spacing between tiles in "Save All Together"
+    pub gutter_px: u32,
+    // Number each tile of the combined sheet in a strip underneath
+    pub tile_labels: bool,
+    // Tag (1-based) for "Save tag…" and "Spin GIF", and the GIF's frame count
+    pub export_tag: usize,
+    pub spin_frames: usize,
+    // Tag (0-based) open in the inspector, and its selected wedge
+    pub inspected_tag: Option<usize>,
+    pub inspected_wedge: usize,
+    // Hand-edited tags by index; regeneration keeps them until they are unpinned
+    pub pinned_tags: HashMap<usize, Vec<Rgb<u8>>>,
+    // Tiles picked with Ctrl/Shift-click for bulk operations, and where a Shift range starts
+    pub selected_tags: HashSet<usize>,
+    pub selection_anchor: Option<usize>,
+    // Tag (0-based) shown in the scaled and blurred right-panel previews; follows the inspector
+    pub preview_subject: usize,
+    // Tag (0-based) open in the full-resolution view, its texture with the render key it
+    // was made for, and the display scale (1 = one image pixel per screen pixel)
+    pub zoom_tag: Option<usize>,
+    pub zoom_texture: Option<(u64, TextureHandle)>,
+    pub zoom_scale: f32,
+    // Tags from another seed shown beside the current ones, and the seed typed for the next run
+    pub seed_comparison: Option<SeedComparison>,
+    pub compare_seed_input: String,
+    // Print resolution, bleed around the combined sheet, and crop marks in the bleed
+    pub print_dpi: f32,
+    pub bleed_mm: f32,
+    pub trim_marks: bool,
+    // Corner reference dots on the combined sheet
+    pub registration_dots: bool,
+    // Embed each tag's manifest data in its PNG
+    pub embed_metadata: bool,
+    // Also save every wedge as its own transparent PNG
+    pub wedge_layers: bool,
+    // Save All Separate puts categorized tags in per-category subfolders
+    pub category_folders: bool,
+    // Comma-separated widths for "Multi-export"
+    pub multi_sizes: String,
     
     // Background color for tag rendering
     pub bg_color: egui::Color32,
@@ -129,9 +467,477 @@ pub struct AppState {
     pub serial_color: egui::Color32,
     pub serial_border: bool,
 
-    // Async blur job
+    // Seed for color selection and grouping; Regenerate picks a new one
+    pub seed: u64,
+    // Fixed ΔE threshold; None searches for the maximum feasible one
+    pub threshold_override: Option<f32>,
+
+    // Output location and image format for saves
+    pub output_dir: String,
+    pub image_format: ImageFormat,
+    pub manifest_format: ManifestFormat,
+
+    // Most recent status message (text, is_error, when it was set)
+    pub status: Option<(String, bool, Instant)>,
+
+    // Blur levels shown in the distance-simulation panel
+    pub blur_level_count: usize,
+    pub blur_max_sigma: f32,
+    // Async blur job, and the preview it was started for (display width, sigmas, source image)
     pub blur_job_id: u64,
+    pub blur_source: Option<(u32, Vec<f32>, image::RgbImage)>,
     pub blurred_rx: Option<mpsc::Receiver<(u64, usize, image::RgbaImage)>>,
+    // Set while the window is unfocused; the blur worker waits between levels
+    pub blur_paused: Arc<AtomicBool>,
+
+    // Background color selection and grouping for a full regeneration, if one is running
+    pub regen_job_id: u64,
+    pub regen_job: Option<RegenJob>,
+    // Background high-res render and save, if one is running
+    pub save_job: Option<SaveJob>,
+    // Folder written by the most recent successful Save All Separate / Together
+    pub last_save_dir: Option<String>,
+    // Resume save: rewrite existing tags whose size differs from the save size, and the
+    // folder, tags to write and status note of the resume about to start
+    pub resume_overwrite_mismatched: bool,
+    pub resume_plan: Option<(String, Vec<usize>, String)>,
+    // System clipboard, opened on first copy and kept so the copied sheet stays available
+    #[cfg(not(target_arch = "wasm32"))]
+    pub clipboard: Option<arboard::Clipboard>,
+}
+
+/// Which save a background job performs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveKind {
+    Separate,
+    Together,
+    /// Separate files once per size in `multi_sizes`, each in its own subfolder
+    MultiSize,
+    /// The missing separate files of an earlier save, in `resume_plan`'s folder
+    Resume,
+    /// Separate files for the tags in `selected_tags` only, numbered from 1
+    Selected,
+}
+
+/// Messages from a background save to the UI
+pub enum SaveMessage {
+    /// Fraction of the work (rendering and writing) done so far
+    Progress(f32),
+    /// Output folder, or the error text
+    Finished(Result<String, String>),
+}
+
+/// A running background save: progress channel plus a flag that stops remaining work
+pub struct SaveJob {
+    pub kind: SaveKind,
+    pub count: usize,
+    // Appended to the status line when the save succeeds
+    pub note: String,
+    pub progress: f32,
+    pub rx: mpsc::Receiver<SaveMessage>,
+    pub cancel: Arc<AtomicBool>,
+}
+
+/// A full regeneration running on a worker thread
+pub struct RegenJob {
+    pub id: u64,
+    pub rx: mpsc::Receiver<(u64, SelectedTags)>,
+    pub cancel: Arc<AtomicBool>,
+    pub started: Instant,
+}
+
+/// Tags selected under another seed with the current settings, for side-by-side comparison
+pub struct SeedComparison {
+    pub seed: u64,
+    // Worker result channel, until the result arrives
+    pub rx: Option<mpsc::Receiver<SelectedTags>>,
+    pub cancel: Arc<AtomicBool>,
+    pub selected: Option<SelectedTags>,
+    /// `selected`'s tags as drawn: wedge order, grid snapping and orientation wedge applied
+    pub tags: Vec<Vec<Rgb<u8>>>,
+    pub textures: Vec<TextureHandle>,
+}
+
+/// Everything needed to select colors and group them into tags, detached from
+/// `AppState` so the work can move to another thread
+#[derive(Debug, Clone)]
+pub struct TagSelection {
+    pub pool: Vec<Rgb<u8>>,
+    pub labs: Vec<Lab>,
+    pub dm: Vec<f32>,
+    pub color_space: ColorSpace,
+    pub lightness_weight: f32,
+    /// Distinct colors for each requested tag
+    pub group_sizes: Vec<usize>,
+    pub threshold_override: Option<f32>,
+    pub search: ThresholdSearch,
+    pub min_lightness_spread: f32,
+    pub seed: u64,
+    pub profiling: bool,
+}
+
+/// Tags chosen by `TagSelection::run`, before wedge ordering
+#[derive(Debug, Clone, Default)]
+pub struct SelectedTags {
+    pub tags: Vec<Vec<Rgb<u8>>>,
+    pub threshold: f32,
+    /// Tag count actually filled, at most the requested one
+    pub count: usize,
+    /// Why no tags could be made, if none were
+    pub shortfall: Option<String>,
+    pub color_select_ms: f64,
+    pub grouping_ms: f64,
+}
+
+impl TagSelection {
+    /// Pick colors at the highest feasible threshold (or the fixed one), then group them.
+    /// `cancelled` is checked between the stages; once it returns true, gives up with None.
+    pub fn run(&self, cancelled: &dyn Fn() -> bool) -> Option<SelectedTags> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let needed: usize = self.group_sizes.iter().sum();
+
+        let t0 = Instant::now();
+        let (threshold, mut colors) = match self.threshold_override {
+            Some(thr) => {
+                let order = if self.search.prefer_vivid {
+                    chroma_order(&self.labs)
+                } else {
+                    let mut order: Vec<usize> = (0..self.pool.len()).collect();
+                    order.shuffle(&mut rng);
+                    order
+                };
+                let picked = pick_distinct_strict(&self.labs, &order, thr, needed);
+                (thr, picked.into_iter().map(|i| self.pool[i]).collect::<Vec<_>>())
+            }
+            None => compute_max_threshold_and_colors_from_pool(
+                &self.pool,
+                &self.labs,
+                needed,
+                &self.search,
+                Some(&self.dm),
+                &mut rng,
+            ),
+        };
+        let color_select_ms = t0.elapsed().as_secs_f64()*1000.0;
+        if self.profiling { println!("[profile] \tcolor select: {:.2} ms (needed={})", color_select_ms, needed); }
+        if cancelled() {
+            return None;
+        }
+
+        // If not enough colors, reduce count to what's possible
+        let mut used = 0;
+        let fillable = self.group_sizes.iter().take_while(|&&s| { used += s; used <= colors.len() }).count();
+        let count = fillable.max(1);
+        let group_sizes = &self.group_sizes[..count];
+        colors.truncate(group_sizes.iter().sum());
+
+        let labs: Vec<Lab> = colors.iter().map(|&c| self.color_space.weighted_coords(c, self.lightness_weight)).collect();
+        let t1 = Instant::now();
+        let (tags, shortfall) = if colors.len() < group_sizes[0] {
+            // The pool cannot fill even one tag (e.g. many sectors from a small image palette)
+            (Vec::new(), Some(format!("Only {} usable colors for {} wedges per tag; enlarge the candidate pool", colors.len(), group_sizes[0])))
+        } else {
+            (group_colors_into_groups_monte_carlo(colors, labs, group_sizes, SliderConfig::GROUPING_ITERATIONS, self.min_lightness_spread, &mut rng), None)
+        };
+        let grouping_ms = t1.elapsed().as_secs_f64()*1000.0;
+        if self.profiling { println!("[profile] \tgrouping: {:.2} ms (tags={}, wedges={})", grouping_ms, count, group_sizes[0]); }
+        if cancelled() {
+            return None;
+        }
+        Some(SelectedTags { tags, threshold, count, shortfall, color_select_ms, grouping_ms })
+    }
+}
+
+/// Everything needed to render the full-resolution tags, detached from `AppState`
+/// so the work can move to another thread
+#[derive(Debug, Clone)]
+pub struct HighResRender {
+    pub size: (u32, u32),
+    pub style: MarkerStyle,
+    pub serial_style: Option<(f32, f32, Rgb<u8>, bool)>,
+    pub rotations: Vec<f32>,
+    pub sixteen_bit: bool,
+}
+
+impl HighResRender {
+    /// Render every tag in parallel, each with as many sides as it has colors. `on_each`
+    /// runs after each tag; returning false stops the remaining work and yields None.
+    pub fn render(&self, tags: &[Vec<Rgb<u8>>], on_each: &(dyn Fn() -> bool + Sync)) -> Option<Vec<DynamicImage>> {
+        tags.par_iter()
+            .enumerate()
+            .map(|(i, colors)| {
+                let img = self.render_one(i, colors);
+                on_each().then_some(img)
+            })
+            .collect()
+    }
+
+    /// Render tag `index` (0-based) alone, with its serial number and rotation
+    pub fn render_one(&self, index: usize, colors: &[Rgb<u8>]) -> DynamicImage {
+        let (w, h) = self.size;
+        let serial = self.serial_style.map(|(ha, va, c, b)| (index + 1, ha, va, c, b));
+        let style = self.style.rotated(self.rotations.get(index).copied().unwrap_or(0.0));
+        let img = DynamicImage::ImageRgb8(draw_marker_polygon(w, h, colors.len(), colors, &style, serial));
+        if self.sixteen_bit { DynamicImage::ImageRgb16(img.into_rgb16()) } else { img }
+    }
+
+    /// Hash of everything `render_one(index, colors)` depends on, except the bit depth
+    pub fn key(&self, index: usize, colors: &[Rgb<u8>]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        colors.iter().map(|c| c.0).collect::<Vec<_>>().hash(&mut hasher);
+        self.size.hash(&mut hasher);
+        self.style.rotated(self.rotations.get(index).copied().unwrap_or(0.0)).hash(&mut hasher);
+        self.serial_style.map(|(ha, va, c, b)| (index + 1, ha.to_bits(), va.to_bits(), c.0, b)).hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Copy rendering at `width` pixels wide, keeping the aspect ratio and pixel-sized details
+    pub fn resized(&self, width: u32) -> HighResRender {
+        let height = ((width as u64 * self.size.1 as u64) / self.size.0.max(1) as u64).max(1) as u32;
+        HighResRender { size: (width, height), style: self.style.scaled_for(width, self.size.0), ..self.clone() }
+    }
+}
+
+/// Parse a comma-separated list of polygon side counts such as "3, 4, 4", kept in order
+pub fn parse_sides_pattern(s: &str) -> Result<Vec<usize>, String> {
+    let mut sides = Vec::new();
+    for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let v: usize = part.parse().map_err(|_| format!("`{}` is not a valid side count", part))?;
+        if !(SliderConfig::SIDES_MIN as usize..=SliderConfig::SIDES_MAX as usize).contains(&v) {
+            return Err(format!("{} is outside the allowed range {}..={}", v, SliderConfig::SIDES_MIN, SliderConfig::SIDES_MAX));
+        }
+        sides.push(v);
+    }
+    if sides.is_empty() {
+        return Err("no side counts given".into());
+    }
+    Ok(sides)
+}
+
+/// Side counts that share `colors` distinct colors among `count` tags as evenly as
+/// possible (the first `colors % count` tags get one more), each with `reserved` extra
+/// wedges and kept within the polygon side limits
+pub fn fit_palette_sides(colors: usize, count: usize, reserved: usize) -> Vec<usize> {
+    let count = count.max(1);
+    let (base, extra) = (colors / count, colors % count);
+    let (min, max) = (SliderConfig::SIDES_MIN as usize, SliderConfig::SIDES_MAX as usize);
+    (0..count).map(|i| (base + usize::from(i < extra) + reserved).clamp(min, max)).collect()
+}
+
+/// Parse a comma-separated list of widths such as "256, 1024, 1600", dropping repeats
+pub fn parse_size_list(s: &str) -> Result<Vec<u32>, String> {
+    let mut sizes = Vec::new();
+    for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let v: u32 = part.parse().map_err(|_| format!("`{}` is not a valid size", part))?;
+        if !(SliderConfig::SAVE_SIZE_MIN..=SliderConfig::SAVE_SIZE_MAX).contains(&v) {
+            return Err(format!("{} is outside the allowed range {}..={}", v, SliderConfig::SAVE_SIZE_MIN, SliderConfig::SAVE_SIZE_MAX));
+        }
+        if !sizes.contains(&v) {
+            sizes.push(v);
+        }
+    }
+    if sizes.is_empty() {
+        return Err("no sizes given".into());
+    }
+    Ok(sizes)
+}
+
+/// Run `job` on a new thread. In the browser it runs to completion on the calling thread
+/// instead, so its results are already waiting when the UI next polls for them.
+fn spawn_worker(job: impl FnOnce() + Send + 'static) {
+    #[cfg(not(target_arch = "wasm32"))]
+    std::thread::spawn(job);
+    #[cfg(target_arch = "wasm32")]
+    job();
+}
+
+/// Sizes, relative to the preview, of the scaled variants in the right panel and the distance chart
+pub const DISTANCE_SCALES: [f32; 18] = [
+    0.5, 0.4, 0.3, 0.2, 0.15, 0.14, 0.13, 0.12, 0.1,
+    0.09, 0.08, 0.07, 0.06, 0.05, 0.04, 0.03, 0.02, 0.01,
+];
+
+/// Wedge that holds the orientation color in orientation mode (the first, clockwise from the top)
+pub const ORIENTATION_WEDGE: usize = 0;
+
+/// Candidate colors, their coordinates and ΔE matrix, borrowed or filtered from the cache
+type SelectionPool<'a> = (Cow<'a, [Rgb<u8>]>, Cow<'a, [Lab]>, Cow<'a, [f32]>);
+
+/// The items at `indices`, in that order, skipping any past the end
+fn pick<T: Clone>(items: &[T], indices: &[usize]) -> Vec<T> {
+    indices.iter().filter_map(|&i| items.get(i).cloned()).collect()
+}
+
+/// Label with `category` in the bottom-left corner of a grid tile, on a color picked from
+/// the name so every tag of a category gets the same one
+fn draw_category_chip(painter: &egui::Painter, tile: egui::Rect, category: &str) {
+    let mut hasher = DefaultHasher::new();
+    category.hash(&mut hasher);
+    let hue = (hasher.finish() % 360) as f32 / 360.0;
+    let fill: egui::Color32 = egui::ecolor::Hsva::new(hue, 0.55, 0.75, 1.0).into();
+    let font = egui::FontId::proportional(SliderConfig::CATEGORY_CHIP_FONT);
+    let galley = painter.layout_no_wrap(category.to_string(), font, egui::Color32::BLACK);
+    let pad = SliderConfig::CATEGORY_CHIP_PAD;
+    let size = (galley.size() + egui::Vec2::splat(2.0 * pad)).min(tile.size());
+    let rect = egui::Rect::from_min_size(tile.left_bottom() - egui::vec2(0.0, size.y), size);
+    painter.with_clip_rect(rect).rect_filled(rect, 2.0, fill);
+    painter.with_clip_rect(rect).galley(rect.min + egui::Vec2::splat(pad), galley, egui::Color32::BLACK);
+}
+
+/// Remove item `i` of a per-tag list, if the list reaches that far
+fn remove_at<T>(items: &mut Vec<T>, i: usize) {
+    if i < items.len() {
+        items.remove(i);
+    }
+}
+
+/// Status suffix counting colors dropped by `merge_near_duplicates`
+fn merged_note(merged: usize) -> String {
+    if merged == 0 {
+        String::new()
+    } else {
+        format!(" ({} near-duplicates merged)", merged)
+    }
+}
+
+/// Rayon pool with `threads` workers (0 = one per core). Building it starts the threads,
+/// so the first render does not pay for spawning them.
+#[cfg(not(target_arch = "wasm32"))]
+fn build_thread_pool(threads: usize) -> Arc<rayon::ThreadPool> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .thread_name(|i| format!("polycue-worker-{}", i))
+        .build()
+        .expect("failed to start worker threads");
+    Arc::new(pool)
+}
+
+/// The browser has no threads to start, so there is one pool made of the page's own thread,
+/// and `threads` is ignored. A thread can only belong to one pool, hence the sharing.
+#[cfg(target_arch = "wasm32")]
+fn build_thread_pool(_threads: usize) -> Arc<rayon::ThreadPool> {
+    thread_local! {
+        static POOL: Arc<rayon::ThreadPool> = Arc::new(
+            rayon::ThreadPoolBuilder::new().num_threads(1).use_current_thread().build().expect("failed to set up the worker pool"),
+        );
+    }
+    POOL.with(Arc::clone)
+}
+
+/// Apply the wedge order to freshly grouped tags. Membership never changes.
+fn reorder_wedges(tags: &mut [Vec<Rgb<u8>>], order: WedgeOrder, space: ColorSpace) {
+    match order {
+        WedgeOrder::None => {}
+        WedgeOrder::BrightDark => {
+            for tag in tags {
+                if tag.len().is_multiple_of(2) {
+                    reorder_bright_dark_alternating(tag);
+                } else {
+                    reorder_bright_dark_odd(tag);
+                }
+            }
+        }
+        WedgeOrder::HueWheel => {
+            for tag in tags {
+                reorder_by_hue(tag);
+            }
+        }
+        WedgeOrder::MaxAdjacent => {
+            tags.par_iter_mut().for_each(|tag| reorder_max_adjacent(tag, space));
+        }
+    }
+}
+
+/// One side of the seed comparison: summary, Adopt button, then the tiles with each tag's
+/// smallest ΔE. Returns true when Adopt was clicked.
+fn comparison_column(ui: &mut egui::Ui, title: &str, threshold: f32, tags: &[Vec<Rgb<u8>>], textures: &[TextureHandle], space: ColorSpace, lightness_weight: f32) -> bool {
+    ui.strong(title);
+    if textures.is_empty() {
+        ui.horizontal(|ui| {
+            ui.spinner();
+            ui.label("Selecting colors…");
+        });
+        return false;
+    }
+    let mins: Vec<f32> = tags.iter().map(|t| min_pairwise_delta_e(t, space, lightness_weight)).collect();
+    let worst = mins.iter().copied().fold(f32::INFINITY, f32::min);
+    let mean = mins.iter().sum::<f32>() / mins.len().max(1) as f32;
+    ui.label(format!("threshold ΔE {:.1} · tag min ΔE: worst {:.1}, mean {:.1}", threshold, worst, mean));
+    let adopt = ui.button("Adopt").clicked();
+    egui::ScrollArea::vertical().id_source(title).show(ui, |ui| {
+        ui.horizontal_wrapped(|ui| {
+            for (tex, d) in textures.iter().zip(&mins) {
+                ui.vertical(|ui| {
+                    let size = SliderConfig::COMPARE_TILE_SIZE;
+                    ui.add(egui::Image::new((tex.id(), egui::vec2(size, size))));
+                    ui.small(format!("ΔE {:.1}", d));
+                });
+            }
+        });
+    });
+    adopt
+}
+
+/// Clickable square of color `c`, outlined when `selected`
+fn color_swatch(ui: &mut egui::Ui, c: Rgb<u8>, size: f32, selected: bool) -> egui::Response {
+    let (rect, resp) = ui.allocate_exact_size(egui::Vec2::splat(size), egui::Sense::click());
+    ui.painter().rect_filled(rect, 2.0, egui::Color32::from_rgb(c[0], c[1], c[2]));
+    if selected {
+        ui.painter().rect_stroke(rect.expand(1.0), 2.0, (2.0, ui.visuals().selection.stroke.color));
+    } else if resp.hovered() {
+        ui.painter().rect_stroke(rect, 2.0, (1.0, ui.visuals().text_color()));
+    }
+    resp
+}
+
+/// Render and save the tags once per width into `<timestamped dir>/<width>/`, each folder
+/// with its own manifest. Every size is rendered fresh; the `high_res` cache is not used.
+#[cfg(not(target_arch = "wasm32"))]
+fn save_multi_size(
+    tags: &[Vec<Rgb<u8>>],
+    threshold: f32,
+    render: &HighResRender,
+    opts: &SaveOptions,
+    sizes: &[u32],
+    step: &(dyn Fn() -> bool + Sync),
+) -> Result<String, Box<dyn std::error::Error>> {
+    let out_dir = create_timestamped_dir(&opts.out_root)?;
+    for &width in sizes {
+        let render = render.resized(width);
+        let images = render.render(tags, step).ok_or(SAVE_CANCELLED)?;
+        let sub_dir = format!("{}/{}", out_dir, width);
+        ensure_out_dir(&sub_dir)?;
+        let opts = SaveOptions { style: render.style, marker_size: render.size, ..opts.clone() };
+        save_all_to_dir(&sub_dir, tags, threshold, &images, &opts, step)?;
+    }
+    Ok(out_dir)
+}
+
+/// Render only the tags at `missing` and write them into the earlier save `out_dir`,
+/// merging its manifest (see `resume_save_to_dir`)
+#[cfg(not(target_arch = "wasm32"))]
+fn resume_save(
+    out_dir: &str,
+    tags: &[Vec<Rgb<u8>>],
+    threshold: f32,
+    render: &HighResRender,
+    opts: &SaveOptions,
+    missing: &[usize],
+    step: &(dyn Fn() -> bool + Sync),
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut images: Vec<Option<DynamicImage>> = vec![None; tags.len()];
+    let rendered: Option<Vec<(usize, DynamicImage)>> = missing
+        .par_iter()
+        .map(|&i| {
+            let img = render.render_one(i, &tags[i]);
+            step().then_some((i, img))
+        })
+        .collect();
+    for (i, img) in rendered.ok_or(SAVE_CANCELLED)? {
+        images[i] = Some(img);
+    }
+    resume_save_to_dir(out_dir, tags, threshold, &images, opts, step)?;
+    Ok(out_dir.to_string())
 }
 
 impl AppState {
@@ -140,65 +946,361 @@ impl AppState {
             count: SliderConfig::COUNT_DEFAULT,
             threshold: SliderConfig::THRESHOLD_DEFAULT,
             sides: SliderConfig::SIDES_DEFAULT,
+            shape: MarkerShape::Polygon,
+            sector_count: SliderConfig::SECTORS_DEFAULT,
+            mixed_sides: SliderConfig::MIXED_SIDES_DEFAULT,
+            fit_palette: SliderConfig::FIT_PALETTE_DEFAULT,
+            sides_pattern_text: SliderConfig::SIDES_PATTERN_DEFAULT.to_string(),
+            sides_pattern: parse_sides_pattern(SliderConfig::SIDES_PATTERN_DEFAULT).unwrap_or_default(),
             tags: Vec::new(),
             textures: Vec::new(),
+            texture_keys: Vec::new(),
             save_size: SliderConfig::SAVE_SIZE_DEFAULT,
             high_res: Vec::new(),
             preview_max_width: SliderConfig::RESOLUTION_DEFAULT,
             columns: SliderConfig::COLUMNS_DEFAULT,
             center_dot: SliderConfig::CENTER_DOT_ENABLED_DEFAULT,
             center_dot_size_pct: SliderConfig::CENTER_DOT_DEFAULT,
+            center_dot_color: egui::Color32::BLACK,
+            center_ring: SliderConfig::CENTER_RING_ENABLED_DEFAULT,
+            center_ring_inner_pct: SliderConfig::CENTER_RING_INNER_DEFAULT,
+            center_polygon: SliderConfig::CENTER_POLYGON_ENABLED_DEFAULT,
+            center_polygon_sides: SliderConfig::CENTER_POLYGON_SIDES_DEFAULT,
             gradient_dot: SliderConfig::GRADIENT_DOT_ENABLED_DEFAULT,
             gradient_dot_size_pct: SliderConfig::GRADIENT_DOT_DEFAULT,
+            gradient_dot_sigma_pct: SliderConfig::GRADIENT_DOT_SIGMA_DEFAULT,
+            gradient_dot_color: egui::Color32::WHITE,
+            margin_pct: SliderConfig::MARGIN_DEFAULT,
+            wedge_gap_px: SliderConfig::WEDGE_GAP_DEFAULT,
+            min_wedge_px: SliderConfig::MIN_WEDGE_PX_DEFAULT,
+            bounding_ring: SliderConfig::BOUNDING_RING_ENABLED_DEFAULT,
+            bounding_ring_px: SliderConfig::BOUNDING_RING_DEFAULT,
+            bounding_ring_color: egui::Color32::BLACK,
+            signature_band: SliderConfig::SIGNATURE_BAND_ENABLED_DEFAULT,
+            signature_band_px: SliderConfig::SIGNATURE_BAND_DEFAULT,
+            signature_band_auto: true,
+            signature_band_color: egui::Color32::GRAY,
+            wedge_gradient: SliderConfig::WEDGE_GRADIENT_DEFAULT,
+            dither: SliderConfig::DITHER_DEFAULT,
+            dither_levels: SliderConfig::DITHER_LEVELS_DEFAULT,
+            random_rotation: SliderConfig::RANDOM_ROTATION_DEFAULT,
+            wedge_order: SliderConfig::WEDGE_ORDER_DEFAULT,
+            tag_sort: SliderConfig::TAG_SORT_DEFAULT,
+            sort_saves: SliderConfig::SORT_SAVES_DEFAULT,
+            tag_order: Vec::new(),
+            orientation_wedge: SliderConfig::ORIENTATION_WEDGE_DEFAULT,
+            orientation_color: egui::Color32::BLACK,
+            min_lightness_spread: SliderConfig::LIGHTNESS_SPREAD_DEFAULT,
+            flat_tags: Vec::new(),
+            tag_rotations: Vec::new(),
+            tag_categories: Vec::new(),
+            fiducial_border: SliderConfig::FIDUCIAL_BORDER_DEFAULT,
+            quiet_zone_pct: SliderConfig::QUIET_ZONE_DEFAULT,
             max_possible_count: SliderConfig::COUNT_MAX as usize,
+            threshold_estimates: HashMap::new(),
+            threshold_estimates_key: 0,
+            max_possible_count_key: 0,
+            reused_colors: Vec::new(),
+            similar_tags: Vec::new(),
+            delta_histogram: (1.0, Vec::new()),
+            similar_tag_distance: SliderConfig::TAG_DISTANCE_DEFAULT,
+            min_usable_threshold: SliderConfig::MIN_USABLE_THRESHOLD_DEFAULT,
+            hold_threshold_floor: SliderConfig::HOLD_THRESHOLD_FLOOR_DEFAULT,
             pending_regen: None,
             regen_deadline: None,
             candidate_pool: Vec::new(),
             candidate_labs: Vec::new(),
+            color_space: SliderConfig::COLOR_SPACE_DEFAULT,
+            lightness_weight: SliderConfig::LIGHTNESS_WEIGHT_DEFAULT,
+            pool_source: SliderConfig::POOL_SOURCE_DEFAULT,
+            snap_to_grid: SliderConfig::SNAP_TO_GRID_DEFAULT,
+            unsnapped_tags: Vec::new(),
+            candidate_dm: Vec::new(),
+            threshold_search: ThresholdSearch::default(),
+            image_palette_k: SliderConfig::IMAGE_PALETTE_K_DEFAULT,
+            pool_merge_delta: SliderConfig::POOL_MERGE_DELTA_DEFAULT,
             right_mono_textures: Vec::new(),
             right_first_scaled_textures: Vec::new(),
             right_blurred_textures: Vec::new(),
             last_left_tile_w: SliderConfig::TILE_WIDTH_DEFAULT,
             last_panel_width: 800.0, // default width
+            resize_settle_at: None,
             profiling: SliderConfig::PROFILING_DEFAULT,
+            thread_count: SliderConfig::THREAD_COUNT_DEFAULT,
+            thread_pool: build_thread_pool(SliderConfig::THREAD_COUNT_DEFAULT),
+            profile_path: SliderConfig::PROFILE_PATH_DEFAULT.to_string(),
             defer_high_res: SliderConfig::DEFER_HIGH_RES_DEFAULT,
+            export_16bit: SliderConfig::EXPORT_16BIT_DEFAULT,
+            gutter_px: SliderConfig::GUTTER_DEFAULT,
+            tile_labels: SliderConfig::TILE_LABELS_DEFAULT,
+            export_tag: 1,
+            inspected_tag: None,
+            preview_subject: 0,
+            zoom_tag: None,
+            zoom_texture: None,
+            zoom_scale: 1.0,
+            seed_comparison: None,
+            compare_seed_input: String::new(),
+            inspected_wedge: 0,
+            pinned_tags: HashMap::new(),
+            selected_tags: HashSet::new(),
+            selection_anchor: None,
+            spin_frames: SliderConfig::SPIN_FRAMES_DEFAULT,
+            print_dpi: SliderConfig::PRINT_DPI_DEFAULT,
+            bleed_mm: SliderConfig::BLEED_MM_DEFAULT,
+            trim_marks: SliderConfig::TRIM_MARKS_DEFAULT,
+            registration_dots: SliderConfig::REGISTRATION_DOTS_DEFAULT,
+            embed_metadata: SliderConfig::EMBED_METADATA_DEFAULT,
+            wedge_layers: SliderConfig::WEDGE_LAYERS_DEFAULT,
+            category_folders: SliderConfig::CATEGORY_FOLDERS_DEFAULT,
+            multi_sizes: SliderConfig::MULTI_SIZES_DEFAULT.to_string(),
             bg_color: egui::Color32::WHITE,
             serial_numbers: SliderConfig::SERIAL_NUMBERS_DEFAULT,
             serial_h_align: SliderConfig::SERIAL_H_ALIGN_DEFAULT,
             serial_v_align: SliderConfig::SERIAL_V_ALIGN_DEFAULT,
             serial_color: egui::Color32::WHITE,
             serial_border: SliderConfig::SERIAL_BORDER_DEFAULT,
+            seed: rand::random(),
+            threshold_override: None,
+            output_dir: SliderConfig::OUTPUT_DIR_DEFAULT.to_string(),
+            image_format: SliderConfig::IMAGE_FORMAT_DEFAULT,
+            manifest_format: SliderConfig::MANIFEST_FORMAT_DEFAULT,
+            status: None,
             blur_job_id: 0,
+            blur_level_count: SliderConfig::BLUR_LEVELS_DEFAULT,
+            blur_max_sigma: SliderConfig::BLUR_MAX_SIGMA_DEFAULT,
+            blur_source: None,
             blurred_rx: None,
+            blur_paused: Arc::new(AtomicBool::new(false)),
+            regen_job_id: 0,
+            regen_job: None,
+            save_job: None,
+            last_save_dir: None,
+            resume_overwrite_mismatched: false,
+            resume_plan: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            clipboard: None,
         };
         
-        // Build cached candidate pool once
-        let mut pool = candidate_srgb_grid();
-        // Filter by lightness range using Lab
-        pool.retain(|&c| {
-            let l = srgb_u8_to_lab(c).l;
-            (20.0..=90.0).contains(&l)
-        });
-        let labs = pool.iter().copied().map(srgb_u8_to_lab).collect();
-        app.candidate_pool = pool;
-        app.candidate_labs = labs;
-        
-        // Calculate initial max possible count
-        app.update_max_possible_count();
+        // Build cached candidate pool once (also calculates initial max possible count)
+        app.set_candidate_pool(app.default_pool());
         
         app
     }
 
+    /// Colors of the selected built-in pool
+    pub fn default_pool(&self) -> Vec<Rgb<u8>> {
+        self.pool_source.colors()
+    }
+
+    /// Replace the cached candidate pool, keeping only colors in the usable lightness range.
+    /// Returns false (leaving the current pool untouched) if too few colors survive.
+    pub fn set_candidate_pool(&mut self, pool: Vec<Rgb<u8>>) -> bool {
+        // Filter by lightness range using Lab
+        let pool: Vec<Rgb<u8>> = pool
+            .into_par_iter()
+            .filter(|&c| (SliderConfig::POOL_LIGHTNESS_MIN..=SliderConfig::POOL_LIGHTNESS_MAX).contains(&srgb_u8_to_lab(c).l))
+            .collect();
+        if pool.len() < self.wedges() {
+            return false;
+        }
+        self.candidate_pool = pool;
+        self.rebuild_pool();
+        true
+    }
+
+    /// Recompute everything derived from the candidate pool (coordinates in
+    /// `color_space`, the ΔE matrix and the max tag count). Call after any change
+    /// to the pool or to how its colors are measured.
+    pub fn rebuild_pool(&mut self) {
+        let t0 = Instant::now();
+        let (space, weight) = (self.color_space, self.lightness_weight);
+        self.candidate_labs = self.candidate_pool.par_iter().map(|&c| space.weighted_coords(c, weight)).collect();
+        self.candidate_dm = pairwise_delta_matrix(&self.candidate_labs);
+        self.update_max_possible_count();
+        self.count = self.count.min(self.max_possible_count);
+        if self.profiling >= ProfileLevel::Summary { println!("[profile] rebuild_pool: {:.2} ms (pool={})", t0.elapsed().as_secs_f64()*1000.0, self.candidate_pool.len()); }
+    }
+
+    /// Ask for an image file and rebuild the candidate pool from its dominant colors
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn sample_pool_from_image(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Images", &["png", "jpg", "jpeg", "bmp", "tif", "tiff", "gif", "webp"])
+            .pick_file()
+        else {
+            return;
+        };
+        self.pool_from_image_file(&path);
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn sample_pool_from_image(&mut self) {
+        self.set_status("Opening image files is only available in the desktop build".into(), true);
+    }
+
+    /// Rebuild the candidate pool from the dominant colors of the image at `path`
+    fn pool_from_image_file(&mut self, path: &std::path::Path) {
+        let img = match image::open(path) {
+            Ok(img) => img,
+            Err(e) => {
+                self.set_status(format!("Could not open {}: {}", path.display(), e), true);
+                return;
+            }
+        };
+        let sampled = palette_from_image(&img, self.image_palette_k);
+        let pool = merge_near_duplicates(&sampled, self.pool_merge_delta);
+        let merged = sampled.len() - pool.len();
+        if self.set_candidate_pool(pool) {
+            self.set_status(format!("Candidate pool: {} colors from {}{}", self.candidate_pool.len(), path.display(), merged_note(merged)), false);
+            self.schedule_regen(RegenKind::Full, 0);
+        } else {
+            self.set_status(format!("Too few usable colors in {}; try a larger k", path.display()), true);
+        }
+    }
+
+    /// Use a file dropped on the window as the candidate pool: palette files (see
+    /// `load_palette`) directly, anything else is sampled as an image
+    pub fn pool_from_dropped_file(&mut self, path: &std::path::Path) {
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+        if !PALETTE_EXTENSIONS.contains(&ext.as_str()) {
+            return self.pool_from_image_file(path);
+        }
+        let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        match load_palette(path) {
+            Ok(loaded) => {
+                let total = loaded.len();
+                let pool = merge_near_duplicates(&loaded, self.pool_merge_delta);
+                let merged = total - pool.len();
+                if self.set_candidate_pool(pool) {
+                    self.set_status(format!("Candidate pool: {} of {} colors from {}{}", self.candidate_pool.len(), total, name, merged_note(merged)), false);
+                    self.schedule_regen(RegenKind::Full, 0);
+                } else {
+                    self.set_status(format!("Too few usable colors in {} for {} wedges", name, self.wedges()), true);
+                }
+            }
+            Err(e) => self.set_status(format!("Could not read palette {}: {}", name, e), true),
+        }
+    }
+
+    /// Build the initial state, overriding defaults with any command line options
+    pub fn from_cli(cli: &Cli) -> Self {
+        let mut app = AppState::new();
+        if let Some(sides) = cli.sides { app.sides = sides; }
+        if let Some(count) = cli.count { app.count = count; }
+        if let Some(t) = cli.threshold { app.threshold_override = Some(t); }
+        if let Some(t) = cli.min_usable_threshold { app.min_usable_threshold = t; }
+        if let Some(v) = cli.min_lightness_spread { app.min_lightness_spread = v; }
+        if let Some(v) = cli.save_size { app.save_size = (v & !1, v & !1); }
+        if let Some(v) = cli.preview_res { app.preview_max_width = v & !1; }
+        if let Some(v) = cli.center_dot { app.center_dot = v; }
+        if let Some(v) = cli.center_dot_size { app.center_dot_size_pct = v; }
+        if let Some(v) = cli.gradient_dot { app.gradient_dot = v; }
+        if let Some(v) = cli.gradient_dot_size { app.gradient_dot_size_pct = v; }
+        if let Some(dir) = &cli.output_dir { app.output_dir = dir.clone(); }
+        if let Some(f) = cli.format { app.image_format = f; }
+        if let Some(f) = cli.manifest_format { app.manifest_format = f; }
+        if let Some(seed) = cli.seed { app.seed = seed; }
+        if let Some(path) = &cli.profile_path { app.profile_path = path.clone(); }
+        app.embed_metadata = cli.embed_metadata;
+        if let Some(space) = cli.color_space { app.color_space = space; }
+        if let Some(source) = cli.pool { app.pool_source = source; }
+        if cli.grayscale { app.pool_source = PoolSource::Grayscale; }
+        if app.pool_source != SliderConfig::POOL_SOURCE_DEFAULT {
+            app.set_candidate_pool(app.default_pool());
+        } else if cli.color_space.is_some() {
+            // Pool coordinates (and the max count) depend on the requested space
+            app.rebuild_pool();
+        } else if cli.sides.is_some() {
+            app.update_max_possible_count();
+        }
+        app
+    }
+
+    /// Hash of everything color selection depends on: the pool, color space, per-tag
+    /// wedge counts, seed and search settings
+    fn selection_key(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.candidate_pool.iter().map(|c| c.0).collect::<Vec<_>>().hash(&mut hasher);
+        (self.color_space.label(), self.lightness_weight.to_bits(), self.wedges(), self.uses_sides_pattern(), &self.sides_pattern, self.uses_fit_palette()).hash(&mut hasher);
+        (self.seed, self.threshold_search.iterations, self.threshold_search.prefer_vivid).hash(&mut hasher);
+        self.orientation_rgb().map(|c| (c.0, self.min_usable_threshold.to_bits())).hash(&mut hasher);
+        (self.threshold_search.lo.map(f32::to_bits), self.threshold_search.hi.map(f32::to_bits)).hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Recompute `max_possible_count` if anything it depends on changed since the last
+    /// time, lowering the tag count (and regenerating) if it no longer fits
+    pub fn refresh_max_possible_count(&mut self) {
+        if self.selection_key() == self.max_possible_count_key {
+            return;
+        }
+        self.update_max_possible_count();
+        if self.count > self.max_possible_count {
+            self.count = self.max_possible_count;
+            self.schedule_regen(RegenKind::Full, 0);
+        }
+    }
+
     pub fn update_max_possible_count(&mut self) {
         // Estimate max possible tags by attempting to find colors for a large number
         // and seeing how many we can actually get
-        let test_needed = 1000 * self.sides; // test with a very high number
+        let test_needed = self.colors_needed(1000); // test with a very high number
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let (pool, labs, dm) = self.selection_pool();
         let (_threshold, colors) = compute_max_threshold_and_colors_from_pool(
-            &self.candidate_pool, 
-            &self.candidate_labs, 
-            test_needed
+            &pool,
+            &labs,
+            test_needed,
+            &self.threshold_search,
+            Some(&dm),
+            &mut rng,
+        );
+        self.max_possible_count = self.tags_fillable(colors.len()).max(1);
+        self.max_possible_count_key = self.selection_key();
+    }
+
+    /// Max feasible ΔE for `count` tags from the cached pool, without grouping. Results are
+    /// memoized per count until the `selection_key` changes.
+    pub fn estimate_threshold(&mut self, count: usize) -> f32 {
+        let key = self.selection_key();
+        if key != self.threshold_estimates_key {
+            self.threshold_estimates.clear();
+            self.threshold_estimates_key = key;
+        }
+        if let Some(&thr) = self.threshold_estimates.get(&count) {
+            return thr;
+        }
+        let needed = self.colors_needed(count);
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        // The same pool regeneration searches, orientation-color exclusions included
+        let (pool, labs, dm) = self.selection_pool();
+        let (thr, _) = compute_max_threshold_and_colors_from_pool(
+            &pool,
+            &labs,
+            needed,
+            &self.threshold_search,
+            Some(&dm),
+            &mut rng,
         );
-        self.max_possible_count = (colors.len() / self.sides).max(1);
+        self.threshold_estimates.insert(count, thr);
+        thr
+    }
+
+    /// Largest count in `from..=to` whose estimated threshold stays at or above
+    /// `min_usable_threshold`, or `from` if none does. Assumes the estimate falls
+    /// as the count grows.
+    fn max_count_above_floor(&mut self, from: usize, to: usize) -> usize {
+        let (mut lo, mut hi) = (from, to);
+        while lo < hi {
+            let mid = lo + (hi - lo).div_ceil(2);
+            if self.estimate_threshold(mid) >= self.min_usable_threshold {
+                lo = mid;
+            } else {
+                hi = mid - 1;
+            }
+        }
+        lo
     }
 
     pub fn schedule_regen(&mut self, kind: RegenKind, delay_ms: u64) {
@@ -208,6 +1310,8 @@ impl AppState {
                 // already have full scheduled; keep earliest deadline
             }
             (_, RegenKind::Full) => {
+                // A regeneration still running is stale now
+                self.cancel_regen_job();
                 self.pending_regen = Some(RegenKind::Full);
             }
             (None, RegenKind::ImagesOnly) => {
@@ -224,259 +1328,1560 @@ impl AppState {
         });
     }
 
+    /// Start a full regeneration: color selection and grouping run on a worker thread,
+    /// superseding any still running, and the previous tags stay on screen until
+    /// `poll_regen_job` applies the result
     pub fn regenerate(&mut self, ctx: &Context) {
-        let t_total = Instant::now();
-        if self.profiling { println!("[profile] regenerate: start"); }
-        
-        // Ensure sides stays within [3, 6]
-        self.sides = self.sides.clamp(3, 6);
-        
-        // Auto-compute max feasible ΔE for the requested number of tags
-        let needed = self.count.saturating_mul(self.sides).max(self.sides);
-        
-        // Use cached candidate pool for speed
-        let t0 = Instant::now();
-        let (auto_thr, mut colors) = compute_max_threshold_and_colors_from_pool(&self.candidate_pool, &self.candidate_labs, needed);
-        if self.profiling { println!("[profile] \tcolor select: {:.2} ms (needed={})", t0.elapsed().as_secs_f64()*1000.0, needed); }
-        
-        self.threshold = auto_thr;
-        if colors.len() < needed {
-            // If not enough colors, reduce count to what's possible
-            self.count = (colors.len() / self.sides).max(1);
-            colors.truncate(self.count * self.sides);
+        if self.profiling >= ProfileLevel::Summary { println!("[profile] regenerate: start"); }
+        self.cancel_regen_job();
+        self.regen_job_id = self.regen_job_id.wrapping_add(1);
+        let job_id = self.regen_job_id;
+        let selection = self.tag_selection();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel::<(u64, SelectedTags)>();
+        self.regen_job = Some(RegenJob { id: job_id, rx, cancel: cancel.clone(), started: Instant::now() });
+
+        // Keep an open comparison on the same settings
+        if let Some(seed) = self.seed_comparison.as_ref().map(|c| c.seed) {
+            self.start_seed_comparison(ctx, seed);
         }
-        
-        let labs: Vec<Lab> = colors.iter().copied().map(srgb_u8_to_lab).collect();
-        let t1 = Instant::now();
-        self.tags = group_colors_into_groups_monte_carlo(colors, labs, self.count, self.sides, 2000);
-        if self.profiling { println!("[profile] \tgrouping: {:.2} ms (tags={}, sides={})", t1.elapsed().as_secs_f64()*1000.0, self.count, self.sides); }
-        
-        // For even-sided markers, reorder each tag to alternate bright/dark to maximize adjacent contrast
-        if self.sides % 2 == 0 {
-            let t2 = Instant::now();
-            for tag in &mut self.tags { 
-                reorder_bright_dark_alternating(tag); 
+
+        let ctx = ctx.clone();
+        let pool = self.thread_pool.clone();
+        spawn_worker(move || {
+            if let Some(selected) = pool.install(|| selection.run(&|| cancel.load(Ordering::Relaxed))) {
+                let _ = tx.send((job_id, selected));
+                ctx.request_repaint();
+            }
+        });
+    }
+
+    /// Stop the running full regeneration, if any; its result will be ignored
+    fn cancel_regen_job(&mut self) {
+        if let Some(job) = self.regen_job.take() {
+            job.cancel.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Apply the result of the background regeneration once it arrives
+    fn poll_regen_job(&mut self, ctx: &Context) {
+        let Some(job) = &self.regen_job else { return };
+        let selected = match job.rx.try_recv() {
+            Ok((id, selected)) if id == job.id => selected,
+            Ok(_) | Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                self.regen_job = None;
+                return self.set_status("Regeneration stopped unexpectedly".into(), true);
+            }
+        };
+        let started = job.started;
+        self.regen_job = None;
+        let mut timings = self.apply_selection(selected);
+
+        // Build lightweight previews (skip heavy high-res resize path)
+        let t4 = Instant::now();
+        self.rebuild_textures_quick(ctx);
+        timings.previews_ms = t4.elapsed().as_secs_f64()*1000.0;
+        timings.total_ms = started.elapsed().as_secs_f64()*1000.0;
+        if self.profiling >= ProfileLevel::Summary { println!("[profile] \tbuild_previews_quick: {:.2} ms", timings.previews_ms); }
+        if self.profiling >= ProfileLevel::Summary { println!("[profile] regenerate: total {:.2} ms", timings.total_ms); }
+        if self.profiling >= ProfileLevel::Summary {
+            if let Err(e) = append_profile_row(&self.profile_path, &timings) {
+                self.set_status(format!("Could not write {}: {}", self.profile_path, e), true);
+            }
+        }
+    }
+
+    /// Inputs for color selection and grouping, detached so they can run on a worker
+    fn tag_selection(&mut self) -> TagSelection {
+        // Ensure sides stays within the slider range
+        self.sides = self.sides.clamp(SliderConfig::SIDES_MIN as usize, SliderConfig::SIDES_MAX as usize);
+        let (pool, labs, dm) = self.selection_pool();
+        TagSelection {
+            pool: pool.into_owned(),
+            labs: labs.into_owned(),
+            dm: dm.into_owned(),
+            color_space: self.color_space,
+            lightness_weight: self.lightness_weight,
+            group_sizes: self.group_sizes(self.count.max(1)),
+            threshold_override: self.threshold_override,
+            search: self.threshold_search,
+            min_lightness_spread: self.min_lightness_spread,
+            seed: self.seed,
+            profiling: self.profiling >= ProfileLevel::Summary,
+        }
+    }
+
+    /// Select colors, group them into tags, and (unless deferred) render high-res images.
+    /// Runs on the calling thread and needs no egui context, so it also drives headless
+    /// runs. Returns per-stage timings.
+    pub fn generate_tags(&mut self) -> ProfileRow {
+        let selected = self.tag_selection().run(&|| false).unwrap_or_default();
+        self.apply_selection(selected)
+    }
+
+    /// Take over freshly selected tags: wedge order, orientation wedge, pinned tags,
+    /// checks, rotations and (unless deferred) the high-res images
+    fn apply_selection(&mut self, selected: SelectedTags) -> ProfileRow {
+        let wedges = self.wedges();
+        self.threshold = selected.threshold;
+        self.count = selected.count;
+        self.tags = selected.tags;
+        if let Some(message) = selected.shortfall {
+            self.set_status(message, true);
+        }
+
+        let mut timings = ProfileRow {
+            count: self.count,
+            sides: wedges,
+            color_select_ms: selected.color_select_ms,
+            grouping_ms: selected.grouping_ms,
+            ..Default::default()
+        };
+
+        // Optionally reorder wedges: bright/dark alternation maximizes adjacent lightness
+        // contrast, max adjacent maximizes neighboring ΔE, the hue wheel is purely aesthetic
+        let t2 = Instant::now();
+        reorder_wedges(&mut self.tags, self.wedge_order, self.color_space);
+        timings.reorder_ms = t2.elapsed().as_secs_f64()*1000.0;
+        if self.profiling >= ProfileLevel::Summary { println!("[profile] \treorder: {:.2} ms", timings.reorder_ms); }
+
+        self.unsnapped_tags.clear();
+        if self.snap_to_grid {
+            self.unsnapped_tags = self.tags.clone();
+            for tag in &mut self.tags {
+                for c in tag.iter_mut() {
+                    *c = snap_to_grid(*c);
+                }
+            }
+        }
+        if let Some(c) = self.orientation_rgb() {
+            for tag in self.tags.iter_mut().chain(&mut self.unsnapped_tags) {
+                tag.insert(ORIENTATION_WEDGE, c);
             }
-            if self.profiling { println!("[profile] \treorder: {:.2} ms", t2.elapsed().as_secs_f64()*1000.0); }
+        }
+        self.apply_pinned_tags();
+        let count = self.tags.len();
+        self.tag_categories.resize(count, None);
+        self.selected_tags.retain(|&i| i < count);
+        self.refresh_tag_stats();
+        if self.snap_to_grid {
+            self.check_snapped_threshold();
         }
         
+        self.update_tag_rotations();
         self.textures.clear();
+        self.texture_keys.clear();
         self.high_res.clear();
 
         // Render high-resolution images once
         if !self.defer_high_res {
             let t3 = Instant::now();
             self.render_high_res_images();
-            if self.profiling { println!("[profile] \trender_high_res: {:.2} ms", t3.elapsed().as_secs_f64()*1000.0); }
+            timings.render_ms = t3.elapsed().as_secs_f64()*1000.0;
+            if self.profiling >= ProfileLevel::Summary { println!("[profile] \trender_high_res: {:.2} ms", timings.render_ms); }
         }
+        timings
+    }
 
-        // Build lightweight previews (skip heavy high-res resize path)
-        let t4 = Instant::now();
-        self.rebuild_textures_quick(ctx);
-        if self.profiling { println!("[profile] \tbuild_previews_quick: {:.2} ms", t4.elapsed().as_secs_f64()*1000.0); }
-        if self.profiling { println!("[profile] regenerate: total {:.2} ms", t_total.elapsed().as_secs_f64()*1000.0); }
+    /// Warn when snapping brought two selected colors closer than the threshold
+    fn check_snapped_threshold(&mut self) {
+        let orientation = self.orientation_rgb().map(|_| ORIENTATION_WEDGE);
+        let unique = |tags: &[Vec<Rgb<u8>>]| {
+            let mut colors: Vec<Rgb<u8>> = distinguishing_colors(tags, orientation).into_iter().flatten().collect();
+            colors.sort_by_key(|c| c.0);
+            colors.dedup();
+            colors
+        };
+        let colors = unique(&self.tags);
+        let merged = unique(&self.unsnapped_tags).len().saturating_sub(colors.len());
+        let min_d = min_pairwise_delta_e(&colors, self.color_space, self.lightness_weight);
+        if merged > 0 {
+            self.set_status(format!("Snapping to the grid merged {} colors into others; turn it off or lower the count", merged), true);
+        } else if min_d < self.threshold {
+            self.set_status(format!("Snapping to the grid lowered the smallest ΔE to {:.1}, below the threshold {:.1}", min_d, self.threshold), true);
+        }
     }
 
-    pub fn render_high_res_images(&mut self) {
-        let t0 = Instant::now();
-        self.high_res.clear();
-        let sides = self.sides;
-        let center_dot = self.center_dot;
-        let center_dot_size_pct = self.center_dot_size_pct;
-        let gradient_dot = self.gradient_dot;
-        let gradient_dot_size_pct = self.gradient_dot_size_pct;
-        let (w, h) = self.save_size;
-        let bg = image::Rgb([self.bg_color.r(), self.bg_color.g(), self.bg_color.b()]);
-        let serial_numbers = self.serial_numbers;
-        let serial_h_align = self.serial_h_align;
-        let serial_v_align = self.serial_v_align;
-        let serial_color = image::Rgb([self.serial_color.r(), self.serial_color.g(), self.serial_color.b()]);
-        let serial_border = self.serial_border;
-        
-        self.high_res = self
-            .tags
-            .par_iter()
-            .enumerate()
-            .map(|(i, colors)| {
-                let serial = if serial_numbers { Some((i + 1, serial_h_align, serial_v_align, serial_color, serial_border)) } else { None };
-                let img = draw_marker_polygon(
-                    w,
-                    h,
-                    sides,
-                    colors,
-                    center_dot,
-                    center_dot_size_pct,
-                    gradient_dot,
-                    gradient_dot_size_pct,
-                    bg,
-                    serial,
-                );
-                DynamicImage::ImageRgb8(img)
-            })
-            .collect();
-        if self.profiling { println!("[profile] render_high_res_images: {:.2} ms (count={}, size={}x{})", t0.elapsed().as_secs_f64()*1000.0, self.tags.len(), self.save_size.0, self.save_size.1); }
+    /// Put hand-edited tags back over freshly generated ones. A pin is dropped once its
+    /// tag's side count no longer matches; pins past the current count wait for it to grow.
+    fn apply_pinned_tags(&mut self) {
+        let tags = &mut self.tags;
+        self.pinned_tags.retain(|&i, colors| match tags.get_mut(i) {
+            Some(tag) if tag.len() == colors.len() => {
+                tag.clone_from(colors);
+                true
+            }
+            Some(_) => false,
+            None => true,
+        });
     }
 
-    pub fn rebuild_textures_quick(&mut self, ctx: &Context) {
-        // Draw previews at the user-chosen resolution, display at tile size
-        let t0 = Instant::now();
-        self.textures.clear();
-        let w = self.preview_max_width.max(2);
-        let h = w; // square preview
-        let sides = self.sides;
-        let center_dot = self.center_dot;
-        let center_dot_size_pct = self.center_dot_size_pct;
-        let gradient_dot = self.gradient_dot;
-        let gradient_dot_size_pct = self.gradient_dot_size_pct;
-        let bg = image::Rgb([self.bg_color.r(), self.bg_color.g(), self.bg_color.b()]);
-        let serial_numbers = self.serial_numbers;
-        let serial_h_align = self.serial_h_align;
-        let serial_v_align = self.serial_v_align;
-        let serial_color = image::Rgb([self.serial_color.r(), self.serial_color.g(), self.serial_color.b()]);
-        let serial_border = self.serial_border;
-        
-        let imgs: Vec<_> = self
-            .tags
-            .par_iter()
-            .enumerate()
-            .map(|(i, colors)| {
-                let serial = if serial_numbers { Some((i + 1, serial_h_align, serial_v_align, serial_color, serial_border)) } else { None };
-                let img = draw_marker_polygon(w, h, sides, colors, center_dot, center_dot_size_pct, gradient_dot, gradient_dot_size_pct, bg, serial);
-                (i, DynamicImage::ImageRgb8(img).to_rgba8())
-            })
-            .collect();
-            
-        for (i, rgba) in imgs.into_iter() {
-            let size = [rgba.width() as usize, rgba.height() as usize];
+    /// Recompute the checks derived from the current tags: reused colors, flat tags,
+    /// similar pairs and the ΔE histogram
+    fn refresh_tag_stats(&mut self) {
+        let distinct = distinguishing_colors(&self.tags, self.orientation_rgb().map(|_| ORIENTATION_WEDGE));
+        self.reused_colors = find_reused_colors(&distinct);
+        self.flat_tags = (0..self.tags.len()).filter(|&i| lightness_spread(&self.tags[i]) < self.min_lightness_spread).collect();
+        self.similar_tags = find_similar_tags(&distinct, self.similar_tag_distance, self.color_space, self.lightness_weight);
+        let selected: Vec<Lab> = distinct.iter().flatten().map(|&c| self.coords(c)).collect();
+        self.delta_histogram = delta_histogram(&pairwise_delta_matrix(&selected), selected.len(), SliderConfig::DELTA_HISTOGRAM_BINS);
+        self.tag_order = self.tag_sort.order(&self.tags);
+    }
+
+    /// Tag indices in the order the grid shows them
+    fn grid_order(&self) -> Vec<usize> {
+        if self.tag_order.len() == self.tags.len() {
+            self.tag_order.clone()
+        } else {
+            (0..self.tags.len()).collect()
+        }
+    }
+
+    /// Tags to save, in file order: the selection for `SaveKind::Selected`, and the grid
+    /// order when saves follow it. None saves every tag in generation order.
+    fn save_order(&self, kind: SaveKind) -> Option<Vec<usize>> {
+        let sorted = self.sort_saves && self.tag_sort != TagSort::Generation;
+        match kind {
+            SaveKind::Selected if sorted => Some(self.grid_order().into_iter().filter(|i| self.selected_tags.contains(i)).collect()),
+            SaveKind::Selected => Some(self.selected_indices()),
+            _ if sorted => Some(self.grid_order()),
+            _ => None,
+        }
+    }
+
+    /// Smallest ΔE between two colors of tag `index`, in the selection color space
+    pub fn tag_min_delta(&self, index: usize) -> f32 {
+        min_pairwise_delta_e(&self.tags[index], self.color_space, self.lightness_weight)
+    }
+
+    /// Hand-edit one wedge of a tag. The tag is pinned so regeneration keeps it, and only
+    /// its own images are re-rendered.
+    pub fn set_wedge_color(&mut self, index: usize, wedge: usize, color: Rgb<u8>) {
+        let Some(slot) = self.tags.get_mut(index).and_then(|tag| tag.get_mut(wedge)) else {
+            return;
+        };
+        if *slot == color {
+            return;
+        }
+        *slot = color;
+        self.pinned_tags.insert(index, self.tags[index].clone());
+        self.refresh_tag_stats();
+        if index < self.high_res.len() {
+            self.high_res[index] = self.high_res_render().render_one(index, &self.tags[index]);
+        }
+        // Preview tiles are keyed by content, so only this tag's texture is rebuilt
+        self.schedule_regen(RegenKind::ImagesOnly, 0);
+    }
+
+    /// Render every tag at the save size without saving, and show the preview subject at 1:1
+    pub fn render_high_res_now(&mut self, ctx: &Context) {
+        if self.tags.is_empty() {
+            return;
+        }
+        let t0 = Instant::now();
+        self.render_high_res_images();
+        let ms = t0.elapsed().as_secs_f64() * 1000.0;
+        let index = self.preview_subject.min(self.tags.len() - 1);
+        let key = self.high_res_render().key(index, &self.tags[index]);
+        let img = self.high_res[index].clone();
+        self.set_zoom_texture(ctx, key, &img);
+        self.zoom_tag = Some(index);
+        self.set_status(format!("Rendered {} tags at {}×{} in {:.0} ms", self.high_res.len(), self.save_size.0, self.save_size.1, ms), false);
+    }
+
+    fn set_zoom_texture(&mut self, ctx: &Context, key: u64, img: &DynamicImage) {
+        let rgba = img.to_rgba8();
+        let size = [rgba.width() as usize, rgba.height() as usize];
+        let color_image = ColorImage::from_rgba_unmultiplied(size, &rgba);
+        self.zoom_texture = Some((key, ctx.load_texture("zoom_view", color_image, TextureOptions::NEAREST)));
+    }
+
+    /// Window showing the zoomed tag at the save size, scrollable when larger than the window.
+    /// The image is rendered on demand and kept until the tag or a render setting changes.
+    fn draw_zoom_view(&mut self, ctx: &Context) {
+        let Some(index) = self.zoom_tag.filter(|&i| i < self.tags.len()) else {
+            self.zoom_tag = None;
+            self.zoom_texture = None;
+            return;
+        };
+        let render = self.high_res_render();
+        let key = render.key(index, &self.tags[index]);
+        if self.zoom_texture.as_ref().map(|(k, _)| *k) != Some(key) {
+            let img = render.render_one(index, &self.tags[index]);
+            self.set_zoom_texture(ctx, key, &img);
+        }
+        let Some((_, tex)) = &self.zoom_texture else { return };
+        let [w, h] = tex.size();
+        let mut open = true;
+        let mut scale = self.zoom_scale;
+        egui::Window::new(format!("Tag {} ({}×{})", index + 1, w, h))
+            .id(egui::Id::new("zoom_view"))
+            .open(&mut open)
+            .default_size(egui::vec2(SliderConfig::ZOOM_WINDOW_SIZE, SliderConfig::ZOOM_WINDOW_SIZE))
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.add(egui::Slider::new(&mut scale, SliderConfig::ZOOM_SCALE_MIN..=SliderConfig::ZOOM_SCALE_MAX).logarithmic(true).text("zoom"));
+                    if ui.button("1:1").on_hover_text("One image pixel per screen pixel").clicked() {
+                        scale = 1.0;
+                    }
+                });
+                // At scale 1 one texel covers one physical pixel
+                let px = scale / ctx.pixels_per_point();
+                egui::ScrollArea::both().show(ui, |ui| {
+                    ui.add(egui::Image::new((tex.id(), egui::vec2(w as f32 * px, h as f32 * px))));
+                });
+            });
+        self.zoom_scale = scale;
+        if !open {
+            self.zoom_tag = None;
+            self.zoom_texture = None;
+        }
+    }
+
+    /// Select tags for `seed` with the current settings on a worker thread, to show beside
+    /// the current tags
+    pub fn start_seed_comparison(&mut self, ctx: &Context, seed: u64) {
+        self.cancel_seed_comparison();
+        let mut selection = self.tag_selection();
+        selection.seed = seed;
+        selection.profiling = false;
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel::<SelectedTags>();
+        self.compare_seed_input = seed.to_string();
+        self.seed_comparison = Some(SeedComparison { seed, rx: Some(rx), cancel: cancel.clone(), selected: None, tags: Vec::new(), textures: Vec::new() });
+
+        let ctx = ctx.clone();
+        let pool = self.thread_pool.clone();
+        spawn_worker(move || {
+            if let Some(selected) = pool.install(|| selection.run(&|| cancel.load(Ordering::Relaxed))) {
+                let _ = tx.send(selected);
+                ctx.request_repaint();
+            }
+        });
+    }
+
+    /// Close the seed comparison, stopping its selection if still running
+    fn cancel_seed_comparison(&mut self) {
+        if let Some(cmp) = self.seed_comparison.take() {
+            cmp.cancel.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Take over the comparison's tags once its worker is done
+    fn poll_seed_comparison(&mut self, ctx: &Context) {
+        let Some(rx) = self.seed_comparison.as_ref().and_then(|c| c.rx.as_ref()) else { return };
+        let selected = match rx.try_recv() {
+            Ok(selected) => selected,
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                self.seed_comparison = None;
+                return self.set_status("Seed comparison stopped unexpectedly".into(), true);
+            }
+        };
+        let tags = self.arranged_tags(&selected.tags);
+        if let Some(cmp) = &mut self.seed_comparison {
+            cmp.rx = None;
+            cmp.selected = Some(selected);
+            cmp.tags = tags;
+        }
+        self.rebuild_comparison_textures(ctx);
+    }
+
+    /// Selected tags as `apply_selection` would draw them, without pins or rotations
+    fn arranged_tags(&self, tags: &[Vec<Rgb<u8>>]) -> Vec<Vec<Rgb<u8>>> {
+        let mut tags = tags.to_vec();
+        reorder_wedges(&mut tags, self.wedge_order, self.color_space);
+        for tag in &mut tags {
+            if self.snap_to_grid {
+                for c in tag.iter_mut() {
+                    *c = snap_to_grid(*c);
+                }
+            }
+            if let Some(c) = self.orientation_rgb() {
+                tag.insert(ORIENTATION_WEDGE, c);
+            }
+        }
+        tags
+    }
+
+    /// Render the comparison tiles at preview resolution
+    fn rebuild_comparison_textures(&mut self, ctx: &Context) {
+        let (w, h) = self.preview_size();
+        let style = self.marker_style().scaled_for(w, self.save_size.0);
+        let serial_style = self.serial_style();
+        let pool = self.thread_pool.clone();
+        let Some(cmp) = &mut self.seed_comparison else { return };
+        let imgs: Vec<_> = pool.install(|| {
+            cmp.tags
+                .par_iter()
+                .enumerate()
+                .map(|(i, colors)| {
+                    let serial = serial_style.map(|(ha, va, c, b)| (i + 1, ha, va, c, b));
+                    DynamicImage::ImageRgb8(draw_marker_polygon(w, h, colors.len(), colors, &style, serial)).to_rgba8()
+                })
+                .collect()
+        });
+        cmp.textures = imgs
+            .iter()
+            .enumerate()
+            .map(|(i, rgba)| {
+                let image = ColorImage::from_rgba_unmultiplied([rgba.width() as usize, rgba.height() as usize], rgba);
+                ctx.load_texture(format!("seed_compare_{}", i), image, TextureOptions::LINEAR)
+            })
+            .collect();
+    }
+
+    /// Make the comparison seed the active one, taking over its tags without selecting again
+    fn adopt_compared_seed(&mut self, ctx: &Context) {
+        let Some(cmp) = self.seed_comparison.take() else { return };
+        let Some(selected) = cmp.selected else { return };
+        self.cancel_regen_job();
+        self.seed = cmp.seed;
+        self.apply_selection(selected);
+        self.rebuild_textures_quick(ctx);
+        self.set_status(format!("Adopted seed {}", self.seed), false);
+    }
+
+    /// Window with the current tags beside those of the comparison seed
+    fn draw_seed_comparison(&mut self, ctx: &Context) {
+        let Some(cmp) = &self.seed_comparison else { return };
+        let (space, weight) = (self.color_space, self.lightness_weight);
+        let mut open = true;
+        let (mut keep, mut adopt, mut rerun) = (false, false, false);
+        let mut seed_input = self.compare_seed_input.clone();
+        egui::Window::new("Compare seeds")
+            .open(&mut open)
+            .default_size(egui::vec2(SliderConfig::COMPARE_WINDOW_SIZE, SliderConfig::COMPARE_WINDOW_SIZE))
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Compare with seed");
+                    let edit = ui.add(egui::TextEdit::singleline(&mut seed_input).desired_width(180.0));
+                    rerun = edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    rerun |= ui.button("Run").clicked();
+                });
+                ui.separator();
+                let cmp_threshold = cmp.selected.as_ref().map_or(0.0, |s| s.threshold);
+                ui.columns(2, |cols| {
+                    keep = comparison_column(&mut cols[0], &format!("Seed {} (current)", self.seed), self.threshold, &self.tags, &self.textures, space, weight);
+                    adopt = comparison_column(&mut cols[1], &format!("Seed {}", cmp.seed), cmp_threshold, &cmp.tags, &cmp.textures, space, weight);
+                });
+            });
+        self.compare_seed_input = seed_input;
+        if adopt {
+            self.adopt_compared_seed(ctx);
+        } else if keep || !open {
+            self.cancel_seed_comparison();
+        } else if rerun {
+            match self.compare_seed_input.trim().parse::<u64>() {
+                Ok(seed) => self.start_seed_comparison(ctx, seed),
+                Err(_) => self.set_status(format!("`{}` is not a valid seed", self.compare_seed_input.trim()), true),
+            }
+        }
+    }
+
+    /// Window for editing the inspected tag: pick a wedge, then a pool color or any color
+    fn draw_tag_inspector(&mut self, ctx: &Context) {
+        let Some(index) = self.inspected_tag.filter(|&i| i < self.tags.len()) else {
+            self.inspected_tag = None;
+            return;
+        };
+        let colors = self.tags[index].clone();
+        let mut wedge = self.inspected_wedge.min(colors.len().saturating_sub(1));
+        let min_delta = self.tag_min_delta(index);
+        let pinned = self.pinned_tags.contains_key(&index);
+        let mut category = self.tag_categories.get(index).cloned().flatten().unwrap_or_default();
+        let mut category_changed = false;
+        let pool = &self.candidate_pool;
+        let mut open = true;
+        let mut picked: Option<Rgb<u8>> = None;
+        let mut unpin = false;
+        egui::Window::new(format!("Tag {}", index + 1))
+            .id(egui::Id::new("tag_inspector"))
+            .open(&mut open)
+            .resizable(false)
+            .default_width(SliderConfig::INSPECTOR_WIDTH)
+            .show(ctx, |ui| {
+                ui.label("Wedges:");
+                ui.horizontal_wrapped(|ui| {
+                    for (w, &c) in colors.iter().enumerate() {
+                        let resp = color_swatch(ui, c, SliderConfig::INSPECTOR_WEDGE_SWATCH, w == wedge)
+                            .on_hover_text(format!("Wedge {}: #{:02X}{:02X}{:02X}", w + 1, c[0], c[1], c[2]));
+                        if resp.clicked() {
+                            wedge = w;
+                        }
+                    }
+                });
+                ui.label(format!("Min ΔE within tag: {:.1}", min_delta));
+                ui.horizontal(|ui| {
+                    ui.label("Category:");
+                    category_changed = ui
+                        .add(egui::TextEdit::singleline(&mut category).hint_text("none").desired_width(SliderConfig::INSPECTOR_WIDTH * 0.6))
+                        .on_hover_text("Inventory set this tag belongs to; recorded in the manifest and optionally used as its save subfolder")
+                        .changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label(format!("Wedge {} color:", wedge + 1));
+                    let mut rgb = colors[wedge].0;
+                    if ui.color_edit_button_srgb(&mut rgb).changed() {
+                        picked = Some(Rgb(rgb));
+                    }
+                });
+                ui.label("From the candidate pool:");
+                egui::ScrollArea::vertical().max_height(SliderConfig::INSPECTOR_POOL_HEIGHT).show(ui, |ui| {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.spacing_mut().item_spacing = egui::Vec2::splat(2.0);
+                        for &c in pool {
+                            let resp = color_swatch(ui, c, SliderConfig::INSPECTOR_POOL_SWATCH, c == colors[wedge])
+                                .on_hover_text(format!("#{:02X}{:02X}{:02X}", c[0], c[1], c[2]));
+                            if resp.clicked() {
+                                picked = Some(c);
+                            }
+                        }
+                    });
+                });
+                if pinned {
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.colored_label(egui::Color32::from_rgb(230, 160, 40), "Edited: kept when regenerating");
+                        unpin = ui.button("Unpin").on_hover_text("Let the next regeneration replace this tag").clicked();
+                    });
+                }
+            });
+        self.inspected_wedge = wedge;
+        if category_changed {
+            self.set_tag_category(index, &category);
+        }
+        if let Some(c) = picked {
+            self.set_wedge_color(index, wedge, c);
+        }
+        if unpin {
+            self.pinned_tags.remove(&index);
+        }
+        if !open {
+            self.inspected_tag = None;
+        }
+    }
+
+    /// Set the category of tag `index`; blank text clears it
+    fn set_tag_category(&mut self, index: usize, text: &str) {
+        if self.tag_categories.len() < self.tags.len() {
+            self.tag_categories.resize(self.tags.len(), None);
+        }
+        let text = text.trim();
+        self.tag_categories[index] = (!text.is_empty()).then(|| text.to_string());
+    }
+
+    /// Selected tag indices in ascending order
+    pub fn selected_indices(&self) -> Vec<usize> {
+        let mut indices: Vec<usize> = self.selected_tags.iter().copied().filter(|&i| i < self.tags.len()).collect();
+        indices.sort_unstable();
+        indices
+    }
+
+    /// Handle a click on tile `index`: Ctrl/Cmd toggles it in the selection, Shift selects
+    /// the range from the last clicked tile (adding to it with Ctrl/Cmd), and a plain click
+    /// selects just this tile and opens it in the inspector
+    fn click_tile(&mut self, index: usize, modifiers: egui::Modifiers) {
+        if modifiers.shift {
+            let anchor = self.selection_anchor.unwrap_or(index);
+            if !modifiers.command {
+                self.selected_tags.clear();
+            }
+            // The range runs between the two tiles as the grid shows them
+            let order = self.grid_order();
+            let pos = |i: usize| order.iter().position(|&k| k == i).unwrap_or(0);
+            let (a, b) = (pos(anchor), pos(index));
+            self.selected_tags.extend(order[a.min(b)..=a.max(b)].iter().copied());
+            return;
+        }
+        self.selection_anchor = Some(index);
+        if modifiers.command {
+            if !self.selected_tags.remove(&index) {
+                self.selected_tags.insert(index);
+            }
+            return;
+        }
+        self.selected_tags = HashSet::from([index]);
+        self.inspected_tag = Some(index);
+        if self.preview_subject != index {
+            self.preview_subject = index;
+            self.schedule_regen(RegenKind::ImagesOnly, 0);
+        }
+    }
+
+    /// Pin (or unpin) every selected tag, so regeneration keeps (or replaces) it
+    fn lock_selected_tags(&mut self, lock: bool) {
+        for i in self.selected_indices() {
+            if lock {
+                self.pinned_tags.insert(i, self.tags[i].clone());
+            } else {
+                self.pinned_tags.remove(&i);
+            }
+        }
+    }
+
+    /// Remove the selected tags with their images, moving later tags (and their pins) up to
+    /// close the gaps. At least one tag is always kept.
+    fn delete_selected_tags(&mut self) {
+        let doomed = self.selected_indices();
+        if doomed.is_empty() {
+            return;
+        }
+        if doomed.len() >= self.tags.len() {
+            return self.set_status("Keep at least one tag; deselect one before deleting".into(), true);
+        }
+        for &i in doomed.iter().rev() {
+            self.tags.remove(i);
+            remove_at(&mut self.tag_rotations, i);
+            remove_at(&mut self.tag_categories, i);
+            remove_at(&mut self.unsnapped_tags, i);
+            remove_at(&mut self.high_res, i);
+            remove_at(&mut self.textures, i);
+            remove_at(&mut self.texture_keys, i);
+        }
+        self.pinned_tags = std::mem::take(&mut self.pinned_tags)
+            .into_iter()
+            .filter(|(i, _)| doomed.binary_search(i).is_err())
+            .map(|(i, colors)| (i - doomed.partition_point(|&d| d < i), colors))
+            .collect();
+        self.count = self.tags.len();
+        self.selected_tags.clear();
+        self.selection_anchor = None;
+        self.inspected_tag = None;
+        self.zoom_tag = None;
+        self.refresh_tag_stats();
+        self.schedule_regen(RegenKind::ImagesOnly, 0);
+        self.set_status(format!("Deleted {} tags; {} left", doomed.len(), self.tags.len()), false);
+    }
+
+    /// Grid of tag previews, `columns` wide. Only the rows in view are laid out, so frame
+    /// time does not grow with the tag count.
+    fn draw_tag_grid(&mut self, ui: &mut egui::Ui) {
+        let cols = self.columns.max(1);
+        let avail = ui.available_width();
+        let spacing = ui.spacing().item_spacing.x;
+        let tile_w = ((avail - spacing * ((cols as f32) - 1.0)) / (cols as f32))
+            .floor()
+            .max(32.0);
+        self.last_left_tile_w = tile_w;
+        let rows = self.textures.len().div_ceil(cols);
+        let order = self.grid_order();
+        egui::ScrollArea::vertical().show_rows(ui, tile_w, rows, |ui, visible| {
+            for row in visible {
+                ui.horizontal(|ui| {
+                    for pos in row * cols..((row + 1) * cols).min(self.textures.len()) {
+                        let i = order.get(pos).copied().filter(|&i| i < self.textures.len()).unwrap_or(pos);
+                        let tex = &self.textures[i];
+                        let resp = ui
+                            .add(egui::Image::new((tex.id(), egui::Vec2::new(tile_w, tile_w))).sense(egui::Sense::click()))
+                            .on_hover_text("Click to edit this tag's colors, Ctrl/Shift-click to select several, double-click to view it at full resolution");
+                        if self.pinned_tags.contains_key(&i) {
+                            let corner = resp.rect.left_top() + egui::vec2(8.0, 8.0);
+                            ui.painter().circle(corner, 5.0, egui::Color32::from_rgb(230, 160, 40), (1.0, egui::Color32::BLACK));
+                        }
+                        if let Some(Some(category)) = self.tag_categories.get(i) {
+                            draw_category_chip(ui.painter(), resp.rect, category);
+                        }
+                        if self.inspected_tag == Some(i) || self.selected_tags.contains(&i) {
+                            ui.painter().rect_stroke(resp.rect, 0.0, (2.0, ui.visuals().selection.stroke.color));
+                        }
+                        if resp.double_clicked() {
+                            self.zoom_tag = Some(i);
+                        }
+                        if resp.clicked() {
+                            let modifiers = ui.input(|input| input.modifiers);
+                            self.click_tile(i, modifiers);
+                        }
+                    }
+                });
+            }
+        });
+    }
+
+    /// Bar showing how much of the pool's capacity (`max_possible_count`) the tag count
+    /// uses, turning amber and then red as it approaches the limit
+    fn draw_capacity_gauge(&self, ui: &mut egui::Ui) {
+        let max = self.max_possible_count.max(1);
+        let fraction = self.count as f32 / max as f32;
+        let color = if fraction >= SliderConfig::CAPACITY_FULL {
+            egui::Color32::from_rgb(220, 70, 60)
+        } else if fraction >= SliderConfig::CAPACITY_TIGHT {
+            egui::Color32::from_rgb(220, 160, 40)
+        } else {
+            egui::Color32::from_rgb(70, 170, 90)
+        };
+        ui.add(egui::ProgressBar::new(fraction).desired_width(SliderConfig::CAPACITY_GAUGE_WIDTH).fill(color).text(format!("{} / {}", self.count, max)))
+            .on_hover_text(format!("{} of at most {} tags the candidate pool can fill at these settings. Shrinking the pool or adding wedges lowers the limit.", self.count, max));
+    }
+
+    /// Bar chart of `delta_histogram` with a red line at the current threshold
+    fn draw_delta_histogram(&self, ui: &mut egui::Ui) {
+        let (bin_w, counts) = &self.delta_histogram;
+        let width = ui.available_width().max(64.0);
+        let (rect, resp) = ui.allocate_exact_size(egui::Vec2::new(width, SliderConfig::DELTA_HISTOGRAM_HEIGHT), egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 4.0, ui.visuals().extreme_bg_color);
+        let peak = counts.iter().copied().max().unwrap_or(0);
+        if peak == 0 {
+            return;
+        }
+        let range = *bin_w * counts.len() as f32;
+        let bar_w = rect.width() / counts.len() as f32;
+        for (i, &c) in counts.iter().enumerate() {
+            let h = rect.height() * c as f32 / peak as f32;
+            let x = rect.left() + i as f32 * bar_w;
+            let bar = egui::Rect::from_min_max(egui::pos2(x + 0.5, rect.bottom() - h), egui::pos2(x + bar_w - 0.5, rect.bottom()));
+            painter.rect_filled(bar, 0.0, egui::Color32::from_rgb(110, 150, 200));
+        }
+        let tx = rect.left() + rect.width() * (self.threshold / range).clamp(0.0, 1.0);
+        painter.line_segment([egui::pos2(tx, rect.top()), egui::pos2(tx, rect.bottom())], (2.0, egui::Color32::RED));
+        let text = ui.visuals().text_color();
+        painter.text(rect.left_top() + egui::vec2(4.0, 2.0), egui::Align2::LEFT_TOP, format!("ΔE 0–{:.1}, peak {}", range, peak), egui::FontId::proportional(11.0), text);
+        if let Some(pos) = resp.hover_pos() {
+            let i = (((pos.x - rect.left()) / bar_w) as usize).min(counts.len() - 1);
+            resp.on_hover_text(format!("ΔE {:.1}–{:.1}: {} pairs", i as f32 * bin_w, (i + 1) as f32 * bin_w, counts[i]));
+        }
+    }
+
+    /// Outline of the center dot
+    pub fn center_shape(&self) -> CenterShape {
+        if self.center_polygon {
+            CenterShape::Polygon { sides: self.center_polygon_sides }
+        } else {
+            CenterShape::Circle
+        }
+    }
+
+    /// Appearance options shared by every rendered tag
+    pub fn marker_style(&self) -> MarkerStyle {
+        MarkerStyle {
+            center_dot: self.center_dot,
+            center_dot_size_pct: self.center_dot_size_pct,
+            center_dot_color: image::Rgb([self.center_dot_color.r(), self.center_dot_color.g(), self.center_dot_color.b()]),
+            center_ring: self.center_ring,
+            center_ring_inner_pct: self.center_ring_inner_pct,
+            gradient_dot: self.gradient_dot,
+            gradient_dot_size_pct: self.gradient_dot_size_pct,
+            gradient_dot_color: image::Rgb([self.gradient_dot_color.r(), self.gradient_dot_color.g(), self.gradient_dot_color.b()]),
+            gradient_dot_sigma_pct: self.gradient_dot_sigma_pct,
+            bg: image::Rgb([self.bg_color.r(), self.bg_color.g(), self.bg_color.b()]),
+            margin_pct: self.margin_pct,
+            fiducial_border: self.fiducial_border,
+            quiet_zone_pct: self.quiet_zone_pct,
+            wedge_gap_px: self.wedge_gap_px,
+            bounding_ring_px: if self.bounding_ring { self.bounding_ring_px } else { 0 },
+            bounding_ring_color: image::Rgb([self.bounding_ring_color.r(), self.bounding_ring_color.g(), self.bounding_ring_color.b()]),
+            signature_band_px: if self.signature_band { self.signature_band_px } else { 0 },
+            signature_band_color: (!self.signature_band_auto).then(|| image::Rgb([self.signature_band_color.r(), self.signature_band_color.g(), self.signature_band_color.b()])),
+            wedge_gradient: self.wedge_gradient,
+            dither_levels: self.dither.then_some(self.dither_levels),
+            min_wedge_px: self.min_wedge_px,
+            rotation_deg: 0.0,
+            shape: self.shape,
+            center_shape: self.center_shape(),
+        }
+    }
+
+    /// Generation inputs recorded in the manifest
+    pub fn generation_settings(&self) -> GenerationSettings {
+        GenerationSettings {
+            count: self.count,
+            wedges: self.wedges(),
+            sides_pattern: if self.uses_fit_palette() {
+                Some(self.tags.iter().map(Vec::len).collect())
+            } else {
+                self.uses_sides_pattern().then(|| self.sides_pattern.clone())
+            },
+            fit_palette: self.uses_fit_palette(),
+            seed: self.seed,
+            threshold_override: self.threshold_override,
+            threshold_search: self.threshold_search,
+            lightness_window: (SliderConfig::POOL_LIGHTNESS_MIN, SliderConfig::POOL_LIGHTNESS_MAX),
+            min_lightness_spread: self.min_lightness_spread,
+            pool_source: self.pool_source,
+            snap_to_grid: self.snap_to_grid,
+            grouping_iterations: SliderConfig::GROUPING_ITERATIONS,
+            wedge_order: self.wedge_order,
+            tag_order: TagSort::Generation,
+            orientation_wedge: self.orientation_rgb().map(|_| ORIENTATION_WEDGE),
+            orientation_rgb: self.orientation_rgb().map(|c| (c[0], c[1], c[2])),
+            color_space: self.color_space,
+            delta_e_formula: self.color_space.delta_e_formula().to_string(),
+            lightness_weight: self.lightness_weight,
+            center_dot: self.center_dot,
+            center_dot_size_pct: self.center_dot_size_pct,
+            center_ring: self.center_ring,
+            center_ring_inner_pct: self.center_ring_inner_pct,
+            center_shape: self.center_shape(),
+            gradient_dot: self.gradient_dot,
+            gradient_dot_size_pct: self.gradient_dot_size_pct,
+            gradient_dot_sigma_pct: self.gradient_dot_sigma_pct,
+        }
+    }
+
+    /// Output location, format, and render settings for the save functions
+    pub fn save_options(&self) -> SaveOptions {
+        SaveOptions {
+            out_root: self.output_dir.clone(),
+            format: self.image_format,
+            style: self.marker_style(),
+            marker_size: self.save_size,
+            similar_tag_distance: Some(self.similar_tag_distance),
+            color_space: self.color_space,
+            lightness_weight: self.lightness_weight,
+            sixteen_bit: self.saves_16bit(),
+            gutter_px: self.gutter_px,
+            tile_labels: self.tile_labels,
+            print_dpi: self.print_dpi,
+            bleed_mm: self.bleed_mm,
+            trim_marks: self.trim_marks,
+            registration_dots: self.registration_dots,
+            seed: self.seed,
+            embed_metadata: self.embed_metadata,
+            wedge_layers: self.wedge_layers,
+            categories: self.tag_categories.clone(),
+            category_folders: self.category_folders,
+            min_lightness_spread: self.min_lightness_spread,
+            manifest_format: self.manifest_format,
+            settings: self.generation_settings(),
+            tag_rotations: self.tag_rotations.clone(),
+            unsnapped: self.unsnapped_tags.clone(),
+        }
+    }
+
+    /// Serial number overlay (h_align, v_align, color, border), if enabled
+    pub fn serial_style(&self) -> Option<(f32, f32, Rgb<u8>, bool)> {
+        self.serial_numbers.then(|| {
+            let color = image::Rgb([self.serial_color.r(), self.serial_color.g(), self.serial_color.b()]);
+            (self.serial_h_align, self.serial_v_align, color, self.serial_border)
+        })
+    }
+
+    /// Colors per tag: the polygon side count, or the sector count for circular markers
+    pub fn wedges(&self) -> usize {
+        self.shape.wedges(self.sides)
+    }
+
+    /// Whether polygon tags take their side counts from `sides_pattern`
+    fn uses_sides_pattern(&self) -> bool {
+        self.mixed_sides && self.shape == MarkerShape::Polygon && !self.sides_pattern.is_empty()
+    }
+
+    /// Whether polygon tags vary their side counts to use the whole pool
+    fn uses_fit_palette(&self) -> bool {
+        self.fit_palette && self.shape == MarkerShape::Polygon
+    }
+
+    /// Colors the selection can draw from: the pool, less any too close to the orientation color
+    fn selectable_colors(&self) -> usize {
+        let Some(orientation) = self.orientation_rgb() else { return self.candidate_labs.len() };
+        let target = self.coords(orientation);
+        self.candidate_labs.iter().filter(|&&c| delta_e(c, target) >= self.min_usable_threshold).count()
+    }
+
+    /// Colors (wedges) of each of the first `count` tags
+    pub fn tag_sides(&self, count: usize) -> Vec<usize> {
+        if self.uses_fit_palette() {
+            fit_palette_sides(self.selectable_colors(), count, usize::from(self.orientation_wedge))
+        } else if self.uses_sides_pattern() {
+            self.sides_pattern.iter().copied().cycle().take(count).collect()
+        } else {
+            vec![self.wedges(); count]
+        }
+    }
+
+    /// Distinct colors selected for each of the first `count` tags: every wedge, less the
+    /// orientation wedge when that mode is on
+    fn group_sizes(&self, count: usize) -> Vec<usize> {
+        let reserved = usize::from(self.orientation_wedge);
+        self.tag_sides(count).into_iter().map(|s| s - reserved).collect()
+    }
+
+    /// Coordinates of `c` for selection-side ΔE: the color space with lightness weighted
+    fn coords(&self, c: Rgb<u8>) -> Lab {
+        self.color_space.weighted_coords(c, self.lightness_weight)
+    }
+
+    /// The fixed orientation color, when wedge `ORIENTATION_WEDGE` is reserved for it
+    pub fn orientation_rgb(&self) -> Option<Rgb<u8>> {
+        let c = self.orientation_color;
+        self.orientation_wedge.then_some(Rgb([c.r(), c.g(), c.b()]))
+    }
+
+    /// Candidate pool for color selection with its coordinates and ΔE matrix. In
+    /// orientation mode, colors closer than `min_usable_threshold` to the orientation
+    /// color are left out so the orientation wedge stays recognizable.
+    fn selection_pool(&self) -> SelectionPool<'_> {
+        let Some(orientation) = self.orientation_rgb() else {
+            return (Cow::Borrowed(&self.candidate_pool), Cow::Borrowed(&self.candidate_labs), Cow::Borrowed(&self.candidate_dm));
+        };
+        let target = self.coords(orientation);
+        let keep: Vec<usize> = (0..self.candidate_labs.len())
+            .filter(|&i| delta_e(self.candidate_labs[i], target) >= self.min_usable_threshold)
+            .collect();
+        let n = self.candidate_labs.len();
+        let dm = keep.iter().flat_map(|&i| keep.iter().map(move |&j| self.candidate_dm[i * n + j])).collect();
+        (
+            keep.iter().map(|&i| self.candidate_pool[i]).collect(),
+            keep.iter().map(|&i| self.candidate_labs[i]).collect(),
+            Cow::Owned(dm),
+        )
+    }
+
+    /// Side counts in fit-palette mode, e.g. "Sides: 5 (2 tags), 6 (3 tags) · all 28 colors"
+    fn fit_palette_summary(&self) -> String {
+        let sides = self.tag_sides(self.count);
+        let mut runs: Vec<(usize, usize)> = Vec::new();
+        for &s in &sides {
+            match runs.iter_mut().find(|r| r.0 == s) {
+                Some(r) => r.1 += 1,
+                None => runs.push((s, 1)),
+            }
+        }
+        runs.sort_unstable();
+        let runs: Vec<String> = runs.iter().map(|&(s, n)| format!("{} ({} {})", s, n, if n == 1 { "tag" } else { "tags" })).collect();
+        let available = self.selectable_colors();
+        let used = self.colors_needed(self.count).min(available);
+        let share = if used == available { format!("all {} colors", available) } else { format!("{} of {} colors", used, available) };
+        format!("Sides: {} · {}", runs.join(", "), share)
+    }
+
+    /// Colors needed to fill the first `count` tags, and at least one tag
+    fn colors_needed(&self, count: usize) -> usize {
+        self.group_sizes(count.max(1)).iter().sum()
+    }
+
+    /// Most tags that `available` colors can fill
+    fn tags_fillable(&self, available: usize) -> usize {
+        let mut used = 0;
+        let sizes = self.group_sizes(available);
+        sizes.iter().take_while(|&&s| { used += s; used <= available }).count()
+    }
+
+    /// Draw one rotation per tag when random rotation is on, from a stream derived from the
+    /// seed so that toggling it leaves the color selection untouched. Offsets stay below one
+    /// wedge so the first wedge still starts at (or just clockwise of) the top.
+    pub fn update_tag_rotations(&mut self) {
+        self.tag_rotations.clear();
+        if !self.random_rotation {
+            return;
+        }
+        let mut rng = StdRng::seed_from_u64(self.seed ^ 0x524f_5441_5445);
+        self.tag_rotations = self.tags.iter().map(|t| rng.gen_range(0.0..360.0 / t.len() as f32)).collect();
+    }
+
+    /// Settings for rendering the tags at save size
+    pub fn high_res_render(&self) -> HighResRender {
+        HighResRender {
+            size: self.save_size,
+            style: self.marker_style(),
+            serial_style: self.serial_style(),
+            rotations: self.tag_rotations.clone(),
+            sixteen_bit: self.saves_16bit(),
+        }
+    }
+
+    /// 16-bit output is only produced for formats that can hold it
+    fn saves_16bit(&self) -> bool {
+        self.export_16bit && self.image_format.supports_16bit()
+    }
+
+    pub fn render_high_res_images(&mut self) {
+        let t0 = Instant::now();
+        self.high_res.clear();
+        let render = self.high_res_render();
+        self.high_res = self.thread_pool.install(|| render.render(&self.tags, &|| true)).unwrap_or_default();
+        if self.profiling >= ProfileLevel::Verbose { println!("[profile] render_high_res_images: {:.2} ms (count={}, size={}x{})", t0.elapsed().as_secs_f64()*1000.0, self.tags.len(), self.save_size.0, self.save_size.1); }
+    }
+
+    /// Pixel size the preview tiles are rendered at (square), before being scaled to the grid
+    pub fn preview_size(&self) -> (u32, u32) {
+        let w = self.preview_max_width.max(2);
+        (w, w)
+    }
+
+    pub fn rebuild_textures_quick(&mut self, ctx: &Context) {
+        // Draw previews at the user-chosen resolution, display at tile size
+        let t0 = Instant::now();
+        let (w, h) = self.preview_size();
+        let style = self.marker_style().scaled_for(w, self.save_size.0);
+        let serial_style = self.serial_style();
+        let rotations = &self.tag_rotations;
+
+        // Everything a tile's pixels depend on; tiles whose key is unchanged keep their texture
+        let keys: Vec<u64> = self
+            .tags
+            .iter()
+            .enumerate()
+            .map(|(i, colors)| {
+                let mut hasher = DefaultHasher::new();
+                colors.iter().map(|c| c.0).collect::<Vec<_>>().hash(&mut hasher);
+                (w, h).hash(&mut hasher);
+                style.rotated(rotations.get(i).copied().unwrap_or(0.0)).hash(&mut hasher);
+                serial_style.map(|(ha, va, c, b)| (i + 1, ha.to_bits(), va.to_bits(), c.0, b)).hash(&mut hasher);
+                hasher.finish()
+            })
+            .collect();
+        let stale: Vec<usize> = (0..keys.len())
+            .filter(|&i| self.texture_keys.get(i) != Some(&keys[i]) || i >= self.textures.len())
+            .collect();
+        
+        let tags = &self.tags;
+        let imgs: Vec<_> = self.thread_pool.install(|| {
+            stale
+                .par_iter()
+                .map(|&i| {
+                    let serial = serial_style.map(|(ha, va, c, b)| (i + 1, ha, va, c, b));
+                    let style = style.rotated(rotations.get(i).copied().unwrap_or(0.0));
+                    let img = draw_marker_polygon(w, h, tags[i].len(), &tags[i], &style, serial);
+                    (i, DynamicImage::ImageRgb8(img).to_rgba8())
+                })
+                .collect()
+        });
+            
+        self.textures.truncate(keys.len());
+        for (i, rgba) in imgs.into_iter() {
+            let size = [rgba.width() as usize, rgba.height() as usize];
             let color_image = ColorImage::from_rgba_unmultiplied(size, &rgba);
             let tex = ctx.load_texture(format!("tag_preview_quick_{}", i), color_image, TextureOptions::LINEAR);
-            self.textures.push(tex);
+            if i < self.textures.len() {
+                self.textures[i] = tex;
+            } else {
+                self.textures.push(tex);
+            }
+        }
+        self.texture_keys = keys;
+        
+        // Also refresh right-panel previews and the seed comparison
+        self.rebuild_right_textures_quick(ctx);
+        self.rebuild_comparison_textures(ctx);
+        if self.profiling >= ProfileLevel::Verbose { println!("[profile] rebuild_textures_quick: {:.2} ms (left previews={}, rebuilt={}, render={}x{})", t0.elapsed().as_secs_f64()*1000.0, self.textures.len(), stale.len(), w, h); }
+    }
+
+    /// Blur sigmas of the distance-simulation panel as fractions of the preview width,
+    /// rising to `blur_max_sigma` and spaced more closely at the light end
+    pub fn blur_levels(&self) -> Vec<f32> {
+        let n = self.blur_level_count.max(1);
+        (1..=n).map(|i| self.blur_max_sigma * (i as f32 / n as f32).powf(1.3)).collect()
+    }
+
+    pub fn rebuild_right_textures_quick(&mut self, ctx: &Context) {
+        // Half-size monochrome for all tags, scaled variants for the preview subject, and blurred versions
+        self.right_mono_textures.clear();
+        self.right_first_scaled_textures.clear();
+
+        if self.tags.is_empty() {
+            self.right_blurred_textures.clear();
+            self.blur_source = None;
+            return;
+        }
+
+        // Use the user-chosen preview resolution as the base for right-panel previews
+        let base_w = self.preview_size().0;
+        let half_w = (base_w / 2).max(2);
+        let half_h = half_w;
+        
+        // Monochrome half-size for all tags
+        let t_mono = Instant::now();
+        let style = self.marker_style();
+        let serial_style = self.serial_style();
+        let mono_style = style.scaled_for(half_w, self.save_size.0);
+        let rotations = &self.tag_rotations;
+        
+        let mono_rgba: Vec<_> = self.thread_pool.install(|| {
+            self.tags
+                .par_iter()
+                .enumerate()
+                .map(|(i, colors)| {
+                    let serial = serial_style.map(|(ha, va, c, b)| (i + 1, ha, va, c, b));
+                    let mono_style = mono_style.rotated(rotations.get(i).copied().unwrap_or(0.0));
+                    let rgb = draw_marker_polygon(half_w, half_h, colors.len(), colors, &mono_style, serial);
+                    (i, DynamicImage::ImageRgb8(rgb).grayscale().to_rgba8())
+                })
+                .collect()
+        });
+            
+        for (i, rgba) in mono_rgba.into_iter() {
+            let size = [rgba.width() as usize, rgba.height() as usize];
+            let color_image = ColorImage::from_rgba_unmultiplied(size, &rgba);
+            let tex = ctx.load_texture(format!("right_mono_{}", i), color_image, TextureOptions::NEAREST);
+            self.right_mono_textures.push(tex);
+        }
+        if self.profiling >= ProfileLevel::Verbose { println!("[profile] \tright mono: {:.2} ms (count={}, size={}x{})", t_mono.elapsed().as_secs_f64()*1000.0, self.right_mono_textures.len(), half_w, half_h); }
+
+        // Preview subject at multiple scales; fall back to the last tag after the count drops
+        let subject = self.preview_subject.min(self.tags.len() - 1);
+        let first_colors = &self.tags[subject];
+        let style = style.rotated(self.tag_rotations.get(subject).copied().unwrap_or(0.0));
+        let t_scaled = Instant::now();
+        // Render once at the preview size and shrink in linear light, as distance would
+        let base_img = draw_marker_polygon(base_w, base_w, first_colors.len(), first_colors, &style.scaled_for(base_w, self.save_size.0), None);
+        for (k, s) in DISTANCE_SCALES.iter().enumerate() {
+            let w = ((base_w as f32) * s).round().max(2.0) as u32;
+            let h = w;
+            let img = downscale_linear(&base_img, w, h);
+            let rgba = DynamicImage::ImageRgb8(img).to_rgba8();
+            let size = [rgba.width() as usize, rgba.height() as usize];
+            let color_image = ColorImage::from_rgba_unmultiplied(size, &rgba);
+            let tex = ctx.load_texture(format!("right_first_scaled_{}", k), color_image, TextureOptions::NEAREST);
+            self.right_first_scaled_textures.push(tex);
+        }
+        if self.profiling >= ProfileLevel::Verbose { println!("[profile] \tright scaled: {:.2} ms (variants={}, base_w={})", t_scaled.elapsed().as_secs_f64()*1000.0, self.right_first_scaled_textures.len(), base_w); }
+
+        // Gaussian blur: render and blur at a smaller working size, then upscale to display size
+        let blur_dst_w = base_w.max(2);
+        let blur_src_w: u32 = blur_dst_w.clamp(16, 128); // cap work size for speed
+        let blur_src_h = blur_src_w;
+        let blur_style = style.scaled_for(blur_src_w, self.save_size.0);
+        let base_small = draw_marker_polygon(blur_src_w, blur_src_h, first_colors.len(), first_colors, &blur_style, None);
+        let blur_levels = self.blur_levels();
+
+        // Same source, size and levels: keep the finished (or still streaming) blurs
+        let unchanged = matches!(&self.blur_source, Some((w, levels, img)) if *w == blur_dst_w && *levels == blur_levels && *img == base_small);
+        if unchanged && self.right_blurred_textures.len() == blur_levels.len() {
+            if self.profiling >= ProfileLevel::Verbose { println!("[profile] \tright blur: unchanged, job not relaunched"); }
+            return;
+        }
+        self.blur_source = Some((blur_dst_w, blur_levels.clone(), base_small.clone()));
+        let base_small_dyn = DynamicImage::ImageRgb8(base_small);
+        
+        // Prepare placeholders so UI can show blanks immediately
+        self.right_blurred_textures = vec![None; blur_levels.len()];
+        
+        // Spawn async blur job to compute each level and stream results
+        self.blur_job_id = self.blur_job_id.wrapping_add(1);
+        let job_id = self.blur_job_id;
+        let (tx, rx) = mpsc::channel::<(u64, usize, image::RgbaImage)>();
+        self.blurred_rx = Some(rx);
+        let base_small_dyn_cloned = base_small_dyn.clone();
+        #[cfg(not(target_arch = "wasm32"))]
+        let paused = self.blur_paused.clone();
+        
+        spawn_worker(move || {
+            for (i, k) in blur_levels.iter().enumerate() {
+                // In the browser the levels are blurred right away, so there is nothing to pause
+                #[cfg(not(target_arch = "wasm32"))]
+                while paused.load(Ordering::Relaxed) {
+                    std::thread::sleep(Duration::from_millis(SliderConfig::BLUR_PAUSE_POLL_MS));
+                }
+                let sigma_full = (blur_dst_w as f32 * k).clamp(0.5, 300.0);
+                let scale = blur_src_w as f32 / blur_dst_w as f32;
+                let sigma_small = (sigma_full * scale).max(0.5);
+                let b_small = image::imageops::blur(&base_small_dyn_cloned, sigma_small);
+                let b_up: DynamicImage = DynamicImage::ImageRgba8(b_small).resize_exact(blur_dst_w, blur_dst_w, FilterType::Triangle);
+                let rgba = b_up.to_rgba8();
+                // A newer job replaced the receiver; stop blurring for this one
+                if tx.send((job_id, i, rgba)).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Show `message` in the status line; errors are drawn in red
+    pub fn set_status(&mut self, message: String, is_error: bool) {
+        self.status = Some((message, is_error, Instant::now()));
+    }
+
+    /// Status line for an export: "<what> saved to <path>" or "<what> failed: <error>"
+    fn report_export(&mut self, what: &str, result: Result<String, Box<dyn std::error::Error>>) {
+        match result {
+            Ok(path) => self.set_status(format!("{} saved to {}", what, path), false),
+            Err(e) => self.set_status(format!("{} failed: {}", what, e), true),
+        }
+    }
+
+    pub fn save_current_tags(&mut self) {
+        self.render_high_res_images();
+        let t0 = Instant::now();
+        let result = save_all(&self.tags, self.threshold, &self.high_res, &self.save_options(), &|| true);
+        if self.profiling >= ProfileLevel::Summary { println!("[profile] save_all: {:.2} ms (count={})", t0.elapsed().as_secs_f64()*1000.0, self.tags.len()); }
+        match result {
+            Ok(dir) => {
+                self.set_status(format!("Saved {} tags to {}", self.tags.len(), dir), false);
+                self.last_save_dir = Some(dir);
+            }
+            Err(e) => self.set_status(format!("Save failed: {}", e), true),
+        }
+    }
+
+    /// Write a ΔE heatmap of the candidate pool into a new output folder
+    pub fn save_candidate_heatmap(&mut self) {
+        let result = create_timestamped_dir(&self.output_dir)
+            .map_err(|e| e.into())
+            .and_then(|dir| save_delta_heatmap(&self.candidate_labs, &dir));
+        self.report_export("Heatmap", result);
+    }
+
+    /// Write a legend image (tag numbers and color swatches) into a new output folder
+    pub fn save_current_legend(&mut self) {
+        let result = create_timestamped_dir(&self.output_dir)
+            .map_err(|e| e.into())
+            .and_then(|dir| {
+                let path = format!("{}/legend.png", dir);
+                save_legend(&self.tags, &path).map(|_| path)
+            });
+        self.report_export("Legend", result);
+    }
+
+    /// Write the tag colors as an Adobe swatch file into a new output folder
+    pub fn save_current_ase(&mut self) {
+        let result = create_timestamped_dir(&self.output_dir)
+            .map_err(|e| e.into())
+            .and_then(|dir| {
+                let path = format!("{}/tags.ase", dir);
+                save_ase(&self.tags, &path).map(|_| path)
+            });
+        self.report_export("Swatches", result);
+    }
+
+    /// Write the tag colors as a GIMP palette into a new output folder
+    pub fn save_current_gpl(&mut self) {
+        let result = create_timestamped_dir(&self.output_dir)
+            .map_err(|e| e.into())
+            .and_then(|dir| {
+                let path = format!("{}/tags.gpl", dir);
+                save_gpl(&self.tags, &path).map(|_| path)
+            });
+        self.report_export("Palette", result);
+    }
+
+    /// Pack the tags at the save resolution into a texture atlas in a new output folder
+    pub fn save_current_atlas(&mut self) {
+        self.render_high_res_images();
+        let result = create_timestamped_dir(&self.output_dir)
+            .map_err(|e| e.into())
+            .and_then(|dir| save_atlas(&self.high_res, self.gutter_px, &dir));
+        self.report_export("Atlas", result);
+    }
+
+    /// Ask where to save a single exported file, starting in the output folder. None when
+    /// the dialog is cancelled. The native dialog asks before replacing an existing file.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_path_dialog(&mut self, file_name: &str, filter: &str, extensions: &[&str]) -> Option<std::path::PathBuf> {
+        let mut dialog = rfd::FileDialog::new().set_file_name(file_name).add_filter(filter, extensions);
+        if std::path::Path::new(&self.output_dir).is_dir() {
+            dialog = dialog.set_directory(&self.output_dir);
+        }
+        dialog.save_file()
+    }
+
+    /// Single-file exports write to a path, which the browser does not have
+    #[cfg(target_arch = "wasm32")]
+    fn save_path_dialog(&mut self, file_name: &str, _filter: &str, _extensions: &[&str]) -> Option<std::path::PathBuf> {
+        self.set_status(format!("Saving {} is only available in the desktop build; Save All downloads the tags", file_name), true);
+        None
+    }
+
+    /// Save tag `export_tag` at the save size to a file picked in a Save As dialog
+    pub fn save_single_tag(&mut self) {
+        let index = self.export_tag.saturating_sub(1);
+        let Some(colors) = self.tags.get(index).cloned() else {
+            return self.set_status(format!("There is no tag {}", self.export_tag), true);
+        };
+        let ext = self.image_format.extension();
+        let name = format!("tag_{:02}.{}", self.export_tag, ext);
+        let Some(path) = self.save_path_dialog(&name, "Images", &["png", "jpg", "jpeg", "bmp", "tif", "tiff"]) else {
+            return;
+        };
+        let img = self.high_res_render().render_one(index, &colors);
+        self.report_export(&format!("Tag {}", self.export_tag), save_single_image(&img, &path));
+    }
+
+    /// Write an animated GIF of tag `export_tag` turning once, to a file picked in a Save As dialog
+    pub fn save_spin_gif(&mut self) {
+        let Some(colors) = self.tags.get(self.export_tag.saturating_sub(1)).cloned() else {
+            return self.set_status(format!("There is no tag {}", self.export_tag), true);
+        };
+        let Some(path) = self.save_path_dialog(&format!("tag_{:02}_spin.gif", self.export_tag), "GIF", &["gif"]) else {
+            return;
+        };
+        let size = SliderConfig::SPIN_GIF_SIZE;
+        let style = self.marker_style().scaled_for(size, self.save_size.0);
+        let path = path.display().to_string();
+        let frames = self.spin_frames;
+        let result = self.thread_pool.install(|| save_rotating_gif(&colors, colors.len(), &style, size, frames, &path).map_err(|e| e.to_string()));
+        let result = result.map(|_| path).map_err(Into::into);
+        self.report_export("Spin GIF", result);
+    }
+
+    /// Write the wedge angles, corners and colors of tag `export_tag` as JSON, to a file
+    /// picked in a Save As dialog
+    pub fn save_wedge_spec_for_tag(&mut self) {
+        let index = self.export_tag.saturating_sub(1);
+        let Some(colors) = self.tags.get(index).cloned() else {
+            return self.set_status(format!("There is no tag {}", self.export_tag), true);
+        };
+        let Some(path) = self.save_path_dialog(&format!("tag_{:02}_wedges.json", self.export_tag), "JSON", &["json"]) else {
+            return;
+        };
+        let style = self.marker_style();
+        let rotation = style.rotation_deg + self.tag_rotations.get(index).copied().unwrap_or(0.0);
+        let path = path.display().to_string();
+        let result = save_wedge_spec(&colors, style.shape, colors.len(), rotation, &path).map(|_| path);
+        self.report_export("Wedge spec", result);
+    }
+
+    /// Write the preview subject's scaled-variant strip as a labeled PNG picked in a Save As dialog
+    pub fn save_distance_chart_for_subject(&mut self) {
+        let index = self.preview_subject.min(self.tags.len().saturating_sub(1));
+        let Some(colors) = self.tags.get(index).cloned() else { return };
+        let Some(path) = self.save_path_dialog(&format!("tag_{:02}_distance.png", index + 1), "PNG", &["png"]) else {
+            return;
+        };
+        let base_w = self.preview_size().0;
+        let style = self.marker_style()
+            .rotated(self.tag_rotations.get(index).copied().unwrap_or(0.0))
+            .scaled_for(base_w, self.save_size.0);
+        let path = path.display().to_string();
+        let result = save_distance_chart(&colors, colors.len(), &style, base_w, &DISTANCE_SCALES, &path).map(|_| path);
+        self.report_export("Distance chart", result);
+    }
+
+    /// Put the combined sheet, as Save All Together would write it, on the clipboard without
+    /// saving anything. Deferred high-res images are rendered first.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn copy_combined_to_clipboard(&mut self) {
+        if self.tags.is_empty() {
+            return self.set_status("No tags to copy".into(), true);
+        }
+        if self.high_res.len() != self.tags.len() {
+            self.render_high_res_images();
+        }
+        let result = build_combined_image(&self.high_res, Some(self.columns), &self.save_options(), &|| true).and_then(|sheet| {
+            if self.clipboard.is_none() {
+                self.clipboard = Some(arboard::Clipboard::new()?);
+            }
+            let clipboard = self.clipboard.as_mut().expect("clipboard was just opened");
+            copy_image_to_clipboard(clipboard, &sheet.image)?;
+            Ok(sheet.image.dimensions())
+        });
+        match result {
+            Ok((w, h)) => self.set_status(format!("Copied the combined sheet ({}×{}) to the clipboard", w, h), false),
+            Err(e) => self.set_status(format!("Could not copy the combined sheet: {}", e), true),
         }
-        
-        // Also refresh right-panel previews
-        self.rebuild_right_textures_quick(ctx);
-        if self.profiling { println!("[profile] rebuild_textures_quick: {:.2} ms (left previews={}, render={}x{})", t0.elapsed().as_secs_f64()*1000.0, self.textures.len(), w, h); }
     }
 
-    pub fn rebuild_right_textures_quick(&mut self, ctx: &Context) {
-        // Half-size monochrome for all tags, scaled variants for first tag, and blurred versions
-        self.right_mono_textures.clear();
-        self.right_first_scaled_textures.clear();
-        self.right_blurred_textures.clear();
+    #[cfg(target_arch = "wasm32")]
+    pub fn copy_combined_to_clipboard(&mut self) {
+        self.set_status("Copying images is only available in the desktop build; Save All Together downloads the sheet".into(), true);
+    }
 
-        if self.tags.is_empty() {
-            return;
+    /// Check the images of the last save against its manifest and report the result
+    pub fn verify_last_save(&mut self) {
+        let Some(dir) = self.last_save_dir.clone() else { return };
+        let report = match verify_output(&dir) {
+            Ok(report) => report,
+            Err(e) => return self.set_status(format!("Verify failed: {}", e), true),
+        };
+        let skipped = if report.skipped.is_empty() { String::new() } else { format!(", {} tags skipped", report.skipped.len()) };
+        if report.mismatches.is_empty() {
+            self.set_status(format!("Verified {} tags ({} wedges): all colors match{}", report.tags_checked, report.wedges_checked, skipped), !report.skipped.is_empty());
+        } else {
+            let first: Vec<String> = report
+                .mismatches
+                .iter()
+                .take(3)
+                .map(|m| {
+                    let hex = |c: Rgb<u8>| format!("#{:02x}{:02x}{:02x}", c[0], c[1], c[2]);
+                    format!("tag {} wedge {} is {} not {} (ΔE {:.1})", m.tag, m.wedge, hex(m.found), hex(m.expected), m.delta_e)
+                })
+                .collect();
+            self.set_status(format!("{} of {} wedges do not match: {}{}", report.mismatches.len(), report.wedges_checked, first.join(", "), skipped), true);
         }
+    }
 
-        // Use the user-chosen preview resolution as the base for right-panel previews
-        let base_w = self.preview_max_width.max(2);
-        let half_w = (base_w / 2).max(2);
-        let half_h = half_w;
-        
-        // Monochrome half-size for all tags
-        let t_mono = Instant::now();
-        let sides = self.sides;
-        let center_dot = self.center_dot;
-        let center_dot_size_pct = self.center_dot_size_pct;
-        let gradient_dot = self.gradient_dot;
-        let gradient_dot_size_pct = self.gradient_dot_size_pct;
-        let bg = image::Rgb([self.bg_color.r(), self.bg_color.g(), self.bg_color.b()]);
-        let serial_numbers = self.serial_numbers;
-        let serial_h_align = self.serial_h_align;
-        let serial_v_align = self.serial_v_align;
-        let serial_color = image::Rgb([self.serial_color.r(), self.serial_color.g(), self.serial_color.b()]);
-        let serial_border = self.serial_border;
-        
-        let mono_rgba: Vec<_> = self
-            .tags
-            .par_iter()
-            .enumerate()
-            .map(|(i, colors)| {
-                let serial = if serial_numbers { Some((i + 1, serial_h_align, serial_v_align, serial_color, serial_border)) } else { None };
-                let rgb = draw_marker_polygon(half_w, half_h, sides, colors, center_dot, center_dot_size_pct, gradient_dot, gradient_dot_size_pct, bg, serial);
-                (i, DynamicImage::ImageRgb8(rgb).grayscale().to_rgba8())
-            })
-            .collect();
-            
-        for (i, rgba) in mono_rgba.into_iter() {
-            let size = [rgba.width() as usize, rgba.height() as usize];
-            let color_image = ColorImage::from_rgba_unmultiplied(size, &rgba);
-            let tex = ctx.load_texture(format!("right_mono_{}", i), color_image, TextureOptions::NEAREST);
-            self.right_mono_textures.push(tex);
+    pub fn reveal_last_save(&mut self) {
+        let Some(dir) = self.last_save_dir.clone() else { return };
+        if let Err(e) = reveal_in_file_manager(&dir) {
+            self.set_status(format!("Could not open {}: {}", dir, e), true);
         }
-        if self.profiling { println!("[profile] \tright mono: {:.2} ms (count={}, size={}x{})", t_mono.elapsed().as_secs_f64()*1000.0, self.right_mono_textures.len(), half_w, half_h); }
+    }
 
-        // First tag at multiple scales
-        let first_colors = &self.tags[0];
-        let scales: [f32; 18] = [
-            0.5, 0.4, 0.3, 0.2, 0.15, 0.14, 0.13, 0.12, 0.1,
-            0.09, 0.08, 0.07, 0.06, 0.05, 0.04, 0.03, 0.02, 0.01,
-        ];
-        let t_scaled = Instant::now();
-        for (k, s) in scales.iter().enumerate() {
-            let w = ((base_w as f32) * s).round().max(2.0) as u32;
-            let h = w;
-            let bg = image::Rgb([self.bg_color.r(), self.bg_color.g(), self.bg_color.b()]);
-            let img = draw_marker_polygon(w, h, self.sides, first_colors, self.center_dot, self.center_dot_size_pct, self.gradient_dot, self.gradient_dot_size_pct, bg, None);
-            let rgba = DynamicImage::ImageRgb8(img).to_rgba8();
-            let size = [rgba.width() as usize, rgba.height() as usize];
-            let color_image = ColorImage::from_rgba_unmultiplied(size, &rgba);
-            let tex = ctx.load_texture(format!("right_first_scaled_{}", k), color_image, TextureOptions::NEAREST);
-            self.right_first_scaled_textures.push(tex);
+    /// Pick an earlier Save All Separate folder and write only the tags missing from it.
+    /// Existing tags of another size are kept (with a warning) unless overwriting is on.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn resume_save_dialog(&mut self) {
+        let mut dialog = rfd::FileDialog::new();
+        if std::path::Path::new(&self.output_dir).is_dir() {
+            dialog = dialog.set_directory(&self.output_dir);
         }
-        if self.profiling { println!("[profile] \tright scaled: {:.2} ms (variants={}, base_w={})", t_scaled.elapsed().as_secs_f64()*1000.0, self.right_first_scaled_textures.len(), base_w); }
+        let Some(dir) = dialog.pick_folder() else { return };
+        self.resume_save_in(&dir.display().to_string());
+    }
 
-        // Gaussian blur: render and blur at a smaller working size, then upscale to display size
-        let blur_dst_w = base_w.max(2);
-        let blur_src_w: u32 = blur_dst_w.clamp(16, 128); // cap work size for speed
-        let blur_src_h = blur_src_w;
-        let bg = image::Rgb([self.bg_color.r(), self.bg_color.g(), self.bg_color.b()]);
-        let base_small = draw_marker_polygon(blur_src_w, blur_src_h, self.sides, first_colors, self.center_dot, self.center_dot_size_pct, self.gradient_dot, self.gradient_dot_size_pct, bg, None);
-        let base_small_dyn = DynamicImage::ImageRgb8(base_small);
-        let blur_levels: [f32; 6] = [0.03, 0.06, 0.10, 0.16, 0.22, 0.30];
-        
-        // Prepare placeholders so UI can show blanks immediately
-        self.right_blurred_textures = vec![None; blur_levels.len()];
-        
-        // Spawn async blur job to compute each level and stream results
-        self.blur_job_id = self.blur_job_id.wrapping_add(1);
-        let job_id = self.blur_job_id;
-        let (tx, rx) = mpsc::channel::<(u64, usize, image::RgbaImage)>();
-        self.blurred_rx = Some(rx);
-        let base_small_dyn_cloned = base_small_dyn.clone();
-        
-        thread::spawn(move || {
-            for (i, k) in blur_levels.iter().enumerate() {
-                let sigma_full = (blur_dst_w as f32 * k).clamp(0.5, 300.0);
-                let scale = blur_src_w as f32 / blur_dst_w as f32;
-                let sigma_small = (sigma_full * scale).max(0.5);
-                let b_small = image::imageops::blur(&base_small_dyn_cloned, sigma_small);
-                let b_up: DynamicImage = DynamicImage::ImageRgba8(b_small).resize_exact(blur_dst_w, blur_dst_w, FilterType::Triangle);
-                let rgba = b_up.to_rgba8();
-                let _ = tx.send((job_id, i, rgba));
-            }
-        });
+    #[cfg(target_arch = "wasm32")]
+    pub fn resume_save_dialog(&mut self) {
+        self.start_save_job(SaveKind::Resume);
     }
 
-    pub fn save_current_tags(&mut self) {
-        self.render_high_res_images();
-        if let Err(e) = save_all(&self.tags, self.threshold, &self.high_res, self.sides) {
-            eprintln!("Save failed: {}", e);
+    pub fn resume_save_in(&mut self, dir: &str) {
+        let scan = match scan_output(dir, self.image_format) {
+            Ok(scan) => scan,
+            Err(e) => return self.set_status(format!("Could not read {}: {}", dir, e), true),
+        };
+        let render = self.high_res_render();
+        let expected = render.style.output_size(render.size.0, render.size.1);
+        let mismatched: Vec<usize> = scan.existing.iter().filter(|(i, size)| *i < self.tags.len() && *size != expected).map(|(i, _)| *i).collect();
+        let missing: Vec<usize> = (0..self.tags.len())
+            .filter(|i| !scan.existing.iter().any(|(k, _)| k == i) || (self.resume_overwrite_mismatched && mismatched.contains(i)))
+            .collect();
+        let mut note = if scan.has_manifest { String::new() } else { " (no manifest found, rebuilt it)".to_string() };
+        if !mismatched.is_empty() && !self.resume_overwrite_mismatched {
+            note.push_str(&format!("; kept {} existing tags that are not {}×{}", mismatched.len(), expected.0, expected.1));
+        }
+        if missing.is_empty() {
+            return self.set_status(format!("Nothing to resume: all {} tags are already in {}{}", self.tags.len(), dir, note), !note.is_empty());
         }
+        self.resume_plan = Some((dir.to_string(), missing, note));
+        self.start_save_job(SaveKind::Resume);
     }
 
-    pub fn save_current_tags_together(&mut self) {
-        self.render_high_res_images();
-        if let Err(e) = save_all_together(&self.tags, self.threshold, &self.high_res, self.sides) {
-            eprintln!("Save together failed: {}", e);
+    /// Render and save on a worker thread, reporting progress to the UI. Rendering and
+    /// writing each count for one step per tag.
+    pub fn start_save_job(&mut self, kind: SaveKind) {
+        if self.save_job.is_some() {
+            return;
+        }
+        // These write into folders, which the browser does not have
+        #[cfg(target_arch = "wasm32")]
+        if matches!(kind, SaveKind::MultiSize | SaveKind::Resume) {
+            return self.set_status("Multi-size and resumed saves are only available in the desktop build".into(), true);
+        }
+        #[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+        let (resume_dir, missing, note) = match kind {
+            SaveKind::Resume => match self.resume_plan.take() {
+                Some(plan) => plan,
+                None => return,
+            },
+            _ => Default::default(),
+        };
+        let mut tags = self.tags.clone();
+        let threshold = self.threshold;
+        let mut opts = self.save_options();
+        let mut render = self.high_res_render();
+        if let Some(indices) = self.save_order(kind) {
+            tags = pick(&tags, &indices);
+            opts.tag_rotations = pick(&opts.tag_rotations, &indices);
+            opts.unsnapped = pick(&opts.unsnapped, &indices);
+            opts.categories = pick(&opts.categories, &indices);
+            opts.settings.count = tags.len();
+            if opts.settings.fit_palette {
+                opts.settings.sides_pattern = Some(tags.iter().map(Vec::len).collect());
+            }
+            render.rotations = pick(&render.rotations, &indices);
+        }
+        if self.sort_saves {
+            opts.settings.tag_order = self.tag_sort;
+        }
+        let profiling = self.profiling >= ProfileLevel::Summary;
+        let columns = self.columns;
+        let sizes = if kind == SaveKind::MultiSize {
+            match parse_size_list(&self.multi_sizes) {
+                Ok(sizes) => sizes,
+                Err(e) => {
+                    self.set_status(format!("Multi-export sizes: {}", e), true);
+                    return;
+                }
+            }
+        } else {
+            Vec::new()
+        };
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel::<SaveMessage>();
+        let count = if kind == SaveKind::Resume { missing.len() } else { tags.len() };
+        self.save_job = Some(SaveJob { kind, count, note, progress: 0.0, rx, cancel: cancel.clone() });
+
+        let pool = self.thread_pool.clone();
+        spawn_worker(move || {
+            let t0 = Instant::now();
+            let passes = sizes.len().max(1);
+            let total = (count * 2 * passes).max(1) as f32;
+            let done = AtomicUsize::new(0);
+            let step = || {
+                let n = done.fetch_add(1, Ordering::Relaxed) + 1;
+                let _ = tx.send(SaveMessage::Progress(n as f32 / total));
+                !cancel.load(Ordering::Relaxed)
+            };
+            #[cfg(not(target_arch = "wasm32"))]
+            let result = pool.install(|| {
+                match kind {
+                    SaveKind::MultiSize => save_multi_size(&tags, threshold, &render, &opts, &sizes, &step),
+                    SaveKind::Resume => resume_save(&resume_dir, &tags, threshold, &render, &opts, &missing, &step),
+                    _ => match render.render(&tags, &step) {
+                        None => Err(SAVE_CANCELLED.into()),
+                        Some(images) if kind == SaveKind::Together => save_all_together(&tags, threshold, &images, Some(columns), &opts, &step),
+                        Some(images) => save_all(&tags, threshold, &images, &opts, &step),
+                    },
+                }
+                .map_err(|e| e.to_string())
+            });
+            // No file system in the browser: the files become downloads
+            #[cfg(target_arch = "wasm32")]
+            let result = pool.install(|| {
+                match render.render(&tags, &step) {
+                    None => Err(SAVE_CANCELLED.into()),
+                    Some(images) if kind == SaveKind::Together => download_all_together(&tags, threshold, &images, Some(columns), &opts, &step),
+                    Some(images) => download_all(&tags, threshold, &images, &opts, &step),
+                }
+                .map_err(|e| e.to_string())
+            });
+            if profiling { println!("[profile] save job {:?}: {:.2} ms (count={}, passes={})", kind, t0.elapsed().as_secs_f64()*1000.0, tags.len(), passes); }
+            let _ = tx.send(SaveMessage::Finished(result));
+        });
+    }
+
+    /// Drain messages from the background save and report its outcome when it ends
+    fn poll_save_job(&mut self, ctx: &Context) {
+        let Some(job) = &mut self.save_job else { return };
+        let mut finished = None;
+        loop {
+            match job.rx.try_recv() {
+                Ok(SaveMessage::Progress(p)) => job.progress = p,
+                Ok(SaveMessage::Finished(result)) => finished = Some(result),
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    finished.get_or_insert_with(|| Err("worker stopped unexpectedly".into()));
+                    break;
+                }
+            }
+        }
+        let Some(result) = finished else {
+            ctx.request_repaint_after(Duration::from_millis(50));
+            return;
+        };
+        let (kind, count) = (job.kind, job.count);
+        let note = std::mem::take(&mut job.note);
+        self.save_job = None;
+        if let (Ok(dir), SaveKind::Separate | SaveKind::Together | SaveKind::Resume | SaveKind::Selected) = (&result, kind) {
+            self.last_save_dir = Some(dir.clone());
+        }
+        match (result, kind) {
+            (Ok(dir), SaveKind::Separate) => self.set_status(format!("Saved {} tags to {}", count, dir), false),
+            (Ok(dir), SaveKind::Together) => self.set_status(format!("Saved combined sheet of {} tags to {}", count, dir), false),
+            (Ok(dir), SaveKind::MultiSize) => self.set_status(format!("Saved {} tags at each size to {}", count, dir), false),
+            (Ok(dir), SaveKind::Resume) => self.set_status(format!("Resumed {}: wrote {} missing tags{}", dir, count, note), !note.is_empty()),
+            (Ok(dir), SaveKind::Selected) => self.set_status(format!("Saved {} selected tags to {}", count, dir), false),
+            (Err(e), _) if e == SAVE_CANCELLED => self.set_status("Save cancelled".into(), true),
+            (Err(e), SaveKind::Separate | SaveKind::Selected) => self.set_status(format!("Save failed: {}", e), true),
+            (Err(e), SaveKind::Together) => self.set_status(format!("Save together failed: {}", e), true),
+            (Err(e), SaveKind::MultiSize) => self.set_status(format!("Multi-export failed: {}", e), true),
+            (Err(e), SaveKind::Resume) => self.set_status(format!("Resume failed: {}", e), true),
         }
+        ctx.request_repaint();
     }
 }
 
 impl eframe::App for AppState {
     fn update(&mut self, ctx: &Context, _frame: &mut eframe::Frame) {
+        // In the background, skip animation, blurs and preview rebuilds; regaining focus
+        // repaints and picks up whatever was deferred
+        let focused = ctx.input(|i| i.focused);
+        self.blur_paused.store(!focused, Ordering::Relaxed);
+
         // Keep animating placeholders if any blurred textures are still loading
-        if self.right_blurred_textures.iter().any(|t| t.is_none()) {
+        if focused && self.right_blurred_textures.iter().any(|t| t.is_none()) {
             ctx.request_repaint_after(Duration::from_millis(16)); 
         }
         
@@ -499,10 +2904,23 @@ impl eframe::App for AppState {
             }
         }
         
-        // Debounced regeneration handler
-        if let (Some(kind), Some(deadline)) = (self.pending_regen, self.regen_deadline) {
+        self.poll_regen_job(ctx);
+        self.poll_seed_comparison(ctx);
+        self.poll_save_job(ctx);
+        self.refresh_max_possible_count();
+
+        // A palette or image dropped on the window replaces the candidate pool
+        let dropped = ctx.input(|i| i.raw.dropped_files.iter().find_map(|f| f.path.clone()));
+        if let Some(path) = dropped {
+            self.pool_from_dropped_file(&path);
+        }
+
+        // Debounced regeneration handler. Full regenerations run in the background too (they
+        // are a worker job); preview-only rebuilds wait for focus
+        let runnable = self.pending_regen.filter(|&kind| focused || kind == RegenKind::Full);
+        if let (Some(kind), Some(deadline)) = (runnable, self.regen_deadline) {
             if Instant::now() >= deadline {
-                if self.profiling { println!("[profile] update: run scheduled {:?}", kind); }
+                if self.profiling >= ProfileLevel::Verbose { println!("[profile] update: run scheduled {:?}", kind); }
                 match kind {
                     RegenKind::Full => self.regenerate(ctx),
                     RegenKind::ImagesOnly => self.rebuild_textures_quick(ctx),
@@ -530,27 +2948,138 @@ impl eframe::App for AppState {
                     ui.horizontal_wrapped(|ui| {
                         ui.spacing_mut().item_spacing.x = 8.0;
                         ui.label("Tags:");
+                        // Logarithmic, so low counts keep room on the slider however large the
+                        // pool's capacity; the buttons step by one
                         let mut count_i = self.count as i32;
-                        let max_count = self.max_possible_count as i32;
-                        if ui.add(egui::Slider::new(&mut count_i, SliderConfig::COUNT_MIN..=max_count)).changed() {
-                            let new_count = count_i as usize;
-                            if new_count != self.count {
-                                self.count = new_count;
+                        let max_count = (self.max_possible_count as i32).max(SliderConfig::COUNT_MIN);
+                        let count_resp = ui.add(egui::Slider::new(&mut count_i, SliderConfig::COUNT_MIN..=max_count).logarithmic(true));
+                        if ui.add_enabled(count_i > SliderConfig::COUNT_MIN, egui::Button::new("−").small()).on_hover_text("One tag fewer").clicked() {
+                            count_i -= 1;
+                        }
+                        if ui.add_enabled(count_i < max_count, egui::Button::new("+").small()).on_hover_text("One tag more").clicked() {
+                            count_i += 1;
+                        }
+                        let mut new_count = count_i as usize;
+                        if self.hold_threshold_floor && self.threshold_override.is_none() && new_count > self.count {
+                            new_count = self.max_count_above_floor(self.count, new_count);
+                        }
+                        if new_count != self.count {
+                            self.count = new_count;
+                            // While dragging only the estimate below updates; regenerate on release
+                            if !count_resp.dragged() {
                                 self.schedule_regen(RegenKind::Full, 200);
                             }
                         }
-                        ui.label(format!("(max: {})", self.max_possible_count));
+                        if count_resp.drag_stopped() {
+                            self.schedule_regen(RegenKind::Full, 0);
+                        }
+                        self.draw_capacity_gauge(ui);
+                        ui.label(format!("pool: {} colors", self.candidate_pool.len())).on_hover_text(format!(
+                            "Candidates left after keeping Lab L within {:.0}–{:.0}; together with the threshold this sets how many tags fit",
+                            SliderConfig::POOL_LIGHTNESS_MIN,
+                            SliderConfig::POOL_LIGHTNESS_MAX
+                        ));
+                        if count_resp.dragged() && self.threshold_override.is_none() {
+                            let estimate = self.estimate_threshold(self.count);
+                            ui.label(format!("≈ΔE {:.1}", estimate))
+                                .on_hover_text("Estimated max ΔE for this many tags; regenerates on release");
+                        }
                         ui.separator();
-                        ui.label("Sides:");
-                        let mut sides_i = self.sides as i32;
-                        if ui.add(egui::Slider::new(&mut sides_i, SliderConfig::SIDES_MIN..=SliderConfig::SIDES_MAX)).changed() {
-                            let new_sides = sides_i as usize;
-                            if new_sides != self.sides {
-                                self.sides = new_sides;
+                        let mut circle = matches!(self.shape, MarkerShape::Sectors { .. });
+                        if circle {
+                            ui.label("Sectors:");
+                            let mut n = self.sector_count as i32;
+                            if ui.add(egui::Slider::new(&mut n, SliderConfig::SECTORS_MIN..=SliderConfig::SECTORS_MAX)).changed() && n as usize != self.sector_count {
+                                self.sector_count = n as usize;
+                                self.shape = MarkerShape::Sectors { count: self.sector_count };
                                 self.update_max_possible_count();
                                 self.count = self.count.min(self.max_possible_count);
                                 self.schedule_regen(RegenKind::Full, 200);
                             }
+                        } else if self.fit_palette {
+                            ui.label(self.fit_palette_summary()).on_hover_text("Every tag takes an even share of the pool; raise or lower the tag count to change the side counts");
+                        } else if self.mixed_sides {
+                            ui.label("Sides:");
+                            let resp = ui.add(egui::TextEdit::singleline(&mut self.sides_pattern_text).desired_width(80.0))
+                                .on_hover_text("Comma-separated side counts, assigned to the tags round-robin (\"3, 4\" alternates triangles and squares)");
+                            if resp.changed() {
+                                match parse_sides_pattern(&self.sides_pattern_text) {
+                                    Ok(pattern) => {
+                                        if pattern != self.sides_pattern {
+                                            self.sides_pattern = pattern;
+                                            self.update_max_possible_count();
+                                            self.count = self.count.min(self.max_possible_count);
+                                            self.schedule_regen(RegenKind::Full, 300);
+                                        }
+                                    }
+                                    Err(e) => self.set_status(format!("Side pattern: {}", e), true),
+                                }
+                            }
+                        } else {
+                            ui.label("Sides:");
+                            let mut sides_i = self.sides as i32;
+                            if ui.add(egui::Slider::new(&mut sides_i, SliderConfig::SIDES_MIN..=SliderConfig::SIDES_MAX)).changed() {
+                                let new_sides = sides_i as usize;
+                                if new_sides != self.sides {
+                                    self.sides = new_sides;
+                                    self.update_max_possible_count();
+                                    self.count = self.count.min(self.max_possible_count);
+                                    self.schedule_regen(RegenKind::Full, 200);
+                                }
+                            }
+                        }
+                        if !circle && !self.fit_palette && ui.checkbox(&mut self.mixed_sides, "mixed").on_hover_text("Give tags different side counts following a pattern").changed() {
+                            self.update_max_possible_count();
+                            self.count = self.count.min(self.max_possible_count);
+                            self.schedule_regen(RegenKind::Full, 0);
+                        }
+                        if !circle && ui.checkbox(&mut self.fit_palette, "fit palette")
+                            .on_hover_text("Use every pool color across exactly the tag count, giving tags as even a share as possible (some one side more than others)")
+                            .changed()
+                        {
+                            self.update_max_possible_count();
+                            self.count = self.count.min(self.max_possible_count);
+                            self.schedule_regen(RegenKind::Full, 0);
+                        }
+                        if ui.checkbox(&mut circle, "circle").on_hover_text("Draw a circle of equal pie sectors instead of a polygon; the sector count sets the colors per tag").changed() {
+                            self.shape = if circle { MarkerShape::Sectors { count: self.sector_count } } else { MarkerShape::Polygon };
+                            self.update_max_possible_count();
+                            self.count = self.count.min(self.max_possible_count);
+                            self.schedule_regen(RegenKind::Full, 0);
+                        }
+                        ui.separator();
+                        ui.label("Order:");
+                        let mut order = self.wedge_order;
+                        egui::ComboBox::from_id_source("wedge_order")
+                            .selected_text(order.label())
+                            .show_ui(ui, |ui| {
+                                for o in WedgeOrder::ALL {
+                                    ui.selectable_value(&mut order, o, o.label());
+                                }
+                            })
+                            .response
+                            .on_hover_text("Wedge order within each tag: as grouped, bright/dark alternating, or a hue wheel");
+                        if order != self.wedge_order {
+                            // Regroup with the same seed so the original order can be restored
+                            self.wedge_order = order;
+                            self.schedule_regen(RegenKind::Full, 0);
+                        }
+                        let orientation_changed = ui.checkbox(&mut self.orientation_wedge, "orientation wedge")
+                            .on_hover_text("Give wedge 1 of every tag the same fixed color so detectors can recover rotation; the other wedges tell tags apart")
+                            .changed();
+                        let color_changed = self.orientation_wedge && ui.color_edit_button_srgba(&mut self.orientation_color).changed();
+                        if orientation_changed || color_changed {
+                            self.update_max_possible_count();
+                            self.count = self.count.min(self.max_possible_count);
+                            self.schedule_regen(RegenKind::Full, 200);
+                        }
+                        ui.separator();
+                        ui.label("Min L spread:");
+                        if ui.add(egui::Slider::new(&mut self.min_lightness_spread, SliderConfig::LIGHTNESS_SPREAD_MIN..=SliderConfig::LIGHTNESS_SPREAD_MAX).step_by(1.0))
+                            .on_hover_text("Grouping avoids tags whose lightest and darkest colors differ by less than this Lab L, so they stay distinct in grayscale")
+                            .changed()
+                        {
+                            self.schedule_regen(RegenKind::Full, 200);
                         }
                     });
                     ui.add_space(2.0);
@@ -568,6 +3097,38 @@ impl eframe::App for AppState {
                                 self.schedule_regen(RegenKind::ImagesOnly, 50);
                             }
                         });
+                        if self.center_dot {
+                            if egui::color_picker::color_edit_button_srgba(ui, &mut self.center_dot_color, egui::color_picker::Alpha::Opaque)
+                                .on_hover_text("Center dot color")
+                                .changed()
+                            {
+                                self.schedule_regen(RegenKind::ImagesOnly, 50);
+                            }
+                            let mut poly = self.center_polygon;
+                            if ui.checkbox(&mut poly, "polygon").on_hover_text("Draw the center dot as a small regular polygon turned like the marker").changed() {
+                                self.center_polygon = poly;
+                                self.schedule_regen(RegenKind::ImagesOnly, 50);
+                            }
+                            if self.center_polygon {
+                                let mut n = self.center_polygon_sides as i32;
+                                if ui.add(egui::Slider::new(&mut n, SliderConfig::SIDES_MIN..=SliderConfig::SIDES_MAX).text("sides")).changed() {
+                                    self.center_polygon_sides = n as usize;
+                                    self.schedule_regen(RegenKind::ImagesOnly, 50);
+                                }
+                            }
+                            let mut ring = self.center_ring;
+                            if ui.checkbox(&mut ring, "ring").on_hover_text("Draw the center dot as a ring so the wedge colors show through the middle").changed() {
+                                self.center_ring = ring;
+                                self.schedule_regen(RegenKind::ImagesOnly, 50);
+                            }
+                            if self.center_ring {
+                                let mut inner = self.center_ring_inner_pct;
+                                if ui.add(egui::Slider::new(&mut inner, SliderConfig::CENTER_RING_INNER_MIN..=SliderConfig::CENTER_RING_INNER_MAX).step_by(1.0).text("% hole")).changed() {
+                                    self.center_ring_inner_pct = inner;
+                                    self.schedule_regen(RegenKind::ImagesOnly, 50);
+                                }
+                            }
+                        }
                         ui.separator();
                         let mut gd = self.gradient_dot;
                         if ui.checkbox(&mut gd, "gradient dot").changed() {
@@ -580,7 +3141,147 @@ impl eframe::App for AppState {
                                 self.gradient_dot_size_pct = gsz;
                                 self.schedule_regen(RegenKind::ImagesOnly, 50);
                             }
+                            if ui.add(egui::Slider::new(&mut self.gradient_dot_sigma_pct, SliderConfig::GRADIENT_DOT_SIGMA_MIN..=SliderConfig::GRADIENT_DOT_SIGMA_MAX).text("σ"))
+                                .on_hover_text("Falloff as a fraction of the dot radius: low gives a tight bright core, high a broad haze")
+                                .changed()
+                            {
+                                self.schedule_regen(RegenKind::ImagesOnly, 50);
+                            }
+                            if egui::color_picker::color_edit_button_srgba(ui, &mut self.gradient_dot_color, egui::color_picker::Alpha::Opaque)
+                                .on_hover_text("Color the gradient dot fades toward")
+                                .changed()
+                            {
+                                self.schedule_regen(RegenKind::ImagesOnly, 50);
+                            }
                         });
+                        ui.separator();
+                        ui.label("margin:");
+                        let mut m = self.margin_pct;
+                        if ui.add(egui::Slider::new(&mut m, SliderConfig::MARGIN_MIN..=SliderConfig::MARGIN_MAX).step_by(0.01)).on_hover_text("Space around the polygon as a fraction of the tag width; 0 touches the edges").changed() {
+                            self.margin_pct = m;
+                            self.schedule_regen(RegenKind::ImagesOnly, 50);
+                        }
+                        ui.separator();
+                        ui.label("wedge gap:");
+                        let mut gap = self.wedge_gap_px;
+                        if ui.add(egui::Slider::new(&mut gap, SliderConfig::WEDGE_GAP_MIN..=SliderConfig::WEDGE_GAP_MAX).text("px")).on_hover_text("Background-colored gap along each spoke at the save resolution, to counter ink spread; 0 keeps wedges touching").changed() {
+                            self.wedge_gap_px = gap;
+                            self.schedule_regen(RegenKind::ImagesOnly, 50);
+                        }
+                        ui.label("min wedge:");
+                        if ui.add(egui::Slider::new(&mut self.min_wedge_px, SliderConfig::MIN_WEDGE_PX_MIN..=SliderConfig::MIN_WEDGE_PX_MAX).text("px"))
+                            .on_hover_text("Fewest pixels every wedge keeps; in small tiles a wedge that rounds away takes the pixels nearest its middle from its neighbors. 0 turns this off")
+                            .changed()
+                        {
+                            self.schedule_regen(RegenKind::ImagesOnly, 50);
+                        }
+                        ui.separator();
+                        let ring_toggled = ui.checkbox(&mut self.bounding_ring, "ring").on_hover_text("Draw a ring just outside the polygon's vertices, on its circumscribed circle; kept inside the tag, so it needs some margin").changed();
+                        let mut ring_changed = false;
+                        if self.bounding_ring {
+                            ring_changed |= ui.add(egui::Slider::new(&mut self.bounding_ring_px, SliderConfig::BOUNDING_RING_MIN..=SliderConfig::BOUNDING_RING_MAX).text("px")).on_hover_text("Ring width at the save resolution").changed();
+                            ring_changed |= ui.color_edit_button_srgba(&mut self.bounding_ring_color).changed();
+                        }
+                        if ring_toggled || ring_changed {
+                            self.schedule_regen(RegenKind::ImagesOnly, 50);
+                        }
+                        ui.separator();
+                        let mut band_changed = ui.checkbox(&mut self.signature_band, "band").on_hover_text("Solid band just outside the polygon edges, for sorting tags by eye; needs some margin").changed();
+                        if self.signature_band {
+                            band_changed |= ui.add(egui::Slider::new(&mut self.signature_band_px, SliderConfig::SIGNATURE_BAND_MIN..=SliderConfig::SIGNATURE_BAND_MAX).text("px")).on_hover_text("Band width at the save resolution").changed();
+                            band_changed |= ui.checkbox(&mut self.signature_band_auto, "signature").on_hover_text("Color each tag's band with the mean Lab of its wedges; off uses the color chosen here for every tag").changed();
+                            if !self.signature_band_auto {
+                                band_changed |= ui.color_edit_button_srgba(&mut self.signature_band_color).changed();
+                            }
+                        }
+                        if band_changed {
+                            self.schedule_regen(RegenKind::ImagesOnly, 50);
+                        }
+                        ui.separator();
+                        let mut wg = self.wedge_gradient;
+                        if ui.checkbox(&mut wg, "wedge gradient").on_hover_text("Fade each wedge from a lighter tint at the center to its color at the rim").changed() {
+                            self.wedge_gradient = wg;
+                            self.schedule_regen(RegenKind::ImagesOnly, 50);
+                        }
+                        ui.separator();
+                        if ui.checkbox(&mut self.dither, "dither").on_hover_text("Fill wedges with a 4×4 Bayer pattern of printable levels instead of flat color").changed() {
+                            self.schedule_regen(RegenKind::ImagesOnly, 50);
+                        }
+                        if self.dither {
+                            let mut levels = self.dither_levels;
+                            if ui.add(egui::Slider::new(&mut levels, SliderConfig::DITHER_LEVELS_MIN..=SliderConfig::DITHER_LEVELS_MAX).text("levels"))
+                                .on_hover_text("Printable levels per channel, including 0 and 255")
+                                .changed()
+                            {
+                                self.dither_levels = levels;
+                                self.schedule_regen(RegenKind::ImagesOnly, 50);
+                            }
+                        }
+                        ui.separator();
+                        let mut rot = self.random_rotation;
+                        if ui.checkbox(&mut rot, "random rotation").on_hover_text("Rotate each tag by its own seeded angle (less than one wedge); recorded in the manifest").changed() {
+                            self.random_rotation = rot;
+                            self.update_tag_rotations();
+                            self.high_res.clear();
+                            self.schedule_regen(RegenKind::ImagesOnly, 50);
+                        }
+                        ui.separator();
+                        let mut fb = self.fiducial_border;
+                        if ui.checkbox(&mut fb, "anchor border").on_hover_text("Surround each tag with a black frame and square corner anchors; saved images grow accordingly").changed() {
+                            self.fiducial_border = fb;
+                            self.schedule_regen(RegenKind::ImagesOnly, 50);
+                        }
+                        ui.label("Quiet zone:");
+                        if ui.add(egui::Slider::new(&mut self.quiet_zone_pct, SliderConfig::QUIET_ZONE_MIN..=SliderConfig::QUIET_ZONE_MAX).step_by(0.01))
+                            .on_hover_text("White band around each tag (outside the anchor border), as a fraction of the tag width; saved images grow accordingly")
+                            .changed()
+                        {
+                            self.schedule_regen(RegenKind::ImagesOnly, 50);
+                        }
+                    });
+                    ui.add_space(2.0);
+                    ui.horizontal_wrapped(|ui| {
+                        ui.spacing_mut().item_spacing.x = 8.0;
+                        ui.label("Pool:");
+                        if ui.button("Sample from image…").on_hover_text("Build the candidate colors from an image's dominant colors").clicked() {
+                            self.sample_pool_from_image();
+                        }
+                        let mut k = self.image_palette_k as i32;
+                        if ui.add(egui::Slider::new(&mut k, SliderConfig::IMAGE_PALETTE_K_MIN..=SliderConfig::IMAGE_PALETTE_K_MAX).text("k")).changed() {
+                            self.image_palette_k = k as usize;
+                        }
+                        ui.add(egui::Slider::new(&mut self.pool_merge_delta, SliderConfig::POOL_MERGE_DELTA_MIN..=SliderConfig::POOL_MERGE_DELTA_MAX).text("merge ΔE"))
+                            .on_hover_text("Imported or sampled colors closer than this ΔE are merged into one candidate, so near-duplicates don't inflate the pool; applies to the next import (0 merges only exact repeats)");
+                        if ui.button("Reset pool").on_hover_text("Return to the selected built-in pool").clicked() && self.set_candidate_pool(self.default_pool()) {
+                            self.schedule_regen(RegenKind::Full, 0);
+                        }
+                        let mut source = self.pool_source;
+                        egui::ComboBox::from_id_source("pool_source")
+                            .selected_text(source.label())
+                            .show_ui(ui, |ui| {
+                                for s in PoolSource::ALL {
+                                    ui.selectable_value(&mut source, s, s.label());
+                                }
+                            })
+                            .response
+                            .on_hover_text("Built-in candidate pool. Grayscale uses only neutral grays so tags differ purely in lightness (for monochrome engraving)");
+                        if source != self.pool_source {
+                            self.pool_source = source;
+                            if self.set_candidate_pool(self.default_pool()) {
+                                self.schedule_regen(RegenKind::Full, 0);
+                            } else {
+                                self.set_status(format!("Too few usable colors in the {} pool for {} wedges", source.label(), self.wedges()), true);
+                            }
+                        }
+                        if ui.checkbox(&mut self.snap_to_grid, "snap to grid")
+                            .on_hover_text("Move each selected color to the nearest level of the coarse 6-level grid per channel, for consistent print runs; warns if that breaks the ΔE threshold")
+                            .changed()
+                        {
+                            self.schedule_regen(RegenKind::Full, 0);
+                        }
+                        if ui.button("Export heatmap").on_hover_text("Save the pairwise ΔE matrix of the candidate pool as an image (brighter = farther)").clicked() {
+                            self.save_candidate_heatmap();
+                        }
                     });
                 });
 
@@ -594,13 +3295,166 @@ impl eframe::App for AppState {
                         ui.spacing_mut().item_spacing.x = 8.0;
                         ui.label(format!("ΔE: {:.1}", self.threshold));
                         if ui.button("Regenerate").clicked() {
+                            self.seed = rand::random();
                             self.regenerate(ctx);
                         }
-                        if ui.button("Save All Separate").clicked() {
-                            self.save_current_tags();
+                        if ui.button("Compare seeds")
+                            .on_hover_text("Generate the same settings under the next seed (or one you enter) and show both sets side by side with each tag's smallest ΔE")
+                            .clicked()
+                        {
+                            self.start_seed_comparison(ctx, self.seed.wrapping_add(1));
+                        }
+                        if self.regen_job.is_some() {
+                            ui.spinner().on_hover_text("Selecting and grouping colors; the previous tags stay until this finishes");
+                        }
+                        let idle = self.save_job.is_none();
+                        if ui.add_enabled(idle, egui::Button::new("Save All Separate")).clicked() {
+                            self.start_save_job(SaveKind::Separate);
+                        }
+                        if ui.add_enabled(idle, egui::Button::new("Save All Together")).clicked() {
+                            self.start_save_job(SaveKind::Together);
+                        }
+                        if ui.button("Copy sheet").on_hover_text("Copy the combined sheet to the clipboard instead of saving it, e.g. to paste into a chat").clicked() {
+                            self.copy_combined_to_clipboard();
+                        }
+                        if ui.add_enabled(idle, egui::Button::new("Resume save…"))
+                            .on_hover_text("Pick an interrupted Save All Separate folder and write only the tags missing from it, merging its manifest")
+                            .clicked()
+                        {
+                            self.resume_save_dialog();
+                        }
+                        ui.checkbox(&mut self.resume_overwrite_mismatched, "overwrite other sizes")
+                            .on_hover_text("When resuming, also rewrite existing tags whose pixel size differs from the current save size");
+                        if ui.add_enabled(idle && self.last_save_dir.is_some(), egui::Button::new("Verify last save"))
+                            .on_hover_text("Reload the last saved images and check every wedge against the manifest colors")
+                            .clicked()
+                        {
+                            self.verify_last_save();
+                        }
+                        if ui.add_enabled(self.last_save_dir.is_some(), egui::Button::new("Open folder"))
+                            .on_hover_text("Show the last Save All Separate / Together folder in the file manager")
+                            .clicked()
+                        {
+                            self.reveal_last_save();
+                        }
+                        if ui.add_enabled(idle, egui::Button::new("Multi-export"))
+                            .on_hover_text("Save All Separate once per listed width, each into its own subfolder with its own manifest")
+                            .clicked()
+                        {
+                            self.start_save_job(SaveKind::MultiSize);
+                        }
+                        ui.add(egui::TextEdit::singleline(&mut self.multi_sizes).desired_width(110.0))
+                            .on_hover_text("Comma-separated widths in pixels; heights follow the save size's aspect ratio");
+                        ui.add(egui::DragValue::new(&mut self.gutter_px).clamp_range(0..=SliderConfig::GUTTER_MAX).suffix(" px gutter"))
+                            .on_hover_text("Background-colored spacing between tiles of the combined sheet, for cutting");
+                        ui.checkbox(&mut self.tile_labels, "labels")
+                            .on_hover_text("Print each tile's number in a strip under it in the combined sheet");
+                        ui.add(egui::DragValue::new(&mut self.bleed_mm).clamp_range(0.0..=SliderConfig::BLEED_MM_MAX).speed(0.1).suffix(" mm bleed"))
+                            .on_hover_text("Background-colored margin added on every side of the combined sheet, trimmed off after printing");
+                        ui.add(egui::DragValue::new(&mut self.print_dpi).clamp_range(SliderConfig::PRINT_DPI_MIN..=SliderConfig::PRINT_DPI_MAX).speed(1.0).suffix(" dpi"))
+                            .on_hover_text("Print resolution used to convert millimeters to pixels");
+                        ui.add_enabled(self.bleed_mm > 0.0, egui::Checkbox::new(&mut self.trim_marks, "trim marks"))
+                            .on_hover_text("Draw crop marks in the bleed at the corners of the trim box");
+                        ui.checkbox(&mut self.registration_dots, "corner dots")
+                            .on_hover_text("Put a 3 mm registration dot 2 mm in from each corner of the combined sheet; positions are in the manifest");
+                        if ui.button("Save legend").on_hover_text("Save an image listing each tag's colors as labeled swatches").clicked() {
+                            self.save_current_legend();
+                        }
+                        if ui.button("Save .ase").on_hover_text("Save an Adobe Swatch Exchange file with one swatch group per tag").clicked() {
+                            self.save_current_ase();
+                        }
+                        if ui.button("Save .gpl").on_hover_text("Save a GIMP palette (also imports into Inkscape and Krita)").clicked() {
+                            self.save_current_gpl();
+                        }
+                        if ui.button("Save atlas").on_hover_text("Pack all tags into a power-of-two texture with a JSON of each tile's UV rectangle (uses the gutter)").clicked() {
+                            self.save_current_atlas();
+                        }
+                        if ui.button("Save tag…").on_hover_text("Save one tag at the save size to a file of your choice").clicked() {
+                            self.save_single_tag();
+                        }
+                        if ui.button("Spin GIF").on_hover_text("Save an animated GIF of one tag making a full turn").clicked() {
+                            self.save_spin_gif();
+                        }
+                        if ui.button("Wedge spec").on_hover_text("Save one tag's wedge angles, unit-radius corners and colors as JSON, for CNC or engraving").clicked() {
+                            self.save_wedge_spec_for_tag();
+                        }
+                        ui.add(egui::DragValue::new(&mut self.export_tag).clamp_range(1..=self.tags.len().max(1)).prefix("tag "));
+                        ui.add(egui::Slider::new(&mut self.spin_frames, SliderConfig::SPIN_FRAMES_MIN..=SliderConfig::SPIN_FRAMES_MAX).text("frames"));
+                    });
+                    if let Some(job) = &self.save_job {
+                        let mut cancel = false;
+                        ui.horizontal(|ui| {
+                            ui.add(egui::ProgressBar::new(job.progress).desired_width(240.0).show_percentage());
+                            cancel = ui.button("Cancel").on_hover_text("Stop after the tags already in progress").clicked();
+                        });
+                        if cancel {
+                            job.cancel.store(true, Ordering::Relaxed);
+                        }
+                    }
+                    if let Some((message, is_error, at)) = &self.status {
+                        let remaining = SliderConfig::STATUS_SECONDS - at.elapsed().as_secs_f32();
+                        if remaining > 0.0 {
+                            let color = if *is_error { egui::Color32::from_rgb(220, 70, 60) } else { egui::Color32::from_rgb(70, 170, 90) };
+                            ui.colored_label(color, message.as_str());
+                            if focused {
+                                ctx.request_repaint_after(Duration::from_secs_f32(remaining));
+                            }
+                        }
+                    }
+                    if !self.reused_colors.is_empty() {
+                        let details: Vec<String> = self
+                            .reused_colors
+                            .iter()
+                            .map(|(c, idxs)| {
+                                let tags: Vec<String> = idxs.iter().map(|i| (i + 1).to_string()).collect();
+                                format!("#{:02X}{:02X}{:02X} in tags {}", c[0], c[1], c[2], tags.join(", "))
+                            })
+                            .collect();
+                        ui.colored_label(egui::Color32::from_rgb(230, 160, 40), format!("⚠ {} color(s) reused across tags", self.reused_colors.len()))
+                            .on_hover_text(details.join("\n"));
+                    }
+                    if !self.tags.is_empty() && self.threshold < self.min_usable_threshold {
+                        ui.colored_label(
+                            egui::Color32::from_rgb(220, 70, 60),
+                            egui::RichText::new(format!("⚠ ΔE {:.1} is below {:.1}: colors may be hard to distinguish; reduce count or widen the lightness range", self.threshold, self.min_usable_threshold)).strong(),
+                        );
+                    }
+                    if !self.flat_tags.is_empty() {
+                        let details: Vec<String> = self
+                            .flat_tags
+                            .iter()
+                            .map(|&i| format!("tag {}: L spread {:.1}", i + 1, lightness_spread(&self.tags[i])))
+                            .collect();
+                        ui.colored_label(egui::Color32::from_rgb(230, 160, 40), format!("⚠ {} tag(s) below the lightness spread", self.flat_tags.len()))
+                            .on_hover_text(details.join("\n"));
+                    }
+                    ui.horizontal_wrapped(|ui| {
+                        ui.spacing_mut().item_spacing.x = 8.0;
+                        ui.label("Usable ΔE ≥");
+                        ui.add(egui::Slider::new(&mut self.min_usable_threshold, SliderConfig::MIN_USABLE_THRESHOLD_MIN..=SliderConfig::MIN_USABLE_THRESHOLD_MAX).step_by(0.5))
+                            .on_hover_text("Warn when the selected threshold falls below this");
+                        ui.checkbox(&mut self.hold_threshold_floor, "hold count")
+                            .on_hover_text("Don't let the tag count rise past the point where the estimated threshold drops below the floor");
+                    });
+                    ui.horizontal_wrapped(|ui| {
+                        ui.spacing_mut().item_spacing.x = 8.0;
+                        ui.label("Similar below:");
+                        let mut td = self.similar_tag_distance;
+                        if ui.add(egui::Slider::new(&mut td, SliderConfig::TAG_DISTANCE_MIN..=SliderConfig::TAG_DISTANCE_MAX).step_by(0.5))
+                            .on_hover_text("Flag tag pairs whose colors, matched one-to-one in the best order, differ by less than this mean ΔE")
+                            .changed()
+                        {
+                            self.similar_tag_distance = td;
+                            self.refresh_tag_stats();
                         }
-                        if ui.button("Save All Together").clicked() {
-                            self.save_current_tags_together();
+                        if !self.similar_tags.is_empty() {
+                            let details: Vec<String> = self
+                                .similar_tags
+                                .iter()
+                                .map(|(i, j, d)| format!("tags {} and {}: {:.1}", i + 1, j + 1, d))
+                                .collect();
+                            ui.colored_label(egui::Color32::from_rgb(230, 160, 40), format!("⚠ {} similar tag pair(s)", self.similar_tags.len()))
+                                .on_hover_text(details.join("\n"));
                         }
                     });
                     ui.add_space(2.0);
@@ -608,10 +3462,17 @@ impl eframe::App for AppState {
                         ui.spacing_mut().item_spacing.x = 8.0;
                         ui.label("Preview res:");
                         let mut pw = self.preview_max_width as f32;
-                        if ui.add(egui::Slider::new(&mut pw, SliderConfig::RESOLUTION_MIN..=SliderConfig::RESOLUTION_MAX).step_by(2.0)).changed() {
+                        if ui.add(egui::Slider::new(&mut pw, SliderConfig::RESOLUTION_MIN..=SliderConfig::RESOLUTION_MAX).step_by(2.0))
+                            .on_hover_text("Pixel width the previews are rendered at; lower is faster but softer. Above the tile size shown next to it, extra pixels only cost time")
+                            .changed()
+                        {
                             self.preview_max_width = (pw.round() as u32) & !1;
                             self.rebuild_textures_quick(ctx);
                         }
+                        let shown = (self.last_left_tile_w * ctx.pixels_per_point()).round() as u32;
+                        let color = if self.preview_size().0 < shown { egui::Color32::from_rgb(220, 160, 40) } else { ui.visuals().weak_text_color() };
+                        ui.colored_label(color, format!("tiles shown at {} px", shown))
+                            .on_hover_text("Amber when previews are rendered smaller than they are shown, so they are scaled up and look soft");
                         ui.separator();
                         ui.label("Save res:");
                         let mut save_res = self.save_size.0 as i32;
@@ -626,15 +3487,53 @@ impl eframe::App for AppState {
                         }
                         ui.separator();
                         let mut prof = self.profiling;
-                        if ui.checkbox(&mut prof, "profiling logs").changed() {
+                        ui.label("profiling:");
+                        egui::ComboBox::from_id_source("profiling")
+                            .selected_text(prof.label())
+                            .show_ui(ui, |ui| {
+                                for level in ProfileLevel::ALL {
+                                    ui.selectable_value(&mut prof, level, level.label());
+                                }
+                            })
+                            .response
+                            .on_hover_text("Console timing logs: summary covers full regenerations, pool rebuilds and saves; verbose adds every preview rebuild");
+                        if prof != self.profiling {
                             self.profiling = prof;
-                            if self.profiling { println!("[profile] enabled"); } else { println!("[profile] disabled"); }
+                            println!("[profile] level: {}", prof.label());
+                        }
+                        if self.profiling >= ProfileLevel::Summary {
+                            ui.add(egui::TextEdit::singleline(&mut self.profile_path).desired_width(140.0))
+                                .on_hover_text("CSV file that receives one row of stage timings per regenerate");
                         }
                         ui.separator();
                         let mut defer = self.defer_high_res;
                         if ui.checkbox(&mut defer, "defer high-res").on_hover_text("Skip rendering high-res images during interactive changes; still renders on Save").changed() {
                             self.defer_high_res = defer;
                         }
+                        if ui.button("Render high-res now").on_hover_text("Render every tag at the save size without saving, and show the selected tag at full resolution").clicked() {
+                            self.render_high_res_now(ctx);
+                        }
+                        ui.separator();
+                        let mut deep = self.export_16bit;
+                        if ui.checkbox(&mut deep, "16-bit export").on_hover_text("Save 16 bits per channel (PNG and TIFF only); the manifest still lists the 8-bit source colors").changed() {
+                            self.export_16bit = deep;
+                        }
+                        ui.checkbox(&mut self.embed_metadata, "embed metadata")
+                            .on_hover_text("Write each tag's colors, Lab values, ΔE, threshold, sides and seed into its PNG (iTXt chunk \"polycue\")");
+                        ui.checkbox(&mut self.wedge_layers, "wedge files")
+                            .on_hover_text("Save All Separate also writes each wedge alone on a transparent PNG (one file per color per tag)");
+                        ui.checkbox(&mut self.category_folders, "category folders")
+                            .on_hover_text("Save All Separate writes each tag with a category into a subfolder named after it; tags without one stay at the top level");
+                        ui.label("Manifest:");
+                        egui::ComboBox::from_id_source("manifest_format")
+                            .selected_text(self.manifest_format.label())
+                            .show_ui(ui, |ui| {
+                                for f in ManifestFormat::ALL {
+                                    ui.selectable_value(&mut self.manifest_format, f, f.label());
+                                }
+                            })
+                            .response
+                            .on_hover_text("File format of the manifest written with each save; the dictionary stays JSON");
                     });
                     ui.add_space(2.0);
                     ui.horizontal_wrapped(|ui| {
@@ -671,6 +3570,87 @@ impl eframe::App for AppState {
                             }
                         }
                     });
+                    ui.collapsing("Advanced", |ui| {
+                        ui.horizontal_wrapped(|ui| {
+                            ui.spacing_mut().item_spacing.x = 8.0;
+                            ui.label("Color space:");
+                            let mut space = self.color_space;
+                            egui::ComboBox::from_id_source("color_space")
+                                .selected_text(space.label())
+                                .show_ui(ui, |ui| {
+                                    for s in ColorSpace::ALL {
+                                        ui.selectable_value(&mut space, s, s.label());
+                                    }
+                                })
+                                .response
+                                .on_hover_text("Space in which ΔE is measured for color selection, grouping and the similarity check");
+                            if space != self.color_space {
+                                self.color_space = space;
+                                self.rebuild_pool();
+                                self.schedule_regen(RegenKind::Full, 0);
+                            }
+                            if ui.add(egui::Slider::new(&mut self.lightness_weight, SliderConfig::LIGHTNESS_WEIGHT_MIN..=SliderConfig::LIGHTNESS_WEIGHT_MAX).text("lightness weight"))
+                                .on_hover_text("Scale on the lightness difference in ΔE. Below 1 the selection spreads colors across hue and chroma instead of light and dark; the threshold is then in these weighted units")
+                                .changed()
+                            {
+                                self.rebuild_pool();
+                                self.schedule_regen(RegenKind::Full, 200);
+                            }
+                            ui.separator();
+                            let threads_resp = ui.add(egui::Slider::new(&mut self.thread_count, 0..=SliderConfig::THREAD_COUNT_MAX).text("threads"))
+                                .on_hover_text("Worker threads for rendering and color selection; 0 uses one per core. Lower it to leave cores free on shared machines");
+                            // Starting a pool spawns its threads, so rebuild on release rather than per drag step
+                            if (threads_resp.changed() && !threads_resp.dragged()) || threads_resp.drag_stopped() {
+                                self.thread_pool = build_thread_pool(self.thread_count);
+                            }
+                            ui.separator();
+                            ui.label("Threshold precision:");
+                            let mut iters = self.threshold_search.iterations as i32;
+                            if ui.add(egui::Slider::new(&mut iters, SliderConfig::SEARCH_ITERATIONS_MIN..=SliderConfig::SEARCH_ITERATIONS_MAX).text("steps"))
+                                .on_hover_text("Binary-search steps for the ΔE threshold; each step halves the remaining uncertainty")
+                                .changed()
+                            {
+                                self.threshold_search.iterations = iters as usize;
+                                self.update_max_possible_count();
+                                self.count = self.count.min(self.max_possible_count);
+                                self.schedule_regen(RegenKind::Full, 200);
+                            }
+                            if ui.checkbox(&mut self.threshold_search.prefer_vivid, "prefer vivid")
+                                .on_hover_text("Among colors that meet the threshold, pick the most saturated first; near-grays stay in the pool for when they are needed")
+                                .changed()
+                            {
+                                self.update_max_possible_count();
+                                self.count = self.count.min(self.max_possible_count);
+                                self.schedule_regen(RegenKind::Full, 0);
+                            }
+                            ui.separator();
+                            let mut bounded = self.threshold_search.lo.is_some() || self.threshold_search.hi.is_some();
+                            if ui.checkbox(&mut bounded, "search bounds").on_hover_text("Restrict the threshold search to an explicit ΔE range").changed() {
+                                if bounded {
+                                    let max_d = self.candidate_dm.iter().copied().fold(0.0f32, f32::max);
+                                    self.threshold_search.lo = Some(0.0);
+                                    self.threshold_search.hi = Some(max_d);
+                                } else {
+                                    self.threshold_search.lo = None;
+                                    self.threshold_search.hi = None;
+                                }
+                                self.update_max_possible_count();
+                                self.count = self.count.min(self.max_possible_count);
+                                self.schedule_regen(RegenKind::Full, 200);
+                            }
+                            if let (Some(mut lo), Some(mut hi)) = (self.threshold_search.lo, self.threshold_search.hi) {
+                                let lo_changed = ui.add(egui::DragValue::new(&mut lo).clamp_range(0.0..=hi).speed(0.5).prefix("lo ")).changed();
+                                let hi_changed = ui.add(egui::DragValue::new(&mut hi).clamp_range(lo..=400.0).speed(0.5).prefix("hi ")).changed();
+                                if lo_changed || hi_changed {
+                                    self.threshold_search.lo = Some(lo);
+                                    self.threshold_search.hi = Some(hi);
+                                    self.update_max_possible_count();
+                                    self.count = self.count.min(self.max_possible_count);
+                                    self.schedule_regen(RegenKind::Full, 200);
+                                }
+                            }
+                        });
+                    });
                 });
             });
         });
@@ -684,35 +3664,65 @@ impl eframe::App for AppState {
                 if ui.add(egui::Slider::new(&mut cols_i, SliderConfig::COLUMNS_MIN..=SliderConfig::COLUMNS_MAX)).changed() {
                     self.columns = cols_i as usize;
                 }
-            });
-            ui.separator();
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                let cols = self.columns.max(1);
-                let avail = ui.available_width();
-                let spacing = ui.spacing().item_spacing.x;
-                let tile_w = ((avail - spacing * ((cols as f32) - 1.0)) / (cols as f32))
-                    .floor()
-                    .max(32.0);
-                self.last_left_tile_w = tile_w;
-                let mut i = 0;
-                while i < self.textures.len() {
-                    ui.horizontal(|ui| {
-                        for _ in 0..cols {
-                            if i >= self.textures.len() { break; }
-                            let tex = &self.textures[i];
-                            ui.add(egui::Image::new((tex.id(), egui::Vec2::new(tile_w, tile_w))));
-                            i += 1;
+                ui.label("Sort:");
+                let mut sort = self.tag_sort;
+                egui::ComboBox::from_id_source("tag_sort")
+                    .selected_text(sort.label())
+                    .show_ui(ui, |ui| {
+                        for s in TagSort::ALL {
+                            ui.selectable_value(&mut sort, s, s.label());
                         }
-                    });
+                    })
+                    .response
+                    .on_hover_text("Order of the tiles: as generated, or by each tag's mean color so similar-looking tags sit together");
+                if sort != self.tag_sort {
+                    self.tag_sort = sort;
+                    self.tag_order = sort.order(&self.tags);
+                }
+                ui.checkbox(&mut self.sort_saves, "sort saves")
+                    .on_hover_text("Number saved files (tag_01, …) and serial numbers in this order instead of generation order; the manifest records it as settings.tag_order");
+                let selected = self.selected_indices().len();
+                if selected > 0 {
+                    ui.separator();
+                    ui.label(format!("{} selected", selected));
+                    if ui.add_enabled(self.save_job.is_none(), egui::Button::new("Export"))
+                        .on_hover_text("Save just the selected tags, numbered from 1, like Save All Separate")
+                        .clicked()
+                    {
+                        self.start_save_job(SaveKind::Selected);
+                    }
+                    if ui.button("Lock").on_hover_text("Pin the selected tags so regeneration keeps them").clicked() {
+                        self.lock_selected_tags(true);
+                    }
+                    if ui.button("Unlock").on_hover_text("Let regeneration replace the selected tags").clicked() {
+                        self.lock_selected_tags(false);
+                    }
+                    if ui.button("Delete").on_hover_text("Remove the selected tags and renumber the rest").clicked() {
+                        self.delete_selected_tags();
+                    }
+                    if ui.button("Clear").clicked() {
+                        self.selected_tags.clear();
+                    }
                 }
             });
+            ui.separator();
+            self.draw_tag_grid(ui);
         });
         
-        // Check if panel width changed and trigger regeneration
+        // Check if panel width changed and regenerate once the resize settles
         let current_width = panel_response.response.rect.width();
-        if (current_width - self.last_panel_width).abs() > 1.0 {
+        if (current_width - self.last_panel_width).abs() > SliderConfig::RESIZE_HYSTERESIS_PX {
             self.last_panel_width = current_width;
-            self.schedule_regen(RegenKind::ImagesOnly, 100);
+            self.resize_settle_at = Some(Instant::now() + Duration::from_millis(SliderConfig::RESIZE_SETTLE_MS));
+        }
+        if let Some(at) = self.resize_settle_at {
+            if Instant::now() >= at {
+                self.resize_settle_at = None;
+                self.schedule_regen(RegenKind::ImagesOnly, 0);
+                ctx.request_repaint();
+            } else {
+                ctx.request_repaint_after(at.saturating_duration_since(Instant::now()));
+            }
         }
 
         // Right half: placeholder for future graphics/content
@@ -720,6 +3730,11 @@ impl eframe::App for AppState {
             egui::ScrollArea::vertical().show(ui, |ui| {
                 let base_w = self.last_left_tile_w.max(32.0);
 
+                // Section: Pairwise ΔE histogram with the threshold marked
+                ui.label("ΔE distribution (selected colors)");
+                self.draw_delta_histogram(ui);
+                ui.separator();
+
                 // Section: All tags monochrome half-size
                 ui.label("Monochrome (half-size)");
                 let mono_w = (base_w * 0.5).max(2.0);
@@ -730,22 +3745,35 @@ impl eframe::App for AppState {
                 });
                 ui.separator();
 
-                // Section: First tag scaled variants
-                ui.label("First tag scaled");
-                let scales: [f32; 18] = [
-                    0.5, 0.4, 0.3, 0.2, 0.15, 0.14, 0.13, 0.12, 0.1,
-                    0.09, 0.08, 0.07, 0.06, 0.05, 0.04, 0.03, 0.02, 0.01,
-                ];
+                let subject = self.preview_subject.min(self.tags.len().saturating_sub(1)) + 1;
+                // Section: Preview subject scaled variants
+                ui.horizontal(|ui| {
+                    ui.label(format!("Tag {} scaled", subject));
+                    if ui.small_button("Export chart").on_hover_text("Save these sizes as one labeled PNG strip").clicked() {
+                        self.save_distance_chart_for_subject();
+                    }
+                });
                 ui.horizontal_wrapped(|ui| {
                     for (i, tex) in self.right_first_scaled_textures.iter().enumerate() {
-                        let w = (base_w * scales[i]).max(2.0);
+                        let w = (base_w * DISTANCE_SCALES[i]).max(2.0);
                         ui.add(egui::Image::new((tex.id(), egui::Vec2::new(w, w))));
                     }
                 });
                 ui.separator();
 
-                // Section: Heavily blurred first tag
-                ui.label("First tag blurred (levels)");
+                // Section: Heavily blurred preview subject
+                ui.horizontal(|ui| {
+                    ui.label(format!("Tag {} blurred (levels)", subject));
+                    let mut levels = self.blur_level_count as i32;
+                    let levels_changed = ui.add(egui::Slider::new(&mut levels, SliderConfig::BLUR_LEVELS_MIN as i32..=SliderConfig::BLUR_LEVELS_MAX as i32).text("levels")).changed();
+                    self.blur_level_count = levels as usize;
+                    let sigma_changed = ui.add(egui::Slider::new(&mut self.blur_max_sigma, SliderConfig::BLUR_MAX_SIGMA_MIN..=SliderConfig::BLUR_MAX_SIGMA_MAX).text("max σ"))
+                        .on_hover_text("Strongest blur, as a fraction of the preview width; the levels ramp up to it")
+                        .changed();
+                    if levels_changed || sigma_changed {
+                        self.schedule_regen(RegenKind::ImagesOnly, 100);
+                    }
+                });
                 let w = base_w;
                 ui.horizontal_wrapped(|ui| {
                     let time = ctx.input(|i| i.time) as f32;
@@ -764,5 +3792,28 @@ impl eframe::App for AppState {
                 });
             });
         });
+
+        self.draw_tag_inspector(ctx);
+        self.draw_zoom_view(ctx);
+        self.draw_seed_comparison(ctx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preview_textures_follow_the_resolution_setting() {
+        let ctx = Context::default();
+        let mut app = AppState::new();
+        app.tags = vec![vec![Rgb([200, 30, 30]), Rgb([30, 200, 30]), Rgb([30, 30, 200])]; 2];
+        for width in [64, 160, 302] {
+            app.preview_max_width = width;
+            assert_eq!(app.preview_size(), (width, width));
+            app.rebuild_textures_quick(&ctx);
+            assert_eq!(app.textures.len(), 2);
+            assert!(app.textures.iter().all(|t| t.size() == [width as usize; 2]), "{width}px");
+        }
     }
 }
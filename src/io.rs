@@ -1,25 +1,649 @@
-use serde::Serialize;
-use image::{DynamicImage, Rgb};
+use serde::{Deserialize, Serialize};
+use image::{DynamicImage, Rgb, RgbImage};
 use palette::Lab;
-use std::fs::{self, File};
+use std::fs::{self, File, OpenOptions};
 use std::io::Write;
 use std::path::Path;
+use rayon::prelude::*;
 use chrono::{DateTime, Local};
-use crate::color::{srgb_u8_to_lab, delta_e};
+use crate::color::{srgb_u8_to_lab, delta_e, distinguishing_colors, ColorSpace, PoolSource, TagSort, ThresholdSearch, WedgeOrder, pairwise_delta_matrix, find_reused_colors, find_similar_tags, lightness_spread, min_adjacent_delta_e, min_pairwise_delta_e, nearest_pantone, snap_to_grid};
+use crate::render::{bounding_ring, dither_color, downscale_linear, draw_marker_polygon, draw_text, draw_trim_marks, fill_disc, draw_wedge_layer, fiducial_geometry, wedge_gradient_color, text_ink_extent, text_width, BoundingRing, CenterShape, FiducialGeometry, MarkerShape, MarkerStyle};
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct TagManifestEntry {
+    /// Path relative to the output folder, inside the category subfolder when those are on
     pub filename: String,
+    /// Inventory set this tag belongs to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
     pub sides: usize,
     pub colors_rgb: Vec<(u8, u8, u8)>,
     pub colors_lab: Vec<(f32, f32, f32)>,
     pub min_pairwise_delta_e: f32,
+    /// Colors as selected, when snapping to the printable grid changed any of them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unsnapped_rgb: Option<Vec<(u8, u8, u8)>>,
+    /// Smallest ΔE between neighboring wedges (last wraps to first), in the selection color space
+    pub min_adjacent_delta_e: f32,
+    /// Color of the band around the tag, when the signature band is on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature_rgb: Option<(u8, u8, u8)>,
+    /// Lab lightness range (max L minus min L) of the tag's colors
+    pub lightness_spread: f32,
+    /// Closest built-in Pantone solid coated reference for each color (approximate)
+    pub nearest_pantone: Vec<PantoneMatch>,
+    /// Tile rectangle (x, y, width, height) within the combined sheet
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub combined_rect: Option<(u32, u32, u32, u32)>,
+    /// Clockwise rotation of this tag's polygon in degrees, when random rotation is on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rotation_deg: Option<f32>,
+    /// One transparent PNG per wedge, when wedge export is on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wedge_files: Option<Vec<WedgeFile>>,
+}
+
+/// Inputs that produced a batch of tags, recorded so the run can be repeated
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GenerationSettings {
+    pub count: usize,
+    /// Colors per tag (polygon sides or circle sectors)
+    pub wedges: usize,
+    /// Per-tag side counts, repeated round-robin, when they override `wedges`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sides_pattern: Option<Vec<usize>>,
+    /// Side counts were chosen to share the whole pool among the tags; `sides_pattern`
+    /// then lists every tag's count in order
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub fit_palette: bool,
+    #[serde(deserialize_with = "u64_or_string")]
+    pub seed: u64,
+    /// Fixed threshold, when the automatic search was bypassed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub threshold_override: Option<f32>,
+    pub threshold_search: ThresholdSearch,
+    /// Lab L range candidate colors were limited to
+    pub lightness_window: (f32, f32),
+    pub min_lightness_spread: f32,
+    /// Built-in pool the candidates were drawn from
+    pub pool_source: PoolSource,
+    /// Selected colors were moved to the nearest grid level per channel
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub snap_to_grid: bool,
+    /// Monte Carlo swap attempts during grouping
+    pub grouping_iterations: usize,
+    pub wedge_order: WedgeOrder,
+    /// Order the tags were numbered in: as generated, or sorted by mean hue or lightness
+    pub tag_order: TagSort,
+    /// Wedge index reserved for the orientation color in every tag, when that mode is on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orientation_wedge: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orientation_rgb: Option<(u8, u8, u8)>,
+    pub color_space: ColorSpace,
+    /// Distance formula applied in `color_space`
+    pub delta_e_formula: String,
+    /// Scale on the lightness term of that formula; the threshold is in these weighted units
+    pub lightness_weight: f32,
+    pub center_dot: bool,
+    pub center_dot_size_pct: f32,
+    pub center_ring: bool,
+    pub center_ring_inner_pct: f32,
+    pub center_shape: CenterShape,
+    pub gradient_dot: bool,
+    pub gradient_dot_size_pct: f32,
+    /// Gaussian sigma of the gradient dot as a fraction of its radius
+    pub gradient_dot_sigma_pct: f32,
+}
+
+/// Nearest Pantone reference for one color, with the CIE76 ΔE to it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PantoneMatch {
+    pub name: String,
+    pub delta_e: f32,
+}
+
+/// Approximate Pantone references for a tag's colors, in wedge order
+fn pantone_matches(labs: &[Lab]) -> Vec<PantoneMatch> {
+    labs.iter()
+        .map(|&lab| {
+            let (name, delta_e) = nearest_pantone(lab);
+            PantoneMatch { name: name.to_string(), delta_e }
+        })
+        .collect()
+}
+
+/// A single wedge saved on its own
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WedgeFile {
+    pub filename: String,
+    pub rgb: (u8, u8, u8),
+}
+
+/// File format used for saved tag images
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Bmp,
+    Tiff,
+}
+
+impl ImageFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpg",
+            ImageFormat::Bmp => "bmp",
+            ImageFormat::Tiff => "tiff",
+        }
+    }
+
+    /// Whether the format can store 16 bits per channel
+    pub fn supports_16bit(self) -> bool {
+        matches!(self, ImageFormat::Png | ImageFormat::Tiff)
+    }
+
+    /// The `image` crate's encoder for this format, for writing to memory
+    #[cfg(target_arch = "wasm32")]
+    pub fn encoding(self) -> image::ImageFormat {
+        match self {
+            ImageFormat::Png => image::ImageFormat::Png,
+            ImageFormat::Jpeg => image::ImageFormat::Jpeg,
+            ImageFormat::Bmp => image::ImageFormat::Bmp,
+            ImageFormat::Tiff => image::ImageFormat::Tiff,
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "image/png",
+            ImageFormat::Jpeg => "image/jpeg",
+            ImageFormat::Bmp => "image/bmp",
+            ImageFormat::Tiff => "image/tiff",
+        }
+    }
+}
+
+/// File format of the batch manifest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ManifestFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl ManifestFormat {
+    pub const ALL: [ManifestFormat; 3] = [ManifestFormat::Json, ManifestFormat::Yaml, ManifestFormat::Toml];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ManifestFormat::Json => "json",
+            ManifestFormat::Yaml => "yaml",
+            ManifestFormat::Toml => "toml",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ManifestFormat::Json => "JSON",
+            ManifestFormat::Yaml => "YAML",
+            ManifestFormat::Toml => "TOML",
+        }
+    }
+}
+
+/// Schema version of `dictionary.json`; bump when the layout changes
+pub const DICTIONARY_VERSION: u32 = 2;
+
+/// Flat, machine-oriented marker list for detection pipelines
+#[derive(Debug, Serialize)]
+pub struct DetectionDictionary {
+    pub version: u32,
+    /// Side count shared by every marker; absent when the batch mixes side counts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sides: Option<usize>,
+    /// Wedge index convention shared by every marker's color lists
+    pub wedge_order: &'static str,
+    pub markers: Vec<DictionaryMarker>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DictionaryMarker {
+    /// 1-based, matching `tag_{:02}` filenames and serial numbers
+    pub id: usize,
+    pub sides: usize,
+    pub colors_rgb: Vec<(u8, u8, u8)>,
+    pub colors_lab: Vec<(f32, f32, f32)>,
+}
+
+// Wedge i spans vertex i to vertex i + 1; vertex 0 points straight up (-90°) and
+// angles increase clockwise in image coordinates (y down).
+const WEDGE_ORDER: &str = "clockwise from top: wedge 0 starts at the top vertex (-90 deg) and spans clockwise to the next vertex";
+
+fn write_json<T: Serialize>(value: &T, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = File::create(path)?;
+    let json = serde_json::to_string_pretty(value)?;
+    file.write_all(json.as_bytes())?;
+    Ok(())
+}
+
+/// Write `manifest.<ext>` into `out_dir` in the chosen format
+fn write_manifest<T: Serialize>(value: &T, out_dir: &str, format: ManifestFormat) -> Result<(), Box<dyn std::error::Error>> {
+    let path = format!("{}/manifest.{}", out_dir, format.extension());
+    if format == ManifestFormat::Json {
+        return write_json(value, &path);
+    }
+    fs::write(path, manifest_text(value, format)?)?;
+    Ok(())
+}
+
+/// Text of a manifest in the chosen format
+fn manifest_text<T: Serialize>(value: &T, format: ManifestFormat) -> Result<String, Box<dyn std::error::Error>> {
+    if format == ManifestFormat::Json {
+        return Ok(serde_json::to_string_pretty(value)?);
+    }
+    // Go through JSON text so f32 fields keep their short decimal form
+    let mut tree: serde_json::Value = serde_json::from_str(&serde_json::to_string(value)?)?;
+    Ok(match format {
+        ManifestFormat::Toml => {
+            toml_integers(&mut tree);
+            toml::to_string_pretty(&toml::Value::try_from(&tree)?)?
+        }
+        _ => serde_yaml::to_string(&tree)?,
+    })
+}
+
+/// TOML integers are signed 64-bit; larger values (such as a random seed) become strings
+fn toml_integers(v: &mut serde_json::Value) {
+    match v {
+        serde_json::Value::Number(n) if n.is_u64() && n.as_i64().is_none() => *v = serde_json::Value::String(n.to_string()),
+        serde_json::Value::Array(items) => items.iter_mut().for_each(toml_integers),
+        serde_json::Value::Object(map) => map.values_mut().for_each(toml_integers),
+        _ => {}
+    }
+}
+
+/// Read back a `u64` that `toml_integers` may have written as a string
+fn u64_or_string<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        Number(u64),
+        Text(String),
+    }
+    match Repr::deserialize(deserializer)? {
+        Repr::Number(n) => Ok(n),
+        Repr::Text(s) => s.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+/// Write `summary.txt` (see `summary_text`) into `out_dir`
+fn save_summary(manifest: &Manifest, tags: &[Vec<Rgb<u8>>], opts: &SaveOptions, out_dir: &str) -> Result<(), Box<dyn std::error::Error>> {
+    fs::write(format!("{}/summary.txt", out_dir), summary_text(manifest, tags, opts.lightness_weight)?)?;
+    Ok(())
+}
+
+/// Human-readable QA report of a saved run: tag count, sides, threshold, the weakest tag,
+/// pairwise ΔE statistics of the selection (lightness weighted) and the settings
+fn summary_text(manifest: &Manifest, tags: &[Vec<Rgb<u8>>], lightness_weight: f32) -> Result<String, Box<dyn std::error::Error>> {
+    let settings = &manifest.settings;
+    let sides: Vec<usize> = tags.iter().map(Vec::len).collect();
+    let (min_sides, max_sides) = (sides.iter().min().copied().unwrap_or(0), sides.iter().max().copied().unwrap_or(0));
+    let sides_text = if min_sides == max_sides { min_sides.to_string() } else { format!("mixed ({}-{})", min_sides, max_sides) };
+
+    // Distances over every selected color, orientation wedge excluded
+    let coords: Vec<Lab> = distinguishing_colors(tags, settings.orientation_wedge)
+        .iter()
+        .flatten()
+        .map(|&c| manifest.color_space.weighted_coords(c, lightness_weight))
+        .collect();
+    let n = coords.len();
+    let dm = pairwise_delta_matrix(&coords);
+    let mut pairs: Vec<f32> = (0..n).flat_map(|i| ((i + 1)..n).map(move |j| (i, j))).map(|(i, j)| dm[i * n + j]).collect();
+    pairs.sort_by(f32::total_cmp);
+
+    let mut out = String::new();
+    out.push_str("Poly Cue run summary\n\n");
+    out.push_str(&format!("Tags:               {}\n", tags.len()));
+    out.push_str(&format!("Sides:              {}\n", sides_text));
+    if manifest.tags.iter().any(|t| t.category.is_some()) {
+        let mut counts: Vec<(&str, usize)> = Vec::new();
+        for entry in &manifest.tags {
+            let name = entry.category.as_deref().unwrap_or("(none)");
+            match counts.iter_mut().find(|(n, _)| *n == name) {
+                Some((_, c)) => *c += 1,
+                None => counts.push((name, 1)),
+            }
+        }
+        let list: Vec<String> = counts.iter().map(|(n, c)| format!("{} ({})", n, c)).collect();
+        out.push_str(&format!("Categories:         {}\n", list.join(", ")));
+    }
+    out.push_str(&format!("Threshold:          ΔE {:.2} ({}, {})\n", manifest.threshold, manifest.color_space.label(), manifest.color_space.delta_e_formula()));
+    let weakest = manifest.tags.iter().enumerate().min_by(|(_, a), (_, b)| a.min_pairwise_delta_e.total_cmp(&b.min_pairwise_delta_e));
+    if let Some((i, entry)) = weakest {
+        out.push_str(&format!("Weakest tag:        {} (min ΔE {:.2})\n", i + 1, entry.min_pairwise_delta_e));
+    }
+    if !pairs.is_empty() {
+        let mean = pairs.iter().sum::<f32>() / pairs.len() as f32;
+        let mid = pairs.len() / 2;
+        let median = if pairs.len().is_multiple_of(2) { (pairs[mid - 1] + pairs[mid]) * 0.5 } else { pairs[mid] };
+        out.push_str(&format!("Pairwise ΔE:        mean {:.2}, median {:.2}, min {:.2} over {} colors\n", mean, median, pairs[0], n));
+    }
+    out.push_str(&format!("Reused colors:      {}\n", manifest.reused_colors.len()));
+    if let Some(similar) = &manifest.similar_tags {
+        out.push_str(&format!("Similar tag pairs:  {}\n", similar.len()));
+    }
+
+    out.push_str("\nSettings:\n");
+    if let serde_json::Value::Object(fields) = serde_json::to_value(settings)? {
+        for (key, value) in fields {
+            out.push_str(&format!("  {}: {}\n", key, value));
+        }
+    }
+    Ok(out)
+}
+
+/// Write a detection dictionary for saved tags to `path`. It is built from the tags' manifest
+/// entries, reusing the Lab values computed there. Side counts come from the entries too,
+/// since tags may each have their own; the top-level `sides` is set only when all agree.
+pub fn save_dictionary(entries: &[TagManifestEntry], path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    write_json(&detection_dictionary(entries), path)
+}
+
+fn detection_dictionary(entries: &[TagManifestEntry]) -> DetectionDictionary {
+    let first = entries.first().map_or(0, |e| e.sides);
+    DetectionDictionary {
+        version: DICTIONARY_VERSION,
+        sides: entries.iter().all(|e| e.sides == first).then_some(first),
+        wedge_order: WEDGE_ORDER,
+        markers: entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| DictionaryMarker {
+                id: i + 1,
+                sides: entry.sides,
+                colors_rgb: entry.colors_rgb.clone(),
+                colors_lab: entry.colors_lab.clone(),
+            })
+            .collect(),
+    }
 }
 
+/// Where and how tags are saved, plus the render settings recorded in the manifest
+#[derive(Debug, Clone)]
+pub struct SaveOptions {
+    /// Root directory for timestamped output folders
+    pub out_root: String,
+    pub format: ImageFormat,
+    pub style: MarkerStyle,
+    /// Marker size before any fiducial border is added
+    pub marker_size: (u32, u32),
+    /// Record tag pairs closer than this tag distance in the manifest
+    pub similar_tag_distance: Option<f32>,
+    /// Space the recorded ΔE values are measured in
+    pub color_space: ColorSpace,
+    /// Scale on the lightness term of those ΔE values, as used for the selection
+    pub lightness_weight: f32,
+    /// Images are 16 bits per channel (the combined grid is written to match)
+    pub sixteen_bit: bool,
+    /// Background-colored spacing between tiles of the combined sheet
+    pub gutter_px: u32,
+    /// Reserve a strip under each tile of the combined sheet for its 1-based number
+    pub tile_labels: bool,
+    /// Print resolution used to convert millimeter sizes to pixels
+    pub print_dpi: f32,
+    /// Background-colored bleed added on every side of the combined sheet
+    pub bleed_mm: f32,
+    /// Draw crop marks at the trim box corners, inside the bleed
+    pub trim_marks: bool,
+    /// Put a registration dot in each corner of the combined sheet
+    pub registration_dots: bool,
+    /// Seed the tags were generated with
+    pub seed: u64,
+    /// Write each tag's manifest data into its PNG as an iTXt chunk (PNG only)
+    pub embed_metadata: bool,
+    /// Per-tag rotation in degrees (empty when tags are not individually rotated)
+    pub tag_rotations: Vec<f32>,
+    /// Per-tag colors as selected, before snapping to the grid (empty when snapping is off)
+    pub unsnapped: Vec<Vec<Rgb<u8>>>,
+    /// Also write each wedge of each tag as its own transparent PNG
+    pub wedge_layers: bool,
+    /// Per-tag category (empty when no tag has one)
+    pub categories: Vec<Option<String>>,
+    /// Save All Separate writes each categorized tag into a subfolder named after its category
+    pub category_folders: bool,
+    /// Minimum Lab lightness range the grouping aimed for (0 when off)
+    pub min_lightness_spread: f32,
+    pub manifest_format: ManifestFormat,
+    /// Recorded in the manifest's `settings` section
+    pub settings: GenerationSettings,
+}
+
+/// A color that appears in more than one tag
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReusedColor {
+    pub rgb: (u8, u8, u8),
+    /// 1-based tag numbers containing this color
+    pub tags: Vec<usize>,
+}
+
+/// Two tags whose colors match too closely overall
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SimilarTagPair {
+    /// 1-based tag numbers
+    pub tags: (usize, usize),
+    /// Mean ΔE under the best color matching
+    pub tag_distance: f32,
+}
+
+/// Print geometry of a combined sheet with bleed
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PrintLayout {
+    pub dpi: f32,
+    pub bleed_mm: f32,
+    pub bleed_px: u32,
+    /// Final cut rectangle (x, y, width, height) within the saved sheet
+    pub trim_box: (u32, u32, u32, u32),
+    pub trim_marks: bool,
+}
+
+/// Diameter of the combined sheet's registration dots, and their gap from the trim edges
+pub const REGISTRATION_DOT_MM: f32 = 3.0;
+pub const REGISTRATION_INSET_MM: f32 = 2.0;
+
+/// Corner reference dots of a combined sheet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistrationDots {
+    pub diameter_px: u32,
+    /// Dot centers in sheet pixels, clockwise from top-left
+    pub centers: [(u32, u32); 4],
+}
+
+/// Convert a physical length to pixels at `dpi`
+pub fn mm_to_px(mm: f32, dpi: f32) -> u32 {
+    (mm.max(0.0) / 25.4 * dpi).round() as u32
+}
+
+/// Contents of `manifest.<ext>`: batch-wide settings plus an entry per tag. Saves write it;
+/// verification and resumed saves read it back.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub threshold: f32,
+    #[serde(deserialize_with = "u64_or_string")]
+    pub seed: u64,
+    pub color_space: ColorSpace,
+    pub shape: MarkerShape,
+    /// Bits per channel of the saved images; `colors_rgb` always lists the 8-bit source colors
+    pub bit_depth: u8,
+    /// Gap between the polygon and the marker edge, as a fraction of the shorter side
+    pub margin_pct: f32,
+    /// Color of the center dot, if one is drawn
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub center_dot_rgb: Option<(u8, u8, u8)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub center_shape: Option<CenterShape>,
+    /// Dot diameters as a percentage of the marker's shorter side, for dots that are drawn
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub center_dot_size_pct: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gradient_dot_size_pct: Option<f32>,
+    /// Wedges fade toward white at the centroid (see `render::wedge_gradient_color`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub wedge_gradient: bool,
+    /// Levels per channel of the ordered dithering applied to wedge fills
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dither_levels: Option<u8>,
+    /// Lightness spread the grouping was asked to keep, when that constraint is on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_lightness_spread: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fiducial_border: Option<FiducialGeometry>,
+    /// Ring around the polygon, measured from the marker center like the wedge geometry
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bounding_ring: Option<BoundingRing>,
+    /// Width of the signature band at the save size; each tag's color is in its entry
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature_band_px: Option<u32>,
+    /// Width of the white band around every tag image; `fiducial_border` positions are
+    /// measured inside it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quiet_zone_px: Option<u32>,
+    pub reused_colors: Vec<ReusedColor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub similar_tags: Option<Vec<SimilarTagPair>>,
+    /// Bleed and trim box of the combined sheet, when a bleed was added
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub print_layout: Option<PrintLayout>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registration_dots: Option<RegistrationDots>,
+    /// Absent from manifests written before it was added
+    #[serde(default)]
+    pub settings: GenerationSettings,
+    pub tags: Vec<TagManifestEntry>,
+}
+
+impl Manifest {
+    fn new(threshold: f32, tags: &[Vec<Rgb<u8>>], opts: &SaveOptions) -> Self {
+        let (w, h) = opts.marker_size;
+        let distinct = distinguishing_colors(tags, opts.settings.orientation_wedge);
+        Manifest {
+            threshold,
+            seed: opts.seed,
+            color_space: opts.color_space,
+            shape: opts.style.shape,
+            bit_depth: if opts.sixteen_bit { 16 } else { 8 },
+            margin_pct: opts.style.margin_pct,
+            center_dot_size_pct: opts.style.center_dot.then_some(opts.style.center_dot_size_pct),
+            center_shape: opts.style.center_dot.then_some(opts.style.center_shape),
+            gradient_dot_size_pct: opts.style.gradient_dot.then_some(opts.style.gradient_dot_size_pct),
+            wedge_gradient: opts.style.wedge_gradient,
+            dither_levels: opts.style.dither_levels,
+            min_lightness_spread: (opts.min_lightness_spread > 0.0).then_some(opts.min_lightness_spread),
+            center_dot_rgb: opts.style.center_dot.then(|| {
+                let c = opts.style.center_dot_color;
+                (c[0], c[1], c[2])
+            }),
+            fiducial_border: opts.style.fiducial_border.then(|| fiducial_geometry(w, h)),
+            bounding_ring: bounding_ring(w, h, &opts.style),
+            signature_band_px: Some(opts.style.signature_band_px).filter(|&b| b > 0),
+            quiet_zone_px: Some(opts.style.quiet_zone_px(w, h)).filter(|&q| q > 0),
+            reused_colors: find_reused_colors(&distinct)
+                .into_iter()
+                .map(|(c, idxs)| ReusedColor { rgb: (c[0], c[1], c[2]), tags: idxs.into_iter().map(|i| i + 1).collect() })
+                .collect(),
+            similar_tags: opts.similar_tag_distance.map(|min| {
+                find_similar_tags(&distinct, min, opts.color_space, opts.lightness_weight)
+                    .into_iter()
+                    .map(|(i, j, d)| SimilarTagPair { tags: (i + 1, j + 1), tag_distance: d })
+                    .collect()
+            }),
+            print_layout: None,
+            registration_dots: None,
+            settings: opts.settings.clone(),
+            tags: Vec::new(),
+        }
+    }
+}
+
+/// iTXt keyword under which `save_all` embeds a tag's metadata
+pub const PNG_METADATA_KEYWORD: &str = "polycue";
+
+/// Per-tag provenance embedded in a saved PNG: the tag's manifest entry plus batch settings
 #[derive(Serialize)]
-struct Manifest {
+struct EmbeddedTagMetadata<'a> {
     threshold: f32,
-    tags: Vec<TagManifestEntry>,
+    sides: usize,
+    seed: u64,
+    color_space: ColorSpace,
+    tag: &'a TagManifestEntry,
+}
+
+/// Write an RGB image (8 or 16 bits per channel) as PNG with one iTXt chunk
+fn save_png_with_text(img: &DynamicImage, path: &str, keyword: &str, text: &str) -> Result<(), Box<dyn std::error::Error>> {
+    encode_png_with_text(img, std::io::BufWriter::new(File::create(path)?), keyword, text)
+}
+
+/// Encode an RGB image as PNG with one iTXt chunk into `out`
+fn encode_png_with_text(img: &DynamicImage, out: impl Write, keyword: &str, text: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut encoder = png::Encoder::new(out, img.width(), img.height());
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.add_itxt_chunk(keyword.to_string(), text.to_string())?;
+    let data = match img {
+        DynamicImage::ImageRgb16(buf) => {
+            encoder.set_depth(png::BitDepth::Sixteen);
+            buf.as_raw().iter().flat_map(|v| v.to_be_bytes()).collect()
+        }
+        other => {
+            encoder.set_depth(png::BitDepth::Eight);
+            other.to_rgb8().into_raw()
+        }
+    };
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&data)?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// Stage timings of one regenerate, in milliseconds
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ProfileRow {
+    pub count: usize,
+    pub sides: usize,
+    pub color_select_ms: f64,
+    pub grouping_ms: f64,
+    pub reorder_ms: f64,
+    pub render_ms: f64,
+    pub previews_ms: f64,
+    pub total_ms: f64,
+}
+
+/// Append a timing row to a CSV file, creating it (and writing the header) on first use
+pub fn append_profile_row(path: &str, row: &ProfileRow) -> std::io::Result<()> {
+    if let Some(parent) = Path::new(path).parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if file.metadata()?.len() == 0 {
+        writeln!(file, "timestamp,count,sides,color_select_ms,grouping_ms,reorder_ms,render_ms,previews_ms,total_ms")?;
+    }
+    writeln!(
+        file,
+        "{},{},{},{:.3},{:.3},{:.3},{:.3},{:.3},{:.3}",
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        row.count,
+        row.sides,
+        row.color_select_ms,
+        row.grouping_ms,
+        row.reorder_ms,
+        row.render_ms,
+        row.previews_ms,
+        row.total_ms,
+    )
 }
 
 /// Ensure output directory exists
@@ -30,98 +654,763 @@ pub fn ensure_out_dir(path: &str) -> std::io::Result<()> {
     Ok(())
 }
 
-/// Save all generated tags and manifest to disk
-pub fn save_all(
-    tags: &[Vec<Rgb<u8>>], 
-    threshold: f32, 
-    images: &[DynamicImage], 
-    sides: usize
-) -> Result<(), Box<dyn std::error::Error>> {
-    // Create timestamped subdirectory
+/// Create and return a new timestamped subdirectory of `out_root`. Saves started within
+/// the same second get `_2`, `_3`, … appended instead of sharing a folder; the folder is
+/// claimed with a plain `create_dir`, so even concurrent saves cannot both get it.
+pub fn create_timestamped_dir(out_root: &str) -> std::io::Result<String> {
     let now: DateTime<Local> = Local::now();
     let timestamp = now.format("%Y-%m-%d_%H-%M-%S").to_string();
-    let out_dir = format!("output/{}", timestamp);
-    ensure_out_dir(&out_dir)?;
+    ensure_out_dir(out_root)?;
+    for n in 1.. {
+        let out_dir = if n == 1 { format!("{}/{}", out_root, timestamp) } else { format!("{}/{}_{}", out_root, timestamp, n) };
+        match fs::create_dir(&out_dir) {
+            Ok(()) => return Ok(out_dir),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    unreachable!("ran out of folder suffixes")
+}
 
-    let mut manifest = Manifest { threshold, tags: Vec::new() };
-    
-    for (idx, colors) in tags.iter().enumerate() {
-        let filename = format!("tag_{:02}.png", idx + 1);
-        let path = format!("{}/{}", out_dir, &filename);
-        
-        // Save from the high-resolution buffer
-        if let Some(img) = images.get(idx) {
-            img.save(&path)?;
+/// Open `dir` in the platform's file manager without waiting for it
+pub fn reveal_in_file_manager(dir: &str) -> std::io::Result<()> {
+    let program = if cfg!(target_os = "windows") {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    std::process::Command::new(program).arg(Path::new(dir)).spawn()?;
+    Ok(())
+}
+
+/// Render the pairwise ΔE matrix of `labs` as a grayscale PNG (brighter = farther apart),
+/// normalized to the matrix's off-diagonal min/max. The file is written into `out_dir`
+/// with that range in its name; returns the path written.
+pub fn save_delta_heatmap(labs: &[Lab], out_dir: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let n = labs.len();
+    if n < 2 {
+        return Err("need at least two colors for a heatmap".into());
+    }
+    let dm = pairwise_delta_matrix(labs);
+    let (mut lo, mut hi) = (f32::INFINITY, 0.0f32);
+    for i in 0..n {
+        for j in (i + 1)..n {
+            lo = lo.min(dm[i * n + j]);
+            hi = hi.max(dm[i * n + j]);
         }
+    }
+    let span = (hi - lo).max(f32::EPSILON);
 
-        let labs_vec: Vec<Lab> = colors.iter().copied().map(srgb_u8_to_lab).collect();
-        
-        // Compute min pairwise ΔE
-        let mut min_pair = f32::INFINITY;
-        for i in 0..labs_vec.len() {
-            for j in (i + 1)..labs_vec.len() {
-                let d = delta_e(labs_vec[i], labs_vec[j]);
-                if d < min_pair { min_pair = d; }
+    // Upscale small matrices so individual cells stay visible
+    let cell = (600 / n as u32).max(1);
+    let size = n as u32 * cell;
+    let img = image::GrayImage::from_fn(size, size, |x, y| {
+        let (i, j) = ((y / cell) as usize, (x / cell) as usize);
+        if i == j {
+            return image::Luma([0]);
+        }
+        let t = (dm[i * n + j] - lo) / span;
+        image::Luma([(t.clamp(0.0, 1.0) * 255.0).round() as u8])
+    });
+    let path = format!("{}/delta_heatmap_{:.1}-{:.1}.png", out_dir, lo, hi);
+    img.save(&path)?;
+    Ok(path)
+}
+
+/// Append one Adobe Swatch Exchange block: type, big-endian byte length, then the body
+fn push_ase_block(out: &mut Vec<u8>, block_type: u16, body: &[u8]) {
+    out.extend_from_slice(&block_type.to_be_bytes());
+    out.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    out.extend_from_slice(body);
+}
+
+/// ASE name field: UTF-16 code unit count (including the terminator), UTF-16BE text, 0x0000
+fn ase_name(name: &str) -> Vec<u8> {
+    let units: Vec<u16> = name.encode_utf16().chain(std::iter::once(0)).collect();
+    let mut out = (units.len() as u16).to_be_bytes().to_vec();
+    for u in units {
+        out.extend_from_slice(&u.to_be_bytes());
+    }
+    out
+}
+
+/// Write an Adobe Swatch Exchange (.ase) file with one group per tag ("Tag01") holding
+/// named RGB swatches ("Tag01-1", "Tag01-2", …) in wedge order
+pub fn save_ase(tags: &[Vec<Rgb<u8>>], path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    const GROUP_START: u16 = 0xC001;
+    const GROUP_END: u16 = 0xC002;
+    const COLOR_ENTRY: u16 = 0x0001;
+    const COLOR_TYPE_NORMAL: u16 = 2;
+
+    let mut blocks = Vec::new();
+    let mut block_count: u32 = 0;
+    for (t, colors) in tags.iter().enumerate() {
+        let group = format!("Tag{:02}", t + 1);
+        push_ase_block(&mut blocks, GROUP_START, &ase_name(&group));
+        for (k, c) in colors.iter().enumerate() {
+            let mut body = ase_name(&format!("{}-{}", group, k + 1));
+            body.extend_from_slice(b"RGB ");
+            for v in c.0 {
+                body.extend_from_slice(&(v as f32 / 255.0).to_be_bytes());
             }
+            body.extend_from_slice(&COLOR_TYPE_NORMAL.to_be_bytes());
+            push_ase_block(&mut blocks, COLOR_ENTRY, &body);
         }
+        push_ase_block(&mut blocks, GROUP_END, &[]);
+        block_count += colors.len() as u32 + 2;
+    }
 
-        manifest.tags.push(TagManifestEntry {
-            filename,
-            sides,
-            colors_rgb: colors.iter().map(|c| (c[0], c[1], c[2])).collect(),
-            colors_lab: labs_vec.iter().map(|l| (l.l, l.a, l.b)).collect(),
-            min_pairwise_delta_e: min_pair,
+    let mut file = File::create(path)?;
+    file.write_all(b"ASEF")?;
+    file.write_all(&1u16.to_be_bytes())?; // version 1.0
+    file.write_all(&0u16.to_be_bytes())?;
+    file.write_all(&block_count.to_be_bytes())?;
+    file.write_all(&blocks)?;
+    Ok(())
+}
+
+/// Write a GIMP/Inkscape/Krita palette (.gpl) listing every tag color in wedge order,
+/// named "Tag 01 color 1" and so on
+pub fn save_gpl(tags: &[Vec<Rgb<u8>>], path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let columns = tags.iter().map(|t| t.len()).max().unwrap_or(0);
+    let mut out = format!("GIMP Palette\nName: PolyCue tags\nColumns: {}\n#\n", columns);
+    for (t, colors) in tags.iter().enumerate() {
+        for (k, c) in colors.iter().enumerate() {
+            out.push_str(&format!("{:3} {:3} {:3}\tTag {:02} color {}\n", c[0], c[1], c[2], t + 1, k + 1));
+        }
+    }
+    fs::write(path, out)?;
+    Ok(())
+}
+
+/// Extensions `load_palette` understands
+pub const PALETTE_EXTENSIONS: [&str; 3] = ["hex", "gpl", "json"];
+
+/// Parse one `rrggbb` / `#rrggbb` color
+fn parse_hex_color(s: &str) -> Option<Rgb<u8>> {
+    let s = s.trim().trim_start_matches('#');
+    if s.len() != 6 {
+        return None;
+    }
+    let v = u32::from_str_radix(s, 16).ok()?;
+    Some(Rgb([(v >> 16) as u8, (v >> 8) as u8, v as u8]))
+}
+
+/// Read a palette file, chosen by extension:
+/// - `.hex`: one `rrggbb` color per line
+/// - `.gpl`: GIMP palette (`R G B name` lines)
+/// - `.json`: an array of `"#rrggbb"` strings or `[r, g, b]` arrays, an object with such a
+///   `colors` array, or a PolyCue manifest/dictionary (every tag's `colors_rgb`)
+///
+/// Repeated colors are dropped; an error is returned if nothing parses.
+pub fn load_palette(path: &Path) -> Result<Vec<Rgb<u8>>, Box<dyn std::error::Error>> {
+    let text = fs::read_to_string(path)?;
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+    let mut colors: Vec<Rgb<u8>> = match ext.as_str() {
+        "hex" => text.lines().filter_map(parse_hex_color).collect(),
+        "gpl" => text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#') && !l.starts_with("GIMP") && !l.contains(':'))
+            .filter_map(|l| {
+                let mut it = l.split_whitespace().map(|v| v.parse::<u8>());
+                match (it.next(), it.next(), it.next()) {
+                    (Some(Ok(r)), Some(Ok(g)), Some(Ok(b))) => Some(Rgb([r, g, b])),
+                    _ => None,
+                }
+            })
+            .collect(),
+        "json" => {
+            let value: serde_json::Value = serde_json::from_str(&text)?;
+            let color_of = |v: &serde_json::Value| match v {
+                serde_json::Value::String(s) => parse_hex_color(s),
+                serde_json::Value::Array(c) if c.len() == 3 => {
+                    let ch: Vec<u8> = c.iter().filter_map(|x| x.as_u64()).map(|x| x.min(255) as u8).collect();
+                    (ch.len() == 3).then(|| Rgb([ch[0], ch[1], ch[2]]))
+                }
+                _ => None,
+            };
+            let list: Vec<&serde_json::Value> = if let Some(tags) = value["tags"].as_array() {
+                tags.iter().filter_map(|t| t["colors_rgb"].as_array()).flatten().collect()
+            } else if let Some(colors) = value["colors"].as_array() {
+                colors.iter().collect()
+            } else {
+                value.as_array().map(|a| a.iter().collect()).unwrap_or_default()
+            };
+            list.into_iter().filter_map(color_of).collect()
+        }
+        _ => return Err(format!("unknown palette type `.{}`", ext).into()),
+    };
+    let mut seen = std::collections::HashSet::new();
+    colors.retain(|c| seen.insert(c.0));
+    if colors.is_empty() {
+        return Err("no colors found".into());
+    }
+    Ok(colors)
+}
+
+/// Layout of `atlas.png`, written next to it as `atlas.json`
+#[derive(Debug, Serialize)]
+pub struct Atlas {
+    pub image: String,
+    pub width: u32,
+    pub height: u32,
+    /// Empty space between neighboring tiles, in pixels
+    pub gutter_px: u32,
+    pub tiles: Vec<AtlasTile>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AtlasTile {
+    /// 1-based, matching `tag_{:02}` filenames
+    pub tag: usize,
+    /// Pixel rectangle (x, y, width, height)
+    pub rect: (u32, u32, u32, u32),
+    /// Normalized (u0, v0, u1, v1) with the origin at the top-left; flip v for bottom-left APIs
+    pub uv: (f32, f32, f32, f32),
+}
+
+/// Pack the tag images into a texture atlas whose sides are powers of two, using the same
+/// near-square grid as `save_all_together`, and write `atlas.png` (transparent between
+/// tiles) and `atlas.json` with each tile's rectangle and UVs into `out_dir`
+pub fn save_atlas(images: &[DynamicImage], gutter_px: u32, out_dir: &str) -> Result<String, Box<dyn std::error::Error>> {
+    if images.is_empty() {
+        return Err("no tags to save".into());
+    }
+    let cols = (images.len() as f32).sqrt().ceil() as u32;
+    let rows = (images.len() as u32).div_ceil(cols);
+    let (tw, th) = (images[0].width(), images[0].height());
+    let width = (cols * tw + (cols - 1) * gutter_px).next_power_of_two();
+    let height = (rows * th + (rows - 1) * gutter_px).next_power_of_two();
+    let mut sheet = image::RgbaImage::new(width, height);
+    let mut tiles = Vec::with_capacity(images.len());
+    for (idx, img) in images.iter().enumerate() {
+        let (col, row) = (idx as u32 % cols, idx as u32 / cols);
+        let (x, y) = (col * (tw + gutter_px), row * (th + gutter_px));
+        image::imageops::replace(&mut sheet, &img.to_rgba8(), x as i64, y as i64);
+        let (w, h) = (width as f32, height as f32);
+        tiles.push(AtlasTile {
+            tag: idx + 1,
+            rect: (x, y, tw, th),
+            uv: (x as f32 / w, y as f32 / h, (x + tw) as f32 / w, (y + th) as f32 / h),
         });
     }
+    sheet.save(format!("{}/atlas.png", out_dir))?;
+    let atlas = Atlas { image: "atlas.png".into(), width, height, gutter_px, tiles };
+    let path = format!("{}/atlas.json", out_dir);
+    write_json(&atlas, &path)?;
+    Ok(path)
+}
 
-    let mut file = File::create(format!("{}/manifest.json", out_dir))?;
-    let json = serde_json::to_string_pretty(&manifest)?;
-    file.write_all(json.as_bytes())?;
+/// Save one image to `path`, in the format its extension names. 16-bit images are
+/// reduced to 8 bits for formats that cannot hold them. Returns the path.
+pub fn save_single_image(img: &DynamicImage, path: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    let format = image::ImageFormat::from_path(path)?;
+    let keeps_16bit = matches!(format, image::ImageFormat::Png | image::ImageFormat::Tiff);
+    if matches!(img, DynamicImage::ImageRgb16(_)) && !keeps_16bit {
+        DynamicImage::ImageRgb8(img.to_rgb8()).save_with_format(path, format)?;
+    } else {
+        img.save_with_format(path, format)?;
+    }
+    Ok(path.display().to_string())
+}
+
+/// Length of one full turn of a spin GIF, whatever its frame count
+pub const SPIN_GIF_LOOP_MS: u32 = 3000;
+
+/// Write an animated GIF of one tag making a full clockwise turn in `frames` evenly spaced
+/// steps, looping forever. Frames are rendered `size` pixels square with `style`, on top of
+/// its own rotation. A flat tag has far fewer than 256 colors, so the GIF palette holds them
+/// exactly; only gradient dots and wedge gradients get quantized.
+pub fn save_rotating_gif(
+    colors: &[Rgb<u8>],
+    sides: usize,
+    style: &MarkerStyle,
+    size: u32,
+    frames: usize,
+    path: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use image::codecs::gif::{GifEncoder, Repeat};
+    let frames = frames.max(1);
+    let delay = image::Delay::from_numer_denom_ms(SPIN_GIF_LOOP_MS, frames as u32);
+    let images: Vec<image::RgbaImage> = (0..frames)
+        .into_par_iter()
+        .map(|k| {
+            let turned = style.rotated(360.0 * k as f32 / frames as f32);
+            DynamicImage::ImageRgb8(draw_marker_polygon(size, size, sides, colors, &turned, None)).to_rgba8()
+        })
+        .collect();
+    let mut encoder = GifEncoder::new_with_speed(std::io::BufWriter::new(File::create(path)?), 10);
+    encoder.set_repeat(Repeat::Infinite)?;
+    encoder.encode_frames(images.into_iter().map(|img| image::Frame::from_parts(img, 0, 0, delay)))?;
     Ok(())
 }
 
-/// Save all tags combined into a single grid image
-pub fn save_all_together(
+/// Geometry of one wedge for machining or engraving
+#[derive(Debug, Serialize)]
+pub struct WedgeSpecEntry {
+    pub index: usize,
+    /// Angles in degrees, clockwise from the +x axis with y pointing down (image
+    /// coordinates); `end_deg - start_deg` is always 360 / wedges
+    pub start_deg: f32,
+    pub end_deg: f32,
+    /// Marker center, start corner and end corner, relative to the center at unit radius
+    pub vertices: [(f32, f32); 3],
+    pub hex: String,
+    pub rgb: (u8, u8, u8),
+}
+
+/// Angular layout of one tag, as written by `save_wedge_spec`
+#[derive(Debug, Serialize)]
+pub struct WedgeSpec {
+    pub wedge_order: &'static str,
+    pub shape: MarkerShape,
+    /// Outer edge between the two corners: "straight" for polygons, "arc" for sectors
+    pub edge: &'static str,
+    pub rotation_deg: f32,
+    pub wedges: Vec<WedgeSpecEntry>,
+}
+
+/// Angle range, unit-radius corners and color of each wedge of a tag drawn with `shape`
+/// and turned clockwise by `rotation_deg`, matching `draw_marker_polygon`
+pub fn wedge_spec(colors: &[Rgb<u8>], shape: MarkerShape, sides: usize, rotation_deg: f32) -> WedgeSpec {
+    let wedges = shape.wedges(sides).max(1);
+    let step = 360.0 / wedges as f32;
+    let corner = |deg: f32| {
+        let a = deg.to_radians();
+        (a.cos(), a.sin())
+    };
+    let wedges = (0..wedges)
+        .map(|i| {
+            let start_deg = -90.0 + rotation_deg + step * i as f32;
+            let end_deg = start_deg + step;
+            let c = colors[i % colors.len()];
+            WedgeSpecEntry {
+                index: i,
+                start_deg,
+                end_deg,
+                vertices: [(0.0, 0.0), corner(start_deg), corner(end_deg)],
+                hex: format!("#{:02X}{:02X}{:02X}", c[0], c[1], c[2]),
+                rgb: (c[0], c[1], c[2]),
+            }
+        })
+        .collect();
+    let edge = match shape {
+        MarkerShape::Polygon => "straight",
+        MarkerShape::Sectors { .. } => "arc",
+    };
+    WedgeSpec { wedge_order: WEDGE_ORDER, shape, edge, rotation_deg, wedges }
+}
+
+/// Write `wedge_spec` for one tag as JSON
+pub fn save_wedge_spec(colors: &[Rgb<u8>], shape: MarkerShape, sides: usize, rotation_deg: f32, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if colors.is_empty() {
+        return Err("tag has no colors".into());
+    }
+    write_json(&wedge_spec(colors, shape, sides, rotation_deg), path)
+}
+
+/// Draw a legend image: one row per tag with its number and a labeled swatch per color,
+/// in wedge order. The image grows in height with the tag count.
+pub fn save_legend(tags: &[Vec<Rgb<u8>>], path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    const PAD: u32 = 16;
+    const ROW_H: u32 = 44;
+    const SWATCH_W: u32 = 124;
+    const SWATCH_H: u32 = 36;
+    const GAP: u32 = 6;
+    const FONT_PX: f32 = 20.0;
+
+    if tags.is_empty() {
+        return Err("no tags to draw".into());
+    }
+    let max_colors = tags.iter().map(|t| t.len()).max().unwrap_or(0) as u32;
+    let label_w = text_width(&format!("Tag {:02}:", tags.len()), FONT_PX).ceil() as u32 + GAP * 2;
+    let width = PAD * 2 + label_w + max_colors * (SWATCH_W + GAP);
+    let height = PAD * 2 + tags.len() as u32 * ROW_H;
+    let mut img = image::RgbImage::from_pixel(width, height, Rgb([255, 255, 255]));
+
+    let text_y_offset = ((ROW_H as f32 - FONT_PX) * 0.5) as i32 - 4;
+    for (t, colors) in tags.iter().enumerate() {
+        let y = PAD + t as u32 * ROW_H;
+        draw_text(&mut img, &format!("Tag {:02}:", t + 1), PAD as i32, y as i32 + text_y_offset, FONT_PX, Rgb([0, 0, 0]));
+        for (k, c) in colors.iter().enumerate() {
+            let x = PAD + label_w + k as u32 * (SWATCH_W + GAP);
+            let sy = y + (ROW_H - SWATCH_H) / 2;
+            for py in sy..sy + SWATCH_H {
+                for px in x..x + SWATCH_W {
+                    img.put_pixel(px, py, *c);
+                }
+            }
+            // Hex label in black or white, whichever reads better on the swatch
+            let ink = if srgb_u8_to_lab(*c).l > 55.0 { Rgb([0, 0, 0]) } else { Rgb([255, 255, 255]) };
+            let hex = format!("#{:02X}{:02X}{:02X}", c[0], c[1], c[2]);
+            let tx = x as f32 + (SWATCH_W as f32 - text_width(&hex, FONT_PX * 0.7)) * 0.5;
+            draw_text(&mut img, &hex, tx as i32, y as i32 + text_y_offset + 2, FONT_PX * 0.7, ink);
+        }
+    }
+    img.save(path)?;
+    Ok(())
+}
+
+/// Draw one tag at each of `scales` (fractions of `base_w`) side by side, smallest last,
+/// with the percentage under each. Each size is downscaled in linear light from a single
+/// `base_w` render, like the scaled previews in the GUI.
+pub fn save_distance_chart(
+    colors: &[Rgb<u8>],
+    sides: usize,
+    style: &MarkerStyle,
+    base_w: u32,
+    scales: &[f32],
+    path: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    const PAD: u32 = 16;
+    const GAP: u32 = 12;
+    const FONT_PX: f32 = 14.0;
+
+    if scales.is_empty() {
+        return Err("no scales given".into());
+    }
+    let base = draw_marker_polygon(base_w, base_w, sides, colors, style, None);
+    let tiles: Vec<(image::RgbImage, String)> = scales
+        .iter()
+        .map(|s| {
+            let w = (base_w as f32 * s).round().max(2.0) as u32;
+            (downscale_linear(&base, w, w), format!("{}%", (s * 100.0).round()))
+        })
+        .collect();
+    let columns: Vec<u32> = tiles
+        .iter()
+        .map(|(img, label)| img.width().max(text_width(label, FONT_PX).ceil() as u32))
+        .collect();
+    let tallest = tiles.iter().map(|(img, _)| img.height()).max().unwrap_or(0);
+    let width = PAD * 2 + columns.iter().sum::<u32>() + GAP * (columns.len() as u32 - 1);
+    let height = PAD * 2 + tallest + GAP / 2 + FONT_PX.ceil() as u32 + 4;
+    let mut chart = image::RgbImage::from_pixel(width, height, Rgb([255, 255, 255]));
+
+    // Tiles sit on a shared baseline, labels centered under their column
+    let mut x = PAD;
+    for ((img, label), col_w) in tiles.iter().zip(&columns) {
+        let tx = x + (col_w - img.width()) / 2;
+        let ty = PAD + tallest - img.height();
+        image::imageops::replace(&mut chart, img, tx as i64, ty as i64);
+        let lx = x as f32 + (*col_w as f32 - text_width(label, FONT_PX)) * 0.5;
+        draw_text(&mut chart, label, lx as i32, (PAD + tallest + GAP / 2) as i32, FONT_PX, Rgb([0, 0, 0]));
+        x += col_w + GAP;
+    }
+    chart.save(path)?;
+    Ok(())
+}
+
+/// Colors of tag `idx` before snapping, if snapping changed any. Colors edited after
+/// snapping no longer match their snapped originals, so a tag with any is left out.
+fn unsnapped_rgb(opts: &SaveOptions, idx: usize, colors: &[Rgb<u8>]) -> Option<Vec<(u8, u8, u8)>> {
+    let original = opts.unsnapped.get(idx).filter(|o| o.len() == colors.len())?;
+    let consistent = original.iter().zip(colors).all(|(&o, &c)| o == c || snap_to_grid(o) == c);
+    (consistent && original.as_slice() != colors).then(|| original.iter().map(|c| (c[0], c[1], c[2])).collect())
+}
+
+/// Error message of a save stopped through its progress callback
+pub const SAVE_CANCELLED: &str = "cancelled";
+
+/// Save all generated tags and manifest to disk. Returns the output folder.
+/// Tags are encoded in parallel; `progress` is called (from worker threads) after each tag
+/// is written, and returning false cancels the remaining writes.
+pub fn save_all(
     tags: &[Vec<Rgb<u8>>], 
     threshold: f32, 
     images: &[DynamicImage], 
-    sides: usize
+    opts: &SaveOptions,
+    progress: &(dyn Fn() -> bool + Sync),
+) -> Result<String, Box<dyn std::error::Error>> {
+    let out_dir = create_timestamped_dir(&opts.out_root)?;
+    save_all_to_dir(&out_dir, tags, threshold, images, opts, progress)?;
+    Ok(out_dir)
+}
+
+/// Manifest of a Save All Separate, with an entry (file names included) for every tag
+fn separate_manifest(tags: &[Vec<Rgb<u8>>], threshold: f32, opts: &SaveOptions) -> Manifest {
+    let mut manifest = Manifest::new(threshold, tags, opts);
+    for (idx, colors) in tags.iter().enumerate() {
+        let labs_vec: Vec<Lab> = colors.iter().copied().map(srgb_u8_to_lab).collect();
+
+        let category = opts.categories.get(idx).cloned().flatten();
+        let folder = match category.as_deref().map(category_folder_name) {
+            Some(name) if opts.category_folders && !name.is_empty() => format!("{}/", name),
+            _ => String::new(),
+        };
+        manifest.tags.push(TagManifestEntry {
+            filename: format!("{}tag_{:02}.{}", folder, idx + 1, opts.format.extension()),
+            category,
+            sides: colors.len(),
+            colors_rgb: colors.iter().map(|c| (c[0], c[1], c[2])).collect(),
+            colors_lab: labs_vec.iter().map(|l| (l.l, l.a, l.b)).collect(),
+            min_pairwise_delta_e: min_pairwise_delta_e(colors, opts.color_space, opts.lightness_weight),
+            min_adjacent_delta_e: min_adjacent_delta_e(colors, opts.color_space),
+            unsnapped_rgb: unsnapped_rgb(opts, idx, colors),
+            signature_rgb: opts.style.signature_band_rgb(colors).map(|c| (c[0], c[1], c[2])),
+            lightness_spread: lightness_spread(colors),
+            nearest_pantone: pantone_matches(&labs_vec),
+            combined_rect: None,
+            rotation_deg: opts.tag_rotations.get(idx).copied(),
+            wedge_files: opts.wedge_layers.then(|| {
+                colors
+                    .iter()
+                    .enumerate()
+                    .map(|(k, c)| WedgeFile {
+                        filename: format!("{}tag_{:02}_wedge_{}.png", folder, idx + 1, k + 1),
+                        rgb: (c[0], c[1], c[2]),
+                    })
+                    .collect()
+            }),
+        });
+    }
+    manifest
+}
+
+/// Like `save_all`, but writes into the existing folder `out_dir`
+pub fn save_all_to_dir(
+    out_dir: &str,
+    tags: &[Vec<Rgb<u8>>],
+    threshold: f32,
+    images: &[DynamicImage],
+    opts: &SaveOptions,
+    progress: &(dyn Fn() -> bool + Sync),
+) -> Result<(), Box<dyn std::error::Error>> {
+    let images: Vec<Option<&DynamicImage>> = (0..tags.len()).map(|i| images.get(i)).collect();
+    let manifest = write_tag_files(out_dir, tags, threshold, &images, opts, progress)?;
+    write_manifest(&manifest, out_dir, opts.manifest_format)?;
+    save_dictionary(&manifest.tags, &format!("{}/dictionary.json", out_dir))?;
+    save_summary(&manifest, tags, opts, out_dir)?;
+    Ok(())
+}
+
+/// Tag images already in an output folder, as found by `scan_output`
+#[derive(Debug, Default)]
+pub struct OutputScan {
+    /// 0-based index and pixel size of every readable `tag_NN.<ext>`
+    pub existing: Vec<(usize, (u32, u32))>,
+    /// Whether the folder has a manifest (in any format) to merge into
+    pub has_manifest: bool,
+}
+
+/// List the `tag_NN` images of `format` in `dir` whose header can be read. A file cut off
+/// mid-write usually still has a readable header, so sizes are returned for the caller to check.
+pub fn scan_output(dir: &str, format: ImageFormat) -> std::io::Result<OutputScan> {
+    let has_manifest = ManifestFormat::ALL.iter().any(|f| Path::new(dir).join(format!("manifest.{}", f.extension())).is_file());
+    let mut scan = OutputScan { has_manifest, ..Default::default() };
+    // Tags sit at the top level or one level down, in category subfolders
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            paths.extend(fs::read_dir(&path)?.filter_map(|e| e.ok()).map(|e| e.path()));
+        } else {
+            paths.push(path);
+        }
+    }
+    for path in paths {
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else { continue };
+        let Some(number) = name.strip_prefix("tag_").and_then(|n| n.strip_suffix(&format!(".{}", format.extension()))) else { continue };
+        // Only plain `tag_NN`, not wedge layers or combined-sheet entries
+        let Ok(number) = number.parse::<usize>() else { continue };
+        if number == 0 {
+            continue;
+        }
+        if let Ok(size) = image::image_dimensions(&path) {
+            scan.existing.push((number - 1, size));
+        }
+    }
+    scan.existing.sort_unstable();
+    Ok(scan)
+}
+
+/// Finish an interrupted Save All Separate in `out_dir`: write the tags that have an image in
+/// `images` (the missing ones), then merge the manifest. Entries for files written now are
+/// replaced, other entries already in the manifest are kept, and tags it lacks are added.
+/// Without a manifest it is rebuilt from the current tags.
+pub fn resume_save_to_dir(
+    out_dir: &str,
+    tags: &[Vec<Rgb<u8>>],
+    threshold: f32,
+    images: &[Option<DynamicImage>],
+    opts: &SaveOptions,
+    progress: &(dyn Fn() -> bool + Sync),
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let refs: Vec<Option<&DynamicImage>> = (0..tags.len()).map(|i| images.get(i).and_then(Option::as_ref)).collect();
+    let manifest = write_tag_files(out_dir, tags, threshold, &refs, opts, progress)?;
+
+    save_dictionary(&manifest.tags, &format!("{}/dictionary.json", out_dir))?;
+    save_summary(&manifest, tags, opts, out_dir)?;
+
+    let (mut old, old_format) = match read_manifest(out_dir)? {
+        Some((m, format)) => (m.tags, Some(format)),
+        None => (Vec::new(), None),
+    };
+    let mut merged = Manifest { tags: Vec::new(), ..manifest };
+    for (entry, image) in manifest.tags.into_iter().zip(&refs) {
+        let kept = old.iter().position(|e| e.filename == entry.filename).filter(|_| image.is_none());
+        merged.tags.push(match kept {
+            Some(i) => old.remove(i),
+            None => entry,
+        });
+    }
+    // Files from a larger batch than the current one stay listed
+    old.retain(|e| !merged.tags.iter().any(|t| t.filename == e.filename));
+    merged.tags.append(&mut old);
+
+    // The merged manifest replaces one written in another format rather than sitting beside it
+    if let Some(format) = old_format.filter(|&f| f != opts.manifest_format) {
+        fs::remove_file(format!("{}/manifest.{}", out_dir, format.extension()))?;
+    }
+    write_manifest(&merged, out_dir, opts.manifest_format)
+}
+
+/// Read the manifest a save left in `out_dir`, in whichever format it was written, along
+/// with that format. `None` when the folder has no manifest.
+pub fn read_manifest(out_dir: &str) -> Result<Option<(Manifest, ManifestFormat)>, Box<dyn std::error::Error>> {
+    for format in ManifestFormat::ALL {
+        let Ok(text) = fs::read_to_string(format!("{}/manifest.{}", out_dir, format.extension())) else { continue };
+        let manifest = match format {
+            ManifestFormat::Json => serde_json::from_str(&text)?,
+            ManifestFormat::Yaml => serde_yaml::from_str(&text)?,
+            ManifestFormat::Toml => toml::from_str(&text)?,
+        };
+        return Ok(Some((manifest, format)));
+    }
+    Ok(None)
+}
+
+/// Folder name for a category: characters that are not safe in paths become `_`, and
+/// surrounding spaces and dots are dropped
+pub fn category_folder_name(category: &str) -> String {
+    category
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.') { c } else { '_' })
+        .collect::<String>()
+        .trim_matches(|c: char| c == ' ' || c == '.')
+        .to_string()
+}
+
+/// Write the tag (and wedge) files of every tag that has an image in `images`, and return
+/// the manifest with an entry for every tag
+fn write_tag_files(
+    out_dir: &str,
+    tags: &[Vec<Rgb<u8>>],
+    threshold: f32,
+    images: &[Option<&DynamicImage>],
+    opts: &SaveOptions,
+    progress: &(dyn Fn() -> bool + Sync),
+) -> Result<Manifest, Box<dyn std::error::Error>> {
+    // Manifest entries first, in index order; they are cheap next to encoding
+    let manifest = separate_manifest(tags, threshold, opts);
+    for folder in manifest.tags.iter().filter_map(|e| e.filename.rsplit_once('/')).map(|(folder, _)| folder) {
+        fs::create_dir_all(format!("{}/{}", out_dir, folder))?;
+    }
+
+    // Encode and write the images in parallel, each to its own path. With fast encoders
+    // (BMP, uncompressed TIFF) disk throughput rather than CPU may set the pace.
+    let entries = &manifest.tags;
+    tags.par_iter().enumerate().try_for_each(|(idx, colors)| -> Result<(), String> {
+        let entry = &entries[idx];
+        let Some(img) = images[idx] else { return Ok(()) };
+        let write = || -> Result<(), Box<dyn std::error::Error>> {
+            // Wedges are drawn fresh at the marker size, with this tag's rotation
+            if let Some(files) = &entry.wedge_files {
+                let (w, h) = opts.marker_size;
+                let style = opts.style.rotated(entry.rotation_deg.unwrap_or(0.0));
+                for (k, file) in files.iter().enumerate() {
+                    draw_wedge_layer(w, h, colors.len(), colors, &style, k).save(format!("{}/{}", out_dir, file.filename))?;
+                }
+            }
+
+            // Save from the high-resolution buffer
+            let path = format!("{}/{}", out_dir, entry.filename);
+            if opts.embed_metadata && opts.format == ImageFormat::Png {
+                let meta = EmbeddedTagMetadata {
+                    threshold,
+                    sides: entry.sides,
+                    seed: opts.seed,
+                    color_space: opts.color_space,
+                    tag: entry,
+                };
+                save_png_with_text(img, &path, PNG_METADATA_KEYWORD, &serde_json::to_string(&meta)?)?;
+            } else {
+                img.save(&path)?;
+            }
+            Ok(())
+        };
+        write().map_err(|e| e.to_string())?;
+        if !progress() {
+            return Err(SAVE_CANCELLED.into());
+        }
+        Ok(())
+    })?;
+    Ok(manifest)
+}
+
+/// Combined grid image from `build_combined_image`, with the layout the manifest records
+pub struct CombinedSheet {
+    pub image: RgbImage,
+    /// Top-left corner of each tile, in tag order
+    pub origins: Vec<(u32, u32)>,
+    pub tile_size: (u32, u32),
+    pub bleed_px: u32,
+    /// Sheet without the bleed (x, y, width, height)
+    pub trim_box: (u32, u32, u32, u32),
+    pub trim_marks: bool,
+    pub registration_dots: Option<RegistrationDots>,
+}
+
+/// Arrange tag images into one grid image with the labels, gutter, bleed, trim marks and
+/// registration dots from `opts`. `columns` fixes the grid width (`None` picks a roughly
+/// square grid); tags fill it row by row. `progress` is called after each tile is placed;
+/// returning false cancels.
+pub fn build_combined_image(
+    images: &[DynamicImage],
+    columns: Option<usize>,
+    opts: &SaveOptions,
+    progress: &(dyn Fn() -> bool + Sync),
+) -> Result<CombinedSheet, Box<dyn std::error::Error>> {
     if images.is_empty() {
-        return Ok(());
+        return Err("no tag images to combine".into());
     }
-    
-    // Create timestamped subdirectory
-    let now: DateTime<Local> = Local::now();
-    let timestamp = now.format("%Y-%m-%d_%H-%M-%S").to_string();
-    let out_dir = format!("output/{}", timestamp);
-    ensure_out_dir(&out_dir)?;
 
-    // Calculate grid dimensions (try to make it roughly square)
+    // Calculate grid dimensions (the given column count, or roughly square)
     let count = images.len();
-    let cols = (count as f32).sqrt().ceil() as usize;
-    let rows = (count + cols - 1) / cols; // Ceiling division
+    let cols = columns.unwrap_or_else(|| (count as f32).sqrt().ceil() as usize).clamp(1, count);
+    let rows = count.div_ceil(cols);
     
     // Get individual image size (assuming all are same size)
     let img_width = images[0].width();
     let img_height = images[0].height();
     
-    // Create combined image
-    let combined_width = cols as u32 * img_width;
-    let combined_height = rows as u32 * img_height;
-    let mut combined = image::ImageBuffer::new(combined_width, combined_height);
-    
-    // Fill with white background
-    for pixel in combined.pixels_mut() {
-        *pixel = image::Rgb([255, 255, 255]);
-    }
+    // Optional label strip under every tile, sized relative to the tile
+    let label_px = (img_height as f32 * 0.08).clamp(12.0, 96.0);
+    let (ink_top, ink_bottom) = text_ink_extent("0123456789", label_px);
+    let ink_h = ink_bottom - ink_top;
+    let label_h = if opts.tile_labels { (ink_h * 2.0).ceil() as u32 } else { 0 };
+
+    // Create combined image, with `gutter_px` between neighboring tiles (not around the sheet)
+    // and the bleed, if any, around the whole grid
+    let gutter = opts.gutter_px;
+    let bleed = mm_to_px(opts.bleed_mm, opts.print_dpi);
+    let cell_height = img_height + label_h;
+    let trim_width = cols as u32 * img_width + (cols as u32 - 1) * gutter;
+    let trim_height = rows as u32 * cell_height + (rows as u32 - 1) * gutter;
+    let combined_width = trim_width + 2 * bleed;
+    let combined_height = trim_height + 2 * bleed;
+    let mut combined = image::ImageBuffer::from_pixel(combined_width, combined_height, opts.style.bg);
+    let cell_origin = |idx: usize| {
+        let col = (idx % cols) as u32;
+        let row = (idx / cols) as u32;
+        (bleed + col * (img_width + gutter), bleed + row * (cell_height + gutter))
+    };
     
     // Place each tag image in the grid
     for (idx, img) in images.iter().enumerate() {
-        let col = idx % cols;
-        let row = idx / cols;
-        let x_offset = col as u32 * img_width;
-        let y_offset = row as u32 * img_height;
+        let (x_offset, y_offset) = cell_origin(idx);
         
         let rgb_img = img.to_rgb8();
         for (x, y, pixel) in rgb_img.enumerate_pixels() {
@@ -129,39 +1418,373 @@ pub fn save_all_together(
                 combined.put_pixel(x_offset + x, y_offset + y, *pixel);
             }
         }
+
+        if opts.tile_labels {
+            // Same numbering as the tag_{:02} files, in black or white against the background
+            let label = format!("{:02}", idx + 1);
+            let ink = if srgb_u8_to_lab(opts.style.bg).l > 55.0 { Rgb([0, 0, 0]) } else { Rgb([255, 255, 255]) };
+            let tx = x_offset as f32 + (img_width as f32 - text_width(&label, label_px)) * 0.5;
+            let ty = (y_offset + img_height) as f32 + (label_h as f32 - ink_h) * 0.5 - ink_top;
+            draw_text(&mut combined, &label, tx as i32, ty.round() as i32, label_px, ink);
+        }
+        if !progress() {
+            return Err(SAVE_CANCELLED.into());
+        }
     }
     
+    let trim_marks = opts.trim_marks && bleed > 0;
+    if trim_marks {
+        // Hairline about 0.25 pt thick, clear of the trim corner by a third of the bleed
+        let thickness = (opts.print_dpi / 288.0).round().max(1.0) as u32;
+        let gap = (bleed / 3).max(1);
+        let ink = if srgb_u8_to_lab(opts.style.bg).l > 55.0 { Rgb([0, 0, 0]) } else { Rgb([255, 255, 255]) };
+        draw_trim_marks(&mut combined, (bleed, bleed, trim_width, trim_height), bleed - gap, gap, thickness, ink);
+    }
+
+    // Registration dots go inside the trim box corners, on top of whatever tiles are there
+    let registration_dots = opts.registration_dots.then(|| {
+        let d = mm_to_px(REGISTRATION_DOT_MM, opts.print_dpi).max(2);
+        let inset = mm_to_px(REGISTRATION_INSET_MM, opts.print_dpi) + d / 2;
+        let (x0, y0) = (bleed + inset, bleed + inset);
+        let (x1, y1) = ((bleed + trim_width).saturating_sub(inset), (bleed + trim_height).saturating_sub(inset));
+        let ink = if srgb_u8_to_lab(opts.style.bg).l > 55.0 { Rgb([0, 0, 0]) } else { Rgb([255, 255, 255]) };
+        let centers = [(x0, y0), (x1, y0), (x1, y1), (x0, y1)];
+        for &(x, y) in &centers {
+            fill_disc(&mut combined, (x as f32 + 0.5, y as f32 + 0.5), d as f32 * 0.5, ink);
+        }
+        RegistrationDots { diameter_px: d, centers }
+    });
+
+    Ok(CombinedSheet {
+        image: combined,
+        origins: (0..count).map(cell_origin).collect(),
+        tile_size: (img_width, img_height),
+        bleed_px: bleed,
+        trim_box: (bleed, bleed, trim_width, trim_height),
+        trim_marks,
+        registration_dots,
+    })
+}
+
+/// Save all tags combined into a single grid image (see `build_combined_image`). Returns
+/// the output folder.
+pub fn save_all_together(
+    tags: &[Vec<Rgb<u8>>], 
+    threshold: f32, 
+    images: &[DynamicImage], 
+    columns: Option<usize>,
+    opts: &SaveOptions,
+    progress: &(dyn Fn() -> bool + Sync),
+) -> Result<String, Box<dyn std::error::Error>> {
+    if images.is_empty() {
+        return Err("no tags to save".into());
+    }
+    let sheet = build_combined_image(images, columns, opts, progress)?;
+    let out_dir = create_timestamped_dir(&opts.out_root)?;
+    let manifest = combined_manifest(tags, threshold, &sheet, opts);
+
     // Save combined image
-    let combined_path = format!("{}/all_tags_combined.png", out_dir);
-    image::DynamicImage::ImageRgb8(combined).save(&combined_path)?;
-    
+    let combined_path = format!("{}/all_tags_combined.{}", out_dir, opts.format.extension());
+    combined_output(sheet.image, opts).save(&combined_path)?;
+
     // Also save manifest
-    let mut manifest = Manifest { threshold, tags: Vec::new() };
+    write_manifest(&manifest, &out_dir, opts.manifest_format)?;
+    save_dictionary(&manifest.tags, &format!("{}/dictionary.json", out_dir))?;
+    save_summary(&manifest, tags, opts, &out_dir)?;
+    Ok(out_dir)
+}
+
+/// The combined sheet at the bit depth it is saved with
+fn combined_output(sheet: RgbImage, opts: &SaveOptions) -> DynamicImage {
+    let combined = DynamicImage::ImageRgb8(sheet);
+    if opts.sixteen_bit {
+        DynamicImage::ImageRgb16(combined.into_rgb16())
+    } else {
+        combined
+    }
+}
+
+/// Manifest of a Save All Together, locating each tag on the combined sheet
+fn combined_manifest(tags: &[Vec<Rgb<u8>>], threshold: f32, sheet: &CombinedSheet, opts: &SaveOptions) -> Manifest {
+    let mut manifest = Manifest::new(threshold, tags, opts);
+    manifest.print_layout = (sheet.bleed_px > 0).then_some(PrintLayout {
+        dpi: opts.print_dpi,
+        bleed_mm: opts.bleed_mm,
+        bleed_px: sheet.bleed_px,
+        trim_box: sheet.trim_box,
+        trim_marks: sheet.trim_marks,
+    });
+    manifest.registration_dots = sheet.registration_dots.clone();
+    let (img_width, img_height) = sheet.tile_size;
     
     for (idx, colors) in tags.iter().enumerate() {
-        let filename = format!("tag_{:02}_in_combined.png", idx + 1);
+        let filename = format!("tag_{:02}_in_combined.{}", idx + 1, opts.format.extension());
+        let (x, y) = sheet.origins[idx];
         let labs_vec: Vec<Lab> = colors.iter().copied().map(srgb_u8_to_lab).collect();
-        
-        // Compute min pairwise ΔE
-        let mut min_pair = f32::INFINITY;
-        for i in 0..labs_vec.len() {
-            for j in (i + 1)..labs_vec.len() {
-                let d = delta_e(labs_vec[i], labs_vec[j]);
-                if d < min_pair { min_pair = d; }
-            }
-        }
 
         manifest.tags.push(TagManifestEntry {
             filename,
-            sides,
+            category: opts.categories.get(idx).cloned().flatten(),
+            sides: colors.len(),
             colors_rgb: colors.iter().map(|c| (c[0], c[1], c[2])).collect(),
             colors_lab: labs_vec.iter().map(|l| (l.l, l.a, l.b)).collect(),
-            min_pairwise_delta_e: min_pair,
+            min_pairwise_delta_e: min_pairwise_delta_e(colors, opts.color_space, opts.lightness_weight),
+            min_adjacent_delta_e: min_adjacent_delta_e(colors, opts.color_space),
+            unsnapped_rgb: unsnapped_rgb(opts, idx, colors),
+            signature_rgb: opts.style.signature_band_rgb(colors).map(|c| (c[0], c[1], c[2])),
+            lightness_spread: lightness_spread(colors),
+            nearest_pantone: pantone_matches(&labs_vec),
+            combined_rect: Some((x, y, img_width, img_height)),
+            rotation_deg: opts.tag_rotations.get(idx).copied(),
+            wedge_files: None,
         });
     }
+    manifest
+}
 
-    let mut file = File::create(format!("{}/manifest.json", out_dir))?;
-    let json = serde_json::to_string_pretty(&manifest)?;
-    file.write_all(json.as_bytes())?;
+/// Where browser saves end up, for status messages in place of a folder path
+#[cfg(target_arch = "wasm32")]
+pub const BROWSER_DOWNLOADS: &str = "the browser's downloads";
+
+/// Hand `bytes` to the browser as a download named `file_name`
+#[cfg(target_arch = "wasm32")]
+pub fn download_bytes(file_name: &str, bytes: &[u8], mime_type: &str) -> Result<(), Box<dyn std::error::Error>> {
+    use wasm_bindgen::JsCast;
+    let js_error = |e: wasm_bindgen::JsValue| -> Box<dyn std::error::Error> { format!("{:?}", e).into() };
+    let document = web_sys::window().and_then(|w| w.document()).ok_or("no browser document")?;
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type(mime_type);
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes));
+    let blob = web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &options).map_err(js_error)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob).map_err(js_error)?;
+    let link: web_sys::HtmlAnchorElement = document.create_element("a").map_err(js_error)?.dyn_into().map_err(|_| "not a link element")?;
+    link.set_href(&url);
+    link.set_download(file_name);
+    link.click();
+    web_sys::Url::revoke_object_url(&url).map_err(js_error)?;
+    Ok(())
+}
+
+/// Encode `img` in `format` into memory
+#[cfg(target_arch = "wasm32")]
+fn encode_image(img: &DynamicImage, format: ImageFormat) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut bytes = Vec::new();
+    img.write_to(&mut std::io::Cursor::new(&mut bytes), format.encoding())?;
+    Ok(bytes)
+}
+
+/// Download the manifest, dictionary and summary that go next to saved tags
+#[cfg(target_arch = "wasm32")]
+fn download_manifest(manifest: &Manifest, tags: &[Vec<Rgb<u8>>], opts: &SaveOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let format = opts.manifest_format;
+    download_bytes(&format!("manifest.{}", format.extension()), manifest_text(manifest, format)?.as_bytes(), "text/plain")?;
+    download_bytes("dictionary.json", serde_json::to_string_pretty(&detection_dictionary(&manifest.tags))?.as_bytes(), "application/json")?;
+    download_bytes("summary.txt", summary_text(manifest, tags, opts.lightness_weight)?.as_bytes(), "text/plain")
+}
+
+/// Browser counterpart of `save_all`: every tag image and the manifest are offered as
+/// downloads under the names `save_all` gives them, with category folders folded into the
+/// file name (`Bin A_tag_01.png`). Wedge layers are not offered.
+#[cfg(target_arch = "wasm32")]
+pub fn download_all(
+    tags: &[Vec<Rgb<u8>>],
+    threshold: f32,
+    images: &[DynamicImage],
+    opts: &SaveOptions,
+    progress: &(dyn Fn() -> bool + Sync),
+) -> Result<String, Box<dyn std::error::Error>> {
+    let manifest = separate_manifest(tags, threshold, opts);
+    for (entry, img) in manifest.tags.iter().zip(images) {
+        let bytes = if opts.embed_metadata && opts.format == ImageFormat::Png {
+            let meta = EmbeddedTagMetadata { threshold, sides: entry.sides, seed: opts.seed, color_space: opts.color_space, tag: entry };
+            let mut bytes = Vec::new();
+            encode_png_with_text(img, &mut bytes, PNG_METADATA_KEYWORD, &serde_json::to_string(&meta)?)?;
+            bytes
+        } else {
+            encode_image(img, opts.format)?
+        };
+        download_bytes(&entry.filename.replace('/', "_"), &bytes, opts.format.mime_type())?;
+        if !progress() {
+            return Err(SAVE_CANCELLED.into());
+        }
+    }
+    download_manifest(&manifest, tags, opts)?;
+    Ok(BROWSER_DOWNLOADS.to_string())
+}
+
+/// Browser counterpart of `save_all_together`: the combined sheet and its manifest are
+/// offered as downloads
+#[cfg(target_arch = "wasm32")]
+pub fn download_all_together(
+    tags: &[Vec<Rgb<u8>>],
+    threshold: f32,
+    images: &[DynamicImage],
+    columns: Option<usize>,
+    opts: &SaveOptions,
+    progress: &(dyn Fn() -> bool + Sync),
+) -> Result<String, Box<dyn std::error::Error>> {
+    if images.is_empty() {
+        return Err("no tags to save".into());
+    }
+    let sheet = build_combined_image(images, columns, opts, progress)?;
+    let manifest = combined_manifest(tags, threshold, &sheet, opts);
+    let bytes = encode_image(&combined_output(sheet.image, opts), opts.format)?;
+    download_bytes(&format!("all_tags_combined.{}", opts.format.extension()), &bytes, opts.format.mime_type())?;
+    download_manifest(&manifest, tags, opts)?;
+    Ok(BROWSER_DOWNLOADS.to_string())
+}
+
+/// Put `img` on the system clipboard; the platform backend hands it to other programs
+/// (as PNG where they ask for that). On Linux the image stays available only while
+/// `clipboard` lives.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn copy_image_to_clipboard(clipboard: &mut arboard::Clipboard, img: &RgbImage) -> Result<(), Box<dyn std::error::Error>> {
+    let rgba = DynamicImage::ImageRgb8(img.clone()).into_rgba8();
+    clipboard.set_image(arboard::ImageData {
+        width: rgba.width() as usize,
+        height: rgba.height() as usize,
+        bytes: std::borrow::Cow::Owned(rgba.into_raw()),
+    })?;
     Ok(())
 }
+
+/// Largest ΔE between a sampled wedge and its manifest color that still counts as a match.
+/// JPEG output gets a looser bound for compression artifacts.
+pub const VERIFY_MAX_DELTA_E: f32 = 3.0;
+pub const VERIFY_MAX_DELTA_E_JPEG: f32 = 10.0;
+
+/// A wedge whose pixels do not match the color recorded for it
+#[derive(Debug)]
+pub struct WedgeMismatch {
+    /// 1-based tag and wedge numbers (wedges clockwise from the top)
+    pub tag: usize,
+    pub wedge: usize,
+    pub expected: Rgb<u8>,
+    pub found: Rgb<u8>,
+    pub delta_e: f32,
+}
+
+/// Outcome of `verify_output`
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    pub tags_checked: usize,
+    pub wedges_checked: usize,
+    /// Tags that could not be checked, with the reason
+    pub skipped: Vec<(usize, String)>,
+    pub mismatches: Vec<WedgeMismatch>,
+}
+
+/// Per-channel rounded mean of a set of colors
+fn mean_rgb(colors: &[Rgb<u8>]) -> Rgb<u8> {
+    let n = colors.len().max(1) as u32;
+    Rgb([0, 1, 2].map(|k| ((colors.iter().map(|c| c[k] as u32).sum::<u32>() + n / 2) / n) as u8))
+}
+
+/// Reload the images of a finished save and check that every wedge shows the color its
+/// manifest entry claims. Each wedge is sampled at five points along its bisector, between
+/// the center/gradient dots and the rim, and passes when most samples are within
+/// `VERIFY_MAX_DELTA_E` of the expected color, so an overlapping serial number does not fail it.
+pub fn verify_output(out_dir: &str) -> Result<VerifyReport, Box<dyn std::error::Error>> {
+    let Some((manifest, _)) = read_manifest(out_dir)? else {
+        return Err("verification needs a manifest.json, manifest.yaml or manifest.toml".into());
+    };
+    let margin_pct = manifest.margin_pct;
+    let dot_pct = [manifest.center_dot_size_pct, manifest.gradient_dot_size_pct].into_iter().flatten().fold(0.0f32, f32::max);
+    let wedge_gradient = manifest.wedge_gradient;
+    let dither_levels = manifest.dither_levels;
+    let sectors = matches!(manifest.shape, MarkerShape::Sectors { .. });
+    let quiet = manifest.quiet_zone_px.unwrap_or(0);
+    let border_offset = manifest
+        .fiducial_border
+        .map(|b| (b.marker_offset.0 + quiet, b.marker_offset.1 + quiet))
+        .unwrap_or((quiet, quiet));
+
+    let mut report = VerifyReport::default();
+    let mut combined: Option<image::RgbImage> = None;
+    for (idx, entry) in manifest.tags.iter().enumerate() {
+        let tag = idx + 1;
+        let filename = entry.filename.as_str();
+        let ext = Path::new(filename).extension().and_then(|e| e.to_str()).unwrap_or("png").to_string();
+        let img = match entry.combined_rect {
+            Some((x, y, w, h)) => {
+                if combined.is_none() {
+                    combined = Some(image::open(format!("{}/all_tags_combined.{}", out_dir, ext))?.to_rgb8());
+                }
+                let sheet = combined.as_ref().expect("combined sheet loaded above");
+                image::imageops::crop_imm(sheet, x, y, w, h).to_image()
+            }
+            None => match image::open(format!("{}/{}", out_dir, filename)) {
+                Ok(img) => img.to_rgb8(),
+                Err(e) => {
+                    report.skipped.push((tag, format!("cannot open {}: {}", filename, e)));
+                    continue;
+                }
+            },
+        };
+        let colors: Vec<Rgb<u8>> = entry.colors_rgb.iter().map(|&(r, g, b)| Rgb([r, g, b])).collect();
+        if colors.is_empty() {
+            report.skipped.push((tag, "no colors recorded".into()));
+            continue;
+        }
+        let max_delta = if ext.eq_ignore_ascii_case("jpg") || ext.eq_ignore_ascii_case("jpeg") { VERIFY_MAX_DELTA_E_JPEG } else { VERIFY_MAX_DELTA_E };
+
+        // Same geometry as `draw_marker_polygon`, inside the quiet zone and fiducial border if any
+        let (ox, oy) = border_offset;
+        let (Some(mw), Some(mh)) = (img.width().checked_sub(2 * ox), img.height().checked_sub(2 * oy)) else {
+            report.skipped.push((tag, format!("{}×{} is smaller than its recorded border", img.width(), img.height())));
+            continue;
+        };
+        // Dithered wedges are compared over 4×4 blocks, which need that much image
+        let dither_block_max = match (img.width().checked_sub(4), img.height().checked_sub(4)) {
+            (Some(w), Some(h)) => Some((w, h)),
+            _ if dither_levels.is_some() => {
+                report.skipped.push((tag, format!("{}×{} is too small to compare dithered wedges", img.width(), img.height())));
+                continue;
+            }
+            _ => None,
+        };
+        let (mw, mh) = (mw as f32, mh as f32);
+        let margin = margin_pct.clamp(0.0, 0.49) * mw.min(mh);
+        let radius = ((mw - 2.0 * margin) * 0.5).min((mh - 2.0 * margin) * 0.5).max(1.0);
+        let (cx, cy) = (ox as f32 + mw * 0.5, oy as f32 + mh * 0.5);
+        let n = colors.len();
+        let step = std::f32::consts::TAU / n as f32;
+        let apothem = if sectors { radius } else { radius * (step * 0.5).cos() };
+        let dot_r = mw.min(mh) * (dot_pct / 100.0) * 0.5;
+        let (lo, hi) = ((dot_r + 2.0).max(apothem * 0.5), apothem * 0.9 - 1.0);
+        if lo >= hi {
+            report.skipped.push((tag, "center dots cover the wedges".into()));
+            continue;
+        }
+        let start = -std::f32::consts::FRAC_PI_2 + entry.rotation_deg.unwrap_or(0.0).to_radians();
+
+        report.tags_checked += 1;
+        for (k, &base) in colors.iter().enumerate() {
+            let mid = start + step * (k as f32 + 0.5);
+            let mut samples: Vec<(f32, Rgb<u8>)> = (0..5)
+                .map(|s| {
+                    let r = lo + (hi - lo) * s as f32 / 4.0;
+                    let (x, y) = ((cx + r * mid.cos()) as u32, (cy + r * mid.sin()) as u32);
+                    let mut found = *img.get_pixel(x.min(img.width() - 1), y.min(img.height() - 1));
+                    let mut expected = if wedge_gradient { wedge_gradient_color(base, r / apothem) } else { base };
+                    if let (Some(levels), Some((max_x, max_y))) = (dither_levels, dither_block_max) {
+                        // Compare means over one full 4×4 dither period, which any aligned block covers
+                        let (bx, by) = (x.saturating_sub(2).min(max_x), y.saturating_sub(2).min(max_y));
+                        let block: Vec<Rgb<u8>> = (0..16).map(|p| *img.get_pixel(bx + p % 4, by + p / 4)).collect();
+                        let pattern: Vec<Rgb<u8>> = (0..16).map(|p| dither_color(expected, levels, p % 4, p / 4)).collect();
+                        found = mean_rgb(&block);
+                        expected = mean_rgb(&pattern);
+                    }
+                    (delta_e(srgb_u8_to_lab(expected), srgb_u8_to_lab(found)), found)
+                })
+                .collect();
+            samples.sort_by(|a, b| a.0.total_cmp(&b.0));
+            report.wedges_checked += 1;
+            let (median, found) = samples[2];
+            if median > max_delta {
+                report.mismatches.push(WedgeMismatch { tag, wedge: k + 1, expected: base, found, delta_e: median });
+            }
+        }
+    }
+    Ok(report)
+}
@@ -0,0 +1,6 @@
+//! Poly Cue's color selection, marker rendering and file output, usable without the
+//! GUI: the `polycue` binary adds the window and command line on top of these modules.
+
+pub mod color;
+pub mod render;
+pub mod io;
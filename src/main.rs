@@ -1,14 +1,36 @@
-mod color;
-mod render;
-mod io;
 mod gui;
+mod cli;
 
-use eframe::{egui, NativeOptions};
+use clap::Parser;
 use gui::AppState;
+use cli::Cli;
 
+#[cfg(not(target_arch = "wasm32"))]
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let native_options = NativeOptions {
-        viewport: egui::ViewportBuilder::default()
+    // Invalid arguments exit here with a usage error, before any window opens
+    let cli = Cli::parse();
+
+    if cli.headless {
+        let mut app = AppState::from_cli(&cli);
+        app.generate_tags();
+        if app.threshold < app.min_usable_threshold {
+            eprintln!(
+                "warning: ΔE {:.1} is below {:.1}; colors may be hard to distinguish. Reduce --count or widen the pool.",
+                app.threshold, app.min_usable_threshold
+            );
+        }
+        app.save_current_tags();
+        if let Some((message, is_error, _)) = &app.status {
+            if *is_error {
+                return Err(message.clone().into());
+            }
+            println!("{}", message);
+        }
+        return Ok(());
+    }
+
+    let native_options = eframe::NativeOptions {
+        viewport: eframe::egui::ViewportBuilder::default()
             .with_inner_size([1600.0, 1200.0])
             .with_min_inner_size([800.0, 600.0])
             .with_position([100.0, 100.0])
@@ -18,11 +40,38 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     eframe::run_native(
         "Poly Cue",
         native_options,
-        Box::new(|cc| {
-            let mut app = AppState::new();
+        Box::new(move |cc| {
+            let mut app = AppState::from_cli(&cli);
             app.regenerate(&cc.egui_ctx);
             Box::new(app)
         }),
     )?;
     Ok(())
 }
+
+/// Id of the canvas in `index.html` the web build draws into
+#[cfg(target_arch = "wasm32")]
+const CANVAS_ID: &str = "polycue_canvas";
+
+/// Browser entry point, started by the page's wasm-bindgen glue. The window size and
+/// position options do not apply: the app fills the canvas.
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    wasm_bindgen_futures::spawn_local(async {
+        let result = eframe::WebRunner::new()
+            .start(
+                CANVAS_ID,
+                eframe::WebOptions::default(),
+                Box::new(|cc| {
+                    // There is no command line in the browser, so every setting starts at its default
+                    let mut app = AppState::from_cli(&Cli::parse_from(["polycue"]));
+                    app.regenerate(&cc.egui_ctx);
+                    Box::new(app)
+                }),
+            )
+            .await;
+        if let Err(e) = result {
+            web_sys::console::error_1(&e);
+        }
+    });
+}
@@ -1,8 +1,11 @@
-use image::{ImageBuffer, Rgb};
-use crate::color::{pairwise_delta_matrix, group_min};
+use image::imageops::{self, FilterType};
+use image::{ImageBuffer, Luma, Pixel, Rgb, Rgb32FImage, RgbImage, Rgba, RgbaImage};
+use crate::color::{pairwise_delta_matrix, group_min, signature_color, srgb_u8_to_lab};
 use palette::Lab;
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use ab_glyph::{Font, FontRef, PxScale, ScaleFont};
+use serde::{Deserialize, Serialize};
+use std::hash::{Hash, Hasher};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Point {
@@ -10,23 +13,331 @@ pub struct Point {
     pub y: i32,
 }
 
-/// Group colors into optimal arrangements using Monte Carlo optimization
+/// Appearance options shared by every tag in a batch
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarkerStyle {
+    pub center_dot: bool,
+    pub center_dot_size_pct: f32,
+    pub center_dot_color: Rgb<u8>,
+    /// Draw the center dot as a ring, leaving the wedges visible inside it
+    pub center_ring: bool,
+    /// Ring inner radius as a percentage of the center dot radius
+    pub center_ring_inner_pct: f32,
+    pub gradient_dot: bool,
+    pub gradient_dot_size_pct: f32,
+    /// Color the gradient dot fades toward at its center
+    pub gradient_dot_color: Rgb<u8>,
+    /// Gaussian sigma of the gradient dot as a fraction of its radius: small values give a
+    /// tight core, large ones a broad haze
+    pub gradient_dot_sigma_pct: f32,
+    pub bg: Rgb<u8>,
+    /// Gap between the polygon and the canvas edge, as a fraction of the shorter side (0 = touching)
+    pub margin_pct: f32,
+    /// Expand the canvas with a black frame and corner anchors (see `fiducial_geometry`)
+    pub fiducial_border: bool,
+    /// White band added around the finished marker, outside any fiducial border, as a
+    /// fraction of the marker's shorter side (0 = none). Always white, whatever `bg`.
+    pub quiet_zone_pct: f32,
+    /// Width of the background-colored gap left along each spoke (0 = wedges touch)
+    pub wedge_gap_px: u32,
+    /// Width of a ring just outside the polygon's circumscribed circle (0 = none)
+    pub bounding_ring_px: u32,
+    pub bounding_ring_color: Rgb<u8>,
+    /// Width of a solid band just outside the polygon edges, drawn under the wedges, that
+    /// marks the tag's group for human sorting (0 = none)
+    pub signature_band_px: u32,
+    /// Band color; None uses each tag's signature color (see `signature_color`)
+    pub signature_band_color: Option<Rgb<u8>>,
+    /// Clockwise rotation of the polygon from its point-up start, in degrees
+    pub rotation_deg: f32,
+    /// Fade each wedge from a lighter variant at the centroid to its base color at the rim
+    pub wedge_gradient: bool,
+    /// Ordered-dither wedge fills to this many levels per channel (see `dither_color`)
+    pub dither_levels: Option<u8>,
+    /// Fewest pixels each wedge keeps on canvases up to `MIN_WEDGE_CANVAS_PX`: a wedge that
+    /// rasterizes to fewer takes the pixels nearest its bisector from neighbors that can spare
+    /// them (0 = off)
+    pub min_wedge_px: u32,
+    pub shape: MarkerShape,
+    pub center_shape: CenterShape,
+}
+
+/// Outline of the marker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum MarkerShape {
+    /// Regular polygon split into one triangle per side
+    #[default]
+    Polygon,
+    /// Circle split into `count` equal pie sectors, independent of the polygon side limit
+    Sectors { count: usize },
+}
+
+/// Outline of the center dot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum CenterShape {
+    #[default]
+    Circle,
+    /// Regular polygon sharing the marker's rotation, with the dot size as its circumscribed diameter
+    Polygon { sides: usize },
+}
+
+impl MarkerShape {
+    /// Number of wedges (and colors per tag) for a marker with the given polygon side count
+    pub fn wedges(self, sides: usize) -> usize {
+        match self {
+            MarkerShape::Polygon => sides,
+            MarkerShape::Sectors { count } => count,
+        }
+    }
+}
+
+impl MarkerStyle {
+    /// Copy with pixel sizes scaled from a `reference_width` canvas to `width`,
+    /// so small previews keep the proportions of the saved image
+    pub fn scaled_for(self, width: u32, reference_width: u32) -> MarkerStyle {
+        let f = width as f32 / reference_width.max(1) as f32;
+        let scale = |px: u32| if px == 0 { 0 } else { ((px as f32 * f).round() as u32).max(1) };
+        MarkerStyle {
+            wedge_gap_px: scale(self.wedge_gap_px),
+            bounding_ring_px: scale(self.bounding_ring_px),
+            signature_band_px: scale(self.signature_band_px),
+            ..self
+        }
+    }
+
+    /// Width in pixels of the quiet zone around a `width` × `height` marker
+    pub fn quiet_zone_px(&self, width: u32, height: u32) -> u32 {
+        (width.min(height) as f32 * self.quiet_zone_pct.max(0.0)).round() as u32
+    }
+
+    /// Color of the signature band around a tag with `colors`, if the band is on
+    pub fn signature_band_rgb(&self, colors: &[Rgb<u8>]) -> Option<Rgb<u8>> {
+        (self.signature_band_px > 0).then(|| self.signature_band_color.unwrap_or_else(|| signature_color(colors)))
+    }
+
+    /// Copy rotated clockwise by a further `deg` degrees
+    pub fn rotated(self, deg: f32) -> MarkerStyle {
+        MarkerStyle { rotation_deg: self.rotation_deg + deg, ..self }
+    }
+
+    /// Pixel size of the image `draw_marker_polygon` makes for a `width` × `height` marker,
+    /// including any fiducial border and quiet zone
+    pub fn output_size(&self, width: u32, height: u32) -> (u32, u32) {
+        let (w, h) = if self.fiducial_border { fiducial_geometry(width, height).canvas } else { (width, height) };
+        let quiet = self.quiet_zone_px(width, height);
+        (w + 2 * quiet, h + 2 * quiet)
+    }
+}
+
+/// Plain wedges on white: no dots, border, gaps, ring or band, and no wedge dropout
+impl Default for MarkerStyle {
+    fn default() -> Self {
+        MarkerStyle {
+            center_dot: false,
+            center_dot_size_pct: 35.0,
+            center_dot_color: Rgb([0, 0, 0]),
+            center_ring: false,
+            center_ring_inner_pct: 50.0,
+            gradient_dot: false,
+            gradient_dot_size_pct: 35.0,
+            gradient_dot_color: Rgb([255, 255, 255]),
+            gradient_dot_sigma_pct: 0.7,
+            bg: Rgb([255, 255, 255]),
+            margin_pct: 0.08,
+            fiducial_border: false,
+            quiet_zone_pct: 0.0,
+            wedge_gap_px: 0,
+            bounding_ring_px: 0,
+            bounding_ring_color: Rgb([0, 0, 0]),
+            signature_band_px: 0,
+            signature_band_color: None,
+            rotation_deg: 0.0,
+            wedge_gradient: false,
+            dither_levels: None,
+            min_wedge_px: 1,
+            shape: MarkerShape::Polygon,
+            center_shape: CenterShape::Circle,
+        }
+    }
+}
+
+// Hashes the exact bit patterns of the float fields, so equal styles always hash alike
+impl Hash for MarkerStyle {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let MarkerStyle {
+            center_dot, center_dot_size_pct, center_dot_color, center_ring, center_ring_inner_pct,
+            gradient_dot, gradient_dot_size_pct, gradient_dot_color, gradient_dot_sigma_pct, bg, margin_pct, fiducial_border,
+            quiet_zone_pct, wedge_gap_px, bounding_ring_px, bounding_ring_color, signature_band_px,
+            signature_band_color, rotation_deg, wedge_gradient, dither_levels, min_wedge_px, shape, center_shape,
+        } = *self;
+        (center_dot, center_ring, gradient_dot, fiducial_border, wedge_gradient).hash(state);
+        for v in [center_dot_size_pct, center_ring_inner_pct, gradient_dot_size_pct, gradient_dot_sigma_pct, margin_pct, quiet_zone_pct, rotation_deg] {
+            v.to_bits().hash(state);
+        }
+        (center_dot_color.0, gradient_dot_color.0, bg.0, wedge_gap_px, dither_levels, min_wedge_px, shape, center_shape).hash(state);
+        (bounding_ring_px, bounding_ring_color.0, signature_band_px, signature_band_color.map(|c| c.0)).hash(state);
+    }
+}
+
+/// Pixel layout of the fiducial border added around a marker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FiducialGeometry {
+    /// Size of the expanded canvas
+    pub canvas: (u32, u32),
+    /// Top-left corner of the original marker within the canvas
+    pub marker_offset: (u32, u32),
+    /// Thickness of the solid black frame along the canvas edge
+    pub frame_px: u32,
+    /// Side length of each square corner anchor
+    pub anchor_px: u32,
+    /// Top-left corners of the anchors, clockwise from top-left
+    pub anchors: [(u32, u32); 4],
+}
+
+/// Smallest gradient-dot sigma, as a fraction of the dot radius, that is honored
+pub const GRADIENT_DOT_SIGMA_MIN: f32 = 0.05;
+
+/// Canvases narrower than this are too small for polygon edges, dots and serial numbers:
+/// wedges are assigned to pixels by angle alone and the overlays are left out, so every
+/// pixel still shows a wedge color
+pub const TINY_CANVAS_PX: u32 = 4;
+
+/// Largest canvas side `MarkerStyle::min_wedge_px` is enforced on. Beyond this a wedge only
+/// comes out smaller than the minimum can ask for under an extreme margin or gap.
+pub const MIN_WEDGE_CANVAS_PX: u32 = 64;
+
+/// Bounding ring of a marker, in pixels from its center (inside any border or quiet zone)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BoundingRing {
+    pub inner_radius_px: f32,
+    pub outer_radius_px: f32,
+    pub rgb: (u8, u8, u8),
+}
+
+/// Ring for a `width` × `height` marker drawn with `style`, if it has one. It starts two
+/// pixels outside the polygon's vertices (clear of their rounding to whole pixels) and is
+/// cut back to the canvas edge, so with no margin it may be clipped or missing.
+pub fn bounding_ring(width: u32, height: u32, style: &MarkerStyle) -> Option<BoundingRing> {
+    if style.bounding_ring_px == 0 {
+        return None;
+    }
+    let (w, h) = (width as f32, height as f32);
+    let margin = style.margin_pct.clamp(0.0, 0.49) * w.min(h);
+    let radius = ((w - 2.0 * margin) * 0.5).min((h - 2.0 * margin) * 0.5).max(1.0);
+    let inner = radius + 2.0;
+    let outer = (inner + style.bounding_ring_px as f32).min(w.min(h) * 0.5);
+    let c = style.bounding_ring_color;
+    (outer > inner).then_some(BoundingRing { inner_radius_px: inner, outer_radius_px: outer, rgb: (c[0], c[1], c[2]) })
+}
+
+/// Compute the fiducial border layout for a marker of the given size
+pub fn fiducial_geometry(width: u32, height: u32) -> FiducialGeometry {
+    let s = width.min(height) as f32;
+    let frame = ((s * 0.03).round() as u32).max(1);
+    let anchor = ((s * 0.08).round() as u32).max(1);
+    let gap = frame;
+    let pad = frame + gap + anchor + gap;
+    let (cw, ch) = (width + 2 * pad, height + 2 * pad);
+    let near = frame + gap;
+    let (far_x, far_y) = (cw - near - anchor, ch - near - anchor);
+    FiducialGeometry {
+        canvas: (cw, ch),
+        marker_offset: (pad, pad),
+        frame_px: frame,
+        anchor_px: anchor,
+        anchors: [(near, near), (far_x, near), (far_x, far_y), (near, far_y)],
+    }
+}
+
+/// Fill an axis-aligned rectangle, clipped to the image bounds
+fn fill_rect(img: &mut ImageBuffer<Rgb<u8>, Vec<u8>>, x0: u32, y0: u32, w: u32, h: u32, color: Rgb<u8>) {
+    let x1 = (x0 + w).min(img.width());
+    let y1 = (y0 + h).min(img.height());
+    for y in y0..y1 {
+        for x in x0..x1 {
+            img.put_pixel(x, y, color);
+        }
+    }
+}
+
+/// Draw crop marks outside the corners of `trim` (x, y, width, height): at each corner a
+/// horizontal and a vertical line of `length` pixels, continuing the trim edges outward and
+/// starting `gap` pixels away from the corner so they never reach into the trimmed area
+pub fn draw_trim_marks(img: &mut ImageBuffer<Rgb<u8>, Vec<u8>>, trim: (u32, u32, u32, u32), length: u32, gap: u32, thickness: u32, color: Rgb<u8>) {
+    let (x, y, w, h) = trim;
+    let t = thickness.max(1);
+    let reach = gap + length;
+    // Each line sits just outside the trim edge it continues, so it is cut away with the bleed
+    for (cx, cy) in [(x, y), (x + w, y), (x, y + h), (x + w, y + h)] {
+        let (left, top) = (cx == x, cy == y);
+        let hx = if left { cx.saturating_sub(reach) } else { cx + gap };
+        let vy = if top { cy.saturating_sub(reach) } else { cy + gap };
+        fill_rect(img, hx, if top { cy.saturating_sub(t) } else { cy }, length, t, color);
+        fill_rect(img, if left { cx.saturating_sub(t) } else { cx }, vy, t, length, color);
+    }
+}
+
+/// Fill a solid disc of `radius` pixels centered on `center`
+pub fn fill_disc(img: &mut ImageBuffer<Rgb<u8>, Vec<u8>>, center: (f32, f32), radius: f32, color: Rgb<u8>) {
+    draw_sectors(img, center, radius, 0.0, 1, 0, |_, _, _| Some(color));
+}
+
+/// Place a marker on a larger canvas with a black frame and square anchors in each corner
+pub fn add_fiducial_border(marker: &ImageBuffer<Rgb<u8>, Vec<u8>>, bg: Rgb<u8>) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+    let g = fiducial_geometry(marker.width(), marker.height());
+    let (cw, ch) = g.canvas;
+    let black = Rgb([0, 0, 0]);
+    let mut out = ImageBuffer::from_pixel(cw, ch, bg);
+    image::imageops::replace(&mut out, marker, g.marker_offset.0 as i64, g.marker_offset.1 as i64);
+
+    let f = g.frame_px;
+    fill_rect(&mut out, 0, 0, cw, f, black);
+    fill_rect(&mut out, 0, ch - f, cw, f, black);
+    fill_rect(&mut out, 0, 0, f, ch, black);
+    fill_rect(&mut out, cw - f, 0, f, ch, black);
+    for &(x, y) in &g.anchors {
+        fill_rect(&mut out, x, y, g.anchor_px, g.anchor_px, black);
+    }
+    out
+}
+
+/// Place an image in the middle of a canvas `px` pixels larger on every side, filled with `fill`
+fn pad_image<P: Pixel>(img: &ImageBuffer<P, Vec<P::Subpixel>>, px: u32, fill: P) -> ImageBuffer<P, Vec<P::Subpixel>> {
+    let mut out = ImageBuffer::from_pixel(img.width() + 2 * px, img.height() + 2 * px, fill);
+    image::imageops::replace(&mut out, img, px as i64, px as i64);
+    out
+}
+
+/// Score lost per unit of Lab L a group falls short of the minimum lightness spread.
+/// Large enough that closing the shortfall outweighs any ΔE gain.
+pub const LIGHTNESS_SPREAD_PENALTY: f32 = 100.0;
+
+/// Group colors into optimal arrangements using Monte Carlo optimization.
+/// Group `k` gets `group_sizes[k]` colors, so tags may differ in side count; swaps trade one
+/// color for one, keeping every size. Groups whose Lab lightness range is below
+/// `min_l_spread` are penalized in the score.
 pub fn group_colors_into_groups_monte_carlo(
     colors: Vec<Rgb<u8>>,
     labs: Vec<Lab>,
-    tag_count: usize,
-    group_size: usize,
+    group_sizes: &[usize],
     iters: usize,
+    min_l_spread: f32,
+    rng: &mut impl Rng,
 ) -> Vec<Vec<Rgb<u8>>> {
     let n = colors.len();
-    assert_eq!(n, tag_count * group_size);
+    let tag_count = group_sizes.len();
+    assert_eq!(n, group_sizes.iter().sum::<usize>());
     let dm = pairwise_delta_matrix(&labs);
+    // CIE L regardless of the ΔE space, so the spread matches what the manifest reports
+    let lightness: Vec<f32> = colors.iter().map(|&c| srgb_u8_to_lab(c).l).collect();
 
     // Greedy initialization: for each group, pick the farthest pair, then add items maximizing min distance to group
     let mut remaining: Vec<usize> = (0..n).collect();
     let mut groups: Vec<Vec<usize>> = Vec::with_capacity(tag_count);
 
-    while !remaining.is_empty() {
+    for &group_size in group_sizes {
         // Seed with farthest pair
         let mut best_pair = (remaining[0], remaining[1], -1.0f32);
         for i in 0..remaining.len() {
@@ -68,16 +379,19 @@ pub fn group_colors_into_groups_monte_carlo(
     }
 
     // Monte Carlo refinement: swap one color between two groups if it improves total score
-    let mut rng = thread_rng();
-    let score_group = |g: &Vec<usize>| -> f32 { group_min(&dm, n, g) };
+    let score_group = |g: &Vec<usize>| -> f32 {
+        let (lo, hi) = g.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &i| (lo.min(lightness[i]), hi.max(lightness[i])));
+        let shortfall = (min_l_spread - (hi - lo)).max(0.0);
+        group_min(&dm, n, g) - LIGHTNESS_SPREAD_PENALTY * shortfall
+    };
 
     for _ in 0..iters {
         if tag_count < 2 { break; }
         let i = rng.gen_range(0..tag_count);
         let mut j = rng.gen_range(0..tag_count);
         if i == j { j = (j + 1) % tag_count; }
-        let ia = rng.gen_range(0..group_size);
-        let jb = rng.gen_range(0..group_size);
+        let ia = rng.gen_range(0..groups[i].len());
+        let jb = rng.gen_range(0..groups[j].len());
 
         let old_i = groups[i].clone();
         let old_j = groups[j].clone();
@@ -104,8 +418,41 @@ pub fn group_colors_into_groups_monte_carlo(
         .collect()
 }
 
-/// Draw a filled triangle using scanline rasterization
-pub fn draw_filled_triangle(img: &mut ImageBuffer<Rgb<u8>, Vec<u8>>, a: Point, b: Point, c: Point, color: Rgb<u8>) {
+/// How far toward white a wedge gradient starts at the centroid
+pub const WEDGE_GRADIENT_LIGHTEN: f32 = 0.5;
+
+/// Wedge color at normalized distance `t` from the centroid (0) to the rim (1)
+pub fn wedge_gradient_color(base: Rgb<u8>, t: f32) -> Rgb<u8> {
+    let k = WEDGE_GRADIENT_LIGHTEN * (1.0 - t.clamp(0.0, 1.0));
+    let mix = |c: u8| (c as f32 + (255.0 - c as f32) * k).round() as u8;
+    Rgb([mix(base[0]), mix(base[1]), mix(base[2])])
+}
+
+/// 4×4 Bayer threshold matrix, indexed `[y % 4][x % 4]`
+pub const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Approximate `c` with `levels` evenly spaced values per channel (0 and 255 included),
+/// choosing between the two nearest by the Bayer threshold at pixel (x, y)
+pub fn dither_color(c: Rgb<u8>, levels: u8, x: u32, y: u32) -> Rgb<u8> {
+    let step = 255.0 / (levels.max(2) - 1) as f32;
+    let threshold = (BAYER_4X4[(y % 4) as usize][(x % 4) as usize] as f32 + 0.5) / 16.0;
+    c.map(|v| {
+        let scaled = v as f32 / step;
+        let lo = scaled.floor();
+        let q = if scaled - lo > threshold { lo + 1.0 } else { lo };
+        (q * step).round().min(255.0) as u8
+    })
+}
+
+/// Draw a filled triangle using scanline rasterization. `shade` gives the color of each
+/// pixel; pass `|_, _| color` for a flat fill.
+pub fn draw_filled_triangle<P: Pixel>(
+    img: &mut ImageBuffer<P, Vec<P::Subpixel>>,
+    a: Point,
+    b: Point,
+    c: Point,
+    mut shade: impl FnMut(u32, u32) -> P,
+) {
     let width = img.width();
     let height = img.height();
     
@@ -130,7 +477,7 @@ pub fn draw_filled_triangle(img: &mut ImageBuffer<Rgb<u8>, Vec<u8>>, a: Point, b
         xa = xa.max(0);
         xb = xb.min(width as i32 - 1);
         for x in xa..=xb {
-            img.put_pixel(x as u32, y as u32, color);
+            img.put_pixel(x as u32, y as u32, shade(x as u32, y as u32));
         }
     };
 
@@ -153,6 +500,72 @@ pub fn draw_filled_triangle(img: &mut ImageBuffer<Rgb<u8>, Vec<u8>>, a: Point, b
 
 static FONT_DATA: &[u8] = include_bytes!("../assets/font.ttf");
 
+/// Advance width of `text` in pixels at the given font height
+pub fn text_width(text: &str, font_px: f32) -> f32 {
+    let font = FontRef::try_from_slice(FONT_DATA).expect("Invalid font.ttf");
+    let sf = font.as_scaled(PxScale::from(font_px));
+    let mut w = 0.0f32;
+    let mut prev_id = None;
+    for ch in text.chars() {
+        let gid = font.glyph_id(ch);
+        if let Some(p) = prev_id { w += sf.kern(p, gid); }
+        w += sf.h_advance(gid);
+        prev_id = Some(gid);
+    }
+    w
+}
+
+/// Vertical extent (top, bottom) of the inked pixels of `text`, relative to the `y` given to `draw_text`.
+/// Glyph outlines in the bundled font do not fill the nominal height exactly, so use this to center text.
+pub fn text_ink_extent(text: &str, font_px: f32) -> (f32, f32) {
+    let font = FontRef::try_from_slice(FONT_DATA).expect("Invalid font.ttf");
+    let scale = PxScale::from(font_px);
+    let ascent = font.as_scaled(scale).ascent();
+    let (mut top, mut bottom) = (f32::INFINITY, f32::NEG_INFINITY);
+    for ch in text.chars() {
+        let g = font.glyph_id(ch).with_scale_and_position(scale, ab_glyph::point(0.0, ascent));
+        if let Some(og) = font.outline_glyph(g) {
+            let b = og.px_bounds();
+            top = top.min(b.min.y);
+            bottom = bottom.max(b.max.y);
+        }
+    }
+    if top > bottom { (0.0, font_px) } else { (top, bottom) }
+}
+
+/// Draw a line of anti-aliased text with its top-left corner at (x, y)
+pub fn draw_text(img: &mut ImageBuffer<Rgb<u8>, Vec<u8>>, text: &str, x: i32, y: i32, font_px: f32, color: Rgb<u8>) {
+    let font = FontRef::try_from_slice(FONT_DATA).expect("Invalid font.ttf");
+    let scale = PxScale::from(font_px);
+    let sf = font.as_scaled(scale);
+    let baseline_y = y as f32 + sf.ascent();
+    let (img_w, img_h) = (img.width() as i32, img.height() as i32);
+    let (cr, cg, cb) = (color[0] as f32, color[1] as f32, color[2] as f32);
+
+    let mut cursor_x = x as f32;
+    let mut prev_id = None;
+    for ch in text.chars() {
+        let gid = font.glyph_id(ch);
+        if let Some(p) = prev_id { cursor_x += sf.kern(p, gid); }
+        let g = gid.with_scale_and_position(scale, ab_glyph::point(cursor_x, baseline_y));
+        if let Some(og) = font.outline_glyph(g) {
+            let b = og.px_bounds();
+            og.draw(|rx, ry, cov| {
+                let px = b.min.x as i32 + rx as i32;
+                let py = b.min.y as i32 + ry as i32;
+                if cov > 0.05 && px >= 0 && px < img_w && py >= 0 && py < img_h {
+                    let p = img.get_pixel_mut(px as u32, py as u32);
+                    p[0] = (p[0] as f32 * (1.0 - cov) + cr * cov) as u8;
+                    p[1] = (p[1] as f32 * (1.0 - cov) + cg * cov) as u8;
+                    p[2] = (p[2] as f32 * (1.0 - cov) + cb * cov) as u8;
+                }
+            });
+        }
+        cursor_x += sf.h_advance(gid);
+        prev_id = Some(gid);
+    }
+}
+
 /// Render a serial number onto an image using a TTF font.
 /// h_align / v_align are 0.0 (top-left) → 1.0 (bottom-right).
 fn draw_serial_number(
@@ -247,75 +660,426 @@ fn draw_serial_number(
     }
 }
 
-/// Draw a polygonal marker with optional center and gradient dots
-#[allow(clippy::too_many_arguments)]
-pub fn draw_marker_polygon(
-    width: u32, 
-    height: u32, 
-    sides: usize, 
-    colors: &[Rgb<u8>], 
-    center_dot: bool, 
-    center_dot_size_pct: f32, 
-    gradient_dot: bool, 
-    gradient_dot_size_pct: f32,
-    bg: Rgb<u8>,
-    serial_number: Option<(usize, f32, f32, Rgb<u8>, bool)>, // (1-based index, h_align, v_align, color, border)
-) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
-    let mut img = ImageBuffer::from_pixel(width, height, bg);
+/// Fill a circle with `count` equal sectors, the first starting at `start_angle` and
+/// continuing clockwise. Every pixel inside the circle is assigned to exactly one sector by
+/// its angle, so the sectors tile the disc without gaps unless `gap_px` asks for them.
+/// `color_at` gets the sector index and the pixel position, and may return None to leave
+/// the pixel untouched.
+fn draw_sectors<P: Pixel>(
+    img: &mut ImageBuffer<P, Vec<P::Subpixel>>,
+    (cx, cy): (f32, f32),
+    radius: f32,
+    start_angle: f32,
+    count: usize,
+    gap_px: u32,
+    color_at: impl Fn(usize, u32, u32) -> Option<P>,
+) {
+    let step = std::f32::consts::TAU / count as f32;
+    let half_gap = gap_px as f32 * 0.5;
+    let r2 = radius * radius;
+    let x0 = ((cx - radius).floor() as i32).max(0);
+    let y0 = ((cy - radius).floor() as i32).max(0);
+    let x1 = ((cx + radius).ceil() as i32).min(img.width() as i32 - 1);
+    let y1 = ((cy + radius).ceil() as i32).min(img.height() as i32 - 1);
+    // Distance from a point at radius `r` and angle `phi` past a spoke to that spoke's ray
+    let spoke_dist = |r: f32, phi: f32| if phi < std::f32::consts::FRAC_PI_2 { r * phi.sin() } else { r };
 
-    let w = width as f32;
-    let h_img = height as f32;
+    for y in y0..=y1 {
+        for x in x0..=x1 {
+            let dx = x as f32 + 0.5 - cx;
+            let dy = y as f32 + 0.5 - cy;
+            let d2 = dx * dx + dy * dy;
+            if d2 > r2 {
+                continue;
+            }
+            let theta = (dy.atan2(dx) - start_angle).rem_euclid(std::f32::consts::TAU);
+            let idx = ((theta / step) as usize).min(count - 1);
+            let r = d2.sqrt();
+            if half_gap > 0.0 {
+                let phi = theta - idx as f32 * step;
+                if spoke_dist(r, phi) < half_gap || spoke_dist(r, step - phi) < half_gap {
+                    continue;
+                }
+            }
+            if let Some(c) = color_at(idx, x as u32, y as u32) {
+                img.put_pixel(x as u32, y as u32, c);
+            }
+        }
+    }
+}
+
+/// Corners of a regular polygon around `(cx, cy)`, the first at `start_angle` and the
+/// rest following clockwise
+fn polygon_vertices((cx, cy): (f32, f32), radius: f32, sides: usize, start_angle: f32) -> Vec<(f32, f32)> {
+    let angle_step = std::f32::consts::TAU / sides as f32;
+    (0..sides)
+        .map(|i| {
+            let a = start_angle + angle_step * i as f32;
+            (cx + radius * a.cos(), cy + radius * a.sin())
+        })
+        .collect()
+}
+
+/// Whether `(x, y)` lies inside the convex polygon `verts`, given in clockwise screen order
+fn inside_convex(verts: &[(f32, f32)], x: f32, y: f32) -> bool {
+    verts.iter().zip(verts.iter().cycle().skip(1)).all(|(&(ax, ay), &(bx, by))| {
+        (bx - ax) * (y - ay) - (by - ay) * (x - ax) >= 0.0
+    })
+}
+
+/// Fill a band `band_px` wide just outside the marker's edges (a ring for sectors). It
+/// reaches a pixel under the edges so the wedges cover the seam, leaving wedge gaps clear.
+fn fill_signature_band(img: &mut ImageBuffer<Rgb<u8>, Vec<u8>>, sides: usize, style: &MarkerStyle, color: Rgb<u8>) {
+    let (w, h) = (img.width() as f32, img.height() as f32);
+    let margin = style.margin_pct.clamp(0.0, 0.49) * w.min(h);
+    let radius = ((w - 2.0 * margin) * 0.5).min((h - 2.0 * margin) * 0.5).max(1.0);
+    let (cx, cy) = (w * 0.5, h * 0.5);
+    let band = style.signature_band_px as f32;
+    let sides = style.shape.wedges(sides);
+    // Vertex radius that moves every edge out by `d` (for sectors, just the radius)
+    let vertex_radius = |d: f32| match style.shape {
+        MarkerShape::Sectors { .. } => radius + d,
+        MarkerShape::Polygon => radius + d / (std::f32::consts::PI / sides as f32).cos(),
+    };
+    let (inner, outer) = (vertex_radius(-1.0).max(0.0), vertex_radius(band));
+    let start_angle = -std::f32::consts::FRAC_PI_2 + style.rotation_deg.to_radians();
+    let (inner_verts, outer_verts) = (
+        polygon_vertices((cx, cy), inner, sides, start_angle),
+        polygon_vertices((cx, cy), outer, sides, start_angle),
+    );
+    let inside = |verts: &[(f32, f32)], r: f32, x: f32, y: f32| match style.shape {
+        MarkerShape::Sectors { .. } => (x - cx).powi(2) + (y - cy).powi(2) < r * r,
+        MarkerShape::Polygon => inside_convex(verts, x, y),
+    };
+    let x0 = ((cx - outer).floor() as i32).max(0);
+    let y0 = ((cy - outer).floor() as i32).max(0);
+    let x1 = ((cx + outer).ceil() as i32).min(img.width() as i32 - 1);
+    let y1 = ((cy + outer).ceil() as i32).min(img.height() as i32 - 1);
+    for y in y0..=y1 {
+        for x in x0..=x1 {
+            let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+            if inside(&outer_verts, outer, px, py) && !inside(&inner_verts, inner, px, py) {
+                img.put_pixel(x as u32, y as u32, color);
+            }
+        }
+    }
+}
+
+/// Fill the wedges of a marker centered on `img`, converting each color with `to_pixel`.
+/// With `only`, just that wedge is drawn and the rest of the canvas is left untouched.
+/// On canvases up to `MIN_WEDGE_CANVAS_PX` with `min_wedge_px` set, wedges are first
+/// rasterized into a map of which wedge owns each pixel, so wedges that would vanish can be
+/// topped up before painting; larger canvases are painted directly.
+fn fill_wedges<P: Pixel>(
+    img: &mut ImageBuffer<P, Vec<P::Subpixel>>,
+    sides: usize,
+    colors: &[Rgb<u8>],
+    style: &MarkerStyle,
+    only: Option<usize>,
+    to_pixel: impl Fn(Rgb<u8>) -> P,
+) {
+    let MarkerStyle { margin_pct, wedge_gap_px, rotation_deg, shape, wedge_gradient, dither_levels, min_wedge_px, .. } = *style;
+    let paint = |c: Rgb<u8>, x: u32, y: u32| match dither_levels {
+        Some(levels) => to_pixel(dither_color(c, levels, x, y)),
+        None => to_pixel(c),
+    };
+    let sides = shape.wedges(sides);
+    let tiny = img.width().min(img.height()) < TINY_CANVAS_PX;
+    let w = img.width() as f32;
+    let h_img = img.height() as f32;
     
     // Draw centered on the full canvas with even padding
-    let margin = 0.08f32 * w.min(h_img);
+    let margin = margin_pct.clamp(0.0, 0.49) * w.min(h_img);
     let radius = ((w - 2.0 * margin) * 0.5)
         .min((h_img - 2.0 * margin) * 0.5)
         .max(1.0);
     let cx = w * 0.5;
     let cy = h_img * 0.5;
     let angle_step = std::f32::consts::TAU / (sides as f32);
-    let start_angle = -std::f32::consts::FRAC_PI_2; // point up
+    let start_angle = -std::f32::consts::FRAC_PI_2 + rotation_deg.to_radians(); // point up, then rotate
+    let geometry = WedgeGeometry { center: (cx, cy), radius, start_angle, sides, gap_px: wedge_gap_px, tiny, sectors: matches!(shape, MarkerShape::Sectors { .. }) };
 
-    let mut verts: Vec<Point> = Vec::with_capacity(sides);
-    for i in 0..sides {
-        let a = start_angle + angle_step * (i as f32);
-        let x = cx + radius * a.cos();
-        let y = cy + radius * a.sin();
-        verts.push(Point { x: x.round() as i32, y: y.round() as i32 });
+    // Gradient position: for polygons, distance from the centroid along the wedge bisector
+    // over the apothem, so it reaches 1 exactly on the polygon edge; for sectors, distance
+    // from the center over the radius
+    let apothem = radius * (angle_step * 0.5).cos();
+    let bisectors: Vec<(f32, f32)> = (0..sides)
+        .map(|i| {
+            let mid = start_angle + angle_step * (i as f32 + 0.5);
+            (mid.cos() / apothem, mid.sin() / apothem)
+        })
+        .collect();
+    let shade = |i: usize, x: u32, y: u32| {
+        let base = colors[i % colors.len()];
+        let c = if !wedge_gradient {
+            base
+        } else if geometry.uses_sectors() {
+            let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+            wedge_gradient_color(base, (dx * dx + dy * dy).sqrt() / radius)
+        } else {
+            let (bx, by) = bisectors[i];
+            wedge_gradient_color(base, (x as f32 - cx) * bx + (y as f32 - cy) * by)
+        };
+        paint(c, x, y)
+    };
+
+    if min_wedge_px == 0 || img.width().max(img.height()) > MIN_WEDGE_CANVAS_PX {
+        rasterize_wedges(img, &geometry, only, shade);
+        return;
+    }
+    // Wedge index + 1 of each pixel, 0 where no wedge is drawn
+    let mut owner: ImageBuffer<Luma<u16>, Vec<u16>> = ImageBuffer::new(img.width(), img.height());
+    rasterize_wedges(&mut owner, &geometry, None, |i, _, _| Luma([i as u16 + 1]));
+    top_up_wedges(&mut owner, sides, (cx, cy), radius, start_angle, min_wedge_px as usize);
+    for (x, y, o) in owner.enumerate_pixels() {
+        let Some(i) = (o[0] as usize).checked_sub(1) else { continue };
+        if only.is_none_or(|k| k == i) {
+            img.put_pixel(x, y, shade(i, x, y));
+        }
     }
+}
+
+/// Where the wedges of one marker go on its canvas (see `fill_wedges`)
+struct WedgeGeometry {
+    center: (f32, f32),
+    radius: f32,
+    start_angle: f32,
+    sides: usize,
+    gap_px: u32,
+    /// Canvas under `TINY_CANVAS_PX`: every pixel goes to a wedge by angle alone
+    tiny: bool,
+    sectors: bool,
+}
+
+impl WedgeGeometry {
+    /// Scanline triangles of a pixel or two overlap, so tiny canvases use the sector path
+    fn uses_sectors(&self) -> bool {
+        self.tiny || self.sectors
+    }
+}
+
+/// Draw the wedges of `geometry` (just wedge `only`, if given) into `img`, with the pixel
+/// `color_at` gives for the wedge index and position
+fn rasterize_wedges<P: Pixel>(img: &mut ImageBuffer<P, Vec<P::Subpixel>>, geometry: &WedgeGeometry, only: Option<usize>, color_at: impl Fn(usize, u32, u32) -> P) {
+    let WedgeGeometry { center: (cx, cy), radius, start_angle, sides, gap_px, tiny, .. } = *geometry;
+    let angle_step = std::f32::consts::TAU / (sides as f32);
+    if geometry.uses_sectors() {
+        // On a tiny canvas the corners are outside the radius but still go to a wedge
+        let (gap_px, reach) = if tiny { (0, (img.width() as f32).hypot(img.height() as f32) * 0.5) } else { (gap_px, radius) };
+        draw_sectors(img, (cx, cy), reach, start_angle, sides, gap_px, |i, x, y| only.is_none_or(|k| k == i).then(|| color_at(i, x, y)));
+        return;
+    }
+    let verts: Vec<Point> = polygon_vertices((cx, cy), radius, sides, start_angle)
+        .into_iter()
+        .map(|(x, y)| Point { x: x.round() as i32, y: y.round() as i32 })
+        .collect();
     let centroid = Point { x: cx.round() as i32, y: cy.round() as i32 };
 
-    // Draw colored triangular segments
+    // Draw the triangular segments
     for i in 0..sides {
-        let v0 = verts[i];
-        let v1 = verts[(i + 1) % sides];
-        let color = colors[i % colors.len()];
-        draw_filled_triangle(&mut img, centroid, v0, v1, color);
+        if only.is_some_and(|k| k != i) {
+            continue;
+        }
+        let shade = |x: u32, y: u32| color_at(i, x, y);
+        if gap_px == 0 {
+            let v0 = verts[i];
+            let v1 = verts[(i + 1) % sides];
+            draw_filled_triangle(img, centroid, v0, v1, shade);
+            continue;
+        }
+        // Offset both spokes of the wedge inward by half the gap, so each spoke ends up
+        // centered in a gap of the full width. The apex slides out along the bisector and
+        // the outer corners slide along the polygon edge.
+        let half_gap = gap_px as f32 * 0.5;
+        let half_angle = angle_step * 0.5;
+        let apex_shift = half_gap / half_angle.sin();
+        if apex_shift >= radius * half_angle.cos() {
+            continue; // gap swallows the whole wedge
+        }
+        let mid = start_angle + angle_step * (i as f32 + 0.5);
+        let a0 = start_angle + angle_step * (i as f32);
+        let a1 = a0 + angle_step;
+        let (p0x, p0y) = (cx + radius * a0.cos(), cy + radius * a0.sin());
+        let (p1x, p1y) = (cx + radius * a1.cos(), cy + radius * a1.sin());
+        let edge_len = ((p1x - p0x).powi(2) + (p1y - p0y).powi(2)).sqrt();
+        let (ux, uy) = ((p1x - p0x) / edge_len, (p1y - p0y) / edge_len);
+        let corner_shift = half_gap / half_angle.cos();
+        let pt = |x: f32, y: f32| Point { x: x.round() as i32, y: y.round() as i32 };
+        let apex = pt(cx + apex_shift * mid.cos(), cy + apex_shift * mid.sin());
+        let v0 = pt(p0x + ux * corner_shift, p0y + uy * corner_shift);
+        let v1 = pt(p1x - ux * corner_shift, p1y - uy * corner_shift);
+        draw_filled_triangle(img, apex, v0, v1, shade);
     }
+}
+
+/// Give every wedge in `owner` (wedge index + 1 per pixel, 0 for none) at least `min_px`
+/// pixels, taking those nearest the middle of its bisector from wedges with more than that
+fn top_up_wedges(owner: &mut ImageBuffer<Luma<u16>, Vec<u16>>, sides: usize, (cx, cy): (f32, f32), radius: f32, start_angle: f32, min_px: usize) {
+    let mut counts = vec![0usize; sides];
+    for o in owner.pixels() {
+        if let Some(i) = (o[0] as usize).checked_sub(1) {
+            counts[i] += 1;
+        }
+    }
+    let step = std::f32::consts::TAU / sides as f32;
+    for i in 0..sides {
+        if counts[i] >= min_px {
+            continue;
+        }
+        let mid = start_angle + step * (i as f32 + 0.5);
+        let (tx, ty) = (cx + 0.5 * radius * mid.cos(), cy + 0.5 * radius * mid.sin());
+        let mut spare: Vec<(f32, u32, u32)> = owner
+            .enumerate_pixels()
+            .filter(|&(_, _, o)| o[0] != 0 && o[0] as usize != i + 1)
+            .map(|(x, y, _)| ((x as f32 + 0.5 - tx).powi(2) + (y as f32 + 0.5 - ty).powi(2), x, y))
+            .collect();
+        spare.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for (_, x, y) in spare {
+            if counts[i] >= min_px {
+                break;
+            }
+            let from = owner.get_pixel(x, y)[0] as usize - 1;
+            if counts[from] > min_px {
+                counts[from] -= 1;
+                counts[i] += 1;
+                owner.put_pixel(x, y, Luma([i as u16 + 1]));
+            }
+        }
+    }
+}
+
+/// Downscale `img` to `width` x `height`, filtering in linear light so thin features fade
+/// the way they do on a distant print instead of darkening as sRGB averages do
+pub fn downscale_linear(img: &RgbImage, width: u32, height: u32) -> RgbImage {
+    let to_linear: Vec<f32> = (0..=255u8)
+        .map(|v| {
+            let c = v as f32 / 255.0;
+            if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+        })
+        .collect();
+    let linear = Rgb32FImage::from_fn(img.width(), img.height(), |x, y| {
+        let Rgb([r, g, b]) = *img.get_pixel(x, y);
+        Rgb([to_linear[r as usize], to_linear[g as usize], to_linear[b as usize]])
+    });
+    let small = imageops::resize(&linear, width, height, FilterType::Triangle);
+    let to_srgb = |c: f32| {
+        let c = c.clamp(0.0, 1.0);
+        let s = if c <= 0.0031308 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 };
+        (s * 255.0).round() as u8
+    };
+    RgbImage::from_fn(width, height, |x, y| {
+        let Rgb([r, g, b]) = *small.get_pixel(x, y);
+        Rgb([to_srgb(r), to_srgb(g), to_srgb(b)])
+    })
+}
 
-    // Optional center dot (solid black circle)
-    if center_dot {
+/// Draw only wedge `index` of a marker on a transparent canvas, in the same place it takes in
+/// the output of `draw_marker_polygon` (including the offsets added by the fiducial border
+/// and quiet zone)
+pub fn draw_wedge_layer(width: u32, height: u32, sides: usize, colors: &[Rgb<u8>], style: &MarkerStyle, index: usize) -> RgbaImage {
+    let mut img = RgbaImage::new(width, height);
+    fill_wedges(&mut img, sides, colors, style, Some(index), |c| Rgba([c[0], c[1], c[2], 255]));
+    if style.fiducial_border {
+        let g = fiducial_geometry(width, height);
+        let mut out = RgbaImage::new(g.canvas.0, g.canvas.1);
+        image::imageops::replace(&mut out, &img, g.marker_offset.0 as i64, g.marker_offset.1 as i64);
+        img = out;
+    }
+    let quiet = style.quiet_zone_px(width, height);
+    if quiet > 0 {
+        img = pad_image(&img, quiet, Rgba([0, 0, 0, 0]));
+    }
+    img
+}
+
+/// Draw a polygonal (or, with `MarkerShape::Sectors`, circular) marker with optional center and gradient dots.
+/// Dot sizes are relative to the canvas, not the polygon, so they are unaffected by the margin.
+pub fn draw_marker_polygon(
+    width: u32, 
+    height: u32, 
+    sides: usize, 
+    colors: &[Rgb<u8>], 
+    style: &MarkerStyle,
+    serial_number: Option<(usize, f32, f32, Rgb<u8>, bool)>, // (1-based index, h_align, v_align, color, border)
+) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+    let MarkerStyle {
+        center_dot, center_dot_size_pct, center_dot_color, center_ring, center_ring_inner_pct,
+        gradient_dot, gradient_dot_size_pct, gradient_dot_color, gradient_dot_sigma_pct, bg, fiducial_border, rotation_deg,
+        center_shape, ..
+    } = *style;
+    let mut img = ImageBuffer::from_pixel(width, height, bg);
+    let tiny = width.min(height) < TINY_CANVAS_PX;
+
+    if let Some(band) = style.signature_band_rgb(colors) {
+        fill_signature_band(&mut img, sides, style, band);
+    }
+    fill_wedges(&mut img, sides, colors, style, None, |c| c);
+
+    let w = width as f32;
+    let h_img = height as f32;
+    let (cx, cy) = (w * 0.5, h_img * 0.5);
+
+    // Optional ring around the polygon, by distance of each pixel from the center
+    if let Some(ring) = bounding_ring(width, height, style) {
+        let (inner2, outer2) = (ring.inner_radius_px.powi(2), ring.outer_radius_px.powi(2));
+        let color = Rgb([ring.rgb.0, ring.rgb.1, ring.rgb.2]);
+        let r = ring.outer_radius_px;
+        let x0 = ((cx - r).floor() as i32).max(0);
+        let y0 = ((cy - r).floor() as i32).max(0);
+        let x1 = ((cx + r).ceil() as i32).min((width as i32) - 1);
+        let y1 = ((cy + r).ceil() as i32).min((height as i32) - 1);
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+                let d2 = dx * dx + dy * dy;
+                if d2 >= inner2 && d2 < outer2 {
+                    img.put_pixel(x as u32, y as u32, color);
+                }
+            }
+        }
+    }
+
+    // Optional center dot (solid circle or polygon, or a ring of either when center_ring is set)
+    if center_dot && !tiny {
         let pct = (center_dot_size_pct / 100.0).clamp(0.01, 1.0);
         let r = ((w.min(h_img)) * pct * 0.5).max(1.0);
         let r2 = r * r;
+        let inner = if center_ring { r * (center_ring_inner_pct / 100.0).clamp(0.0, 0.99) } else { 0.0 };
+        let inner2 = inner * inner;
         let x0 = ((cx - r).floor() as i32).max(0);
         let y0 = ((cy - r).floor() as i32).max(0);
         let x1 = ((cx + r).ceil() as i32).min((width as i32) - 1);
         let y1 = ((cy + r).ceil() as i32).min((height as i32) - 1);
+        let start_angle = -std::f32::consts::FRAC_PI_2 + rotation_deg.to_radians();
+        let (outer_poly, inner_poly) = match center_shape {
+            CenterShape::Circle => (Vec::new(), Vec::new()),
+            CenterShape::Polygon { sides } => (
+                polygon_vertices((cx, cy), r, sides, start_angle),
+                polygon_vertices((cx, cy), inner, sides, start_angle),
+            ),
+        };
         
         for y in y0..=y1 {
             for x in x0..=x1 {
-                let dx = (x as f32) - cx;
-                let dy = (y as f32) - cy;
-                if dx * dx + dy * dy <= r2 {
-                    img.put_pixel(x as u32, y as u32, Rgb([0, 0, 0]));
+                let (fx, fy) = (x as f32, y as f32);
+                let inside = match center_shape {
+                    CenterShape::Circle => {
+                        let d2 = (fx - cx).powi(2) + (fy - cy).powi(2);
+                        d2 <= r2 && (!center_ring || d2 >= inner2)
+                    }
+                    CenterShape::Polygon { .. } => {
+                        inside_convex(&outer_poly, fx, fy) && (!center_ring || !inside_convex(&inner_poly, fx, fy))
+                    }
+                };
+                if inside {
+                    img.put_pixel(x as u32, y as u32, center_dot_color);
                 }
             }
         }
     }
     
-    // Optional gradient dot (Gaussian fade to white)
-    if gradient_dot {
+    // Optional gradient dot (Gaussian fade to gradient_dot_color)
+    if gradient_dot && !tiny {
         let pct_g = (gradient_dot_size_pct / 100.0).clamp(0.01, 1.0);
         let rg = ((w.min(h_img)) * pct_g * 0.5).max(1.0);
         let rg2 = rg * rg;
@@ -323,7 +1087,7 @@ pub fn draw_marker_polygon(
         let y0 = ((cy - rg).floor() as i32).max(0);
         let x1 = ((cx + rg).ceil() as i32).min((width as i32) - 1);
         let y1 = ((cy + rg).ceil() as i32).min((height as i32) - 1);
-        let sigma = (rg * 0.7).max(0.5);
+        let sigma = (rg * gradient_dot_sigma_pct.max(GRADIENT_DOT_SIGMA_MIN)).max(0.5);
         let two_sigma2 = 2.0 * sigma * sigma;
         
         for y in y0..=y1 {
@@ -335,21 +1099,26 @@ pub fn draw_marker_polygon(
                     let alpha = (-dist2 / two_sigma2).exp();
                     if alpha > 0.001 {
                         let p = img.get_pixel_mut(x as u32, y as u32);
-                        let (r0, g0, b0) = (p[0] as f32, p[1] as f32, p[2] as f32);
                         let inv = 1.0 - alpha;
-                        let r1 = (255.0 * alpha + r0 * inv).round().clamp(0.0, 255.0) as u8;
-                        let g1 = (255.0 * alpha + g0 * inv).round().clamp(0.0, 255.0) as u8;
-                        let b1 = (255.0 * alpha + b0 * inv).round().clamp(0.0, 255.0) as u8;
-                        *p = Rgb([r1, g1, b1]);
+                        let mix = |k: usize| (gradient_dot_color[k] as f32 * alpha + p[k] as f32 * inv).round().clamp(0.0, 255.0) as u8;
+                        *p = Rgb([mix(0), mix(1), mix(2)]);
                     }
                 }
             }
         }
     }
 
-    if let Some((number, h_align, v_align, color, border)) = serial_number {
+    if let Some((number, h_align, v_align, color, border)) = serial_number.filter(|_| !tiny) {
         draw_serial_number(&mut img, number, h_align, v_align, color, border);
     }
 
+    if fiducial_border {
+        img = add_fiducial_border(&img, bg);
+    }
+    let quiet = style.quiet_zone_px(width, height);
+    if quiet > 0 {
+        img = pad_image(&img, quiet, Rgb([255, 255, 255]));
+    }
+
     img
 }
@@ -0,0 +1,254 @@
+//! Color conversion, pool building, selection and wedge ordering
+
+use image::{DynamicImage, Rgb, RgbImage};
+use palette::Lab;
+use polycue::color::{compute_max_threshold_and_colors_from_pool, delta_e, farthest_point_order, find_similar_tags, lab_hue, linear_u8_to_lab, merge_near_duplicates, min_adjacent_delta_l, min_pairwise_delta_e, nearest_pantone, palette_from_image, pairwise_delta_matrix, pick_distinct_strict, reorder_bright_dark_alternating, reorder_bright_dark_odd, reorder_by_hue, reorder_max_adjacent, srgb_u8_to_lab, ColorSpace, PoolSource, ThresholdSearch};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+#[test]
+fn kmeans_recovers_three_color_clusters() {
+    let clusters = [Rgb([200, 30, 40]), Rgb([30, 160, 60]), Rgb([40, 60, 190])];
+    // Three bands with a little per-pixel jitter, so each cluster has some spread
+    let img = RgbImage::from_fn(90, 60, |x, y| {
+        let base = clusters[(x / 30) as usize];
+        let jitter = ((x * 7 + y * 13) % 7) as i16 - 3;
+        Rgb(base.0.map(|v| (v as i16 + jitter).clamp(0, 255) as u8))
+    });
+    let palette = palette_from_image(&DynamicImage::ImageRgb8(img), 3);
+    assert_eq!(palette.len(), 3);
+    for c in clusters {
+        let nearest = palette.iter().map(|&p| delta_e(srgb_u8_to_lab(p), srgb_u8_to_lab(c))).fold(f32::INFINITY, f32::min);
+        assert!(nearest < 1.5, "{:?} recovered only within ΔE {:.2}: {:?}", c, nearest, palette);
+    }
+}
+
+#[test]
+fn more_search_iterations_close_in_on_the_feasibility_boundary() {
+    // With exactly three colors to pick, a threshold is feasible only when the whole pool
+    // fits, so the shuffle never matters and the boundary is the smallest pairwise ΔE
+    let pool = vec![Rgb([0, 0, 0]), Rgb([90, 90, 90]), Rgb([255, 255, 255])];
+    let labs: Vec<Lab> = pool.iter().map(|&c| srgb_u8_to_lab(c)).collect();
+    let dm = pairwise_delta_matrix(&labs);
+    let exact = dm.iter().copied().filter(|&d| d > 0.0).fold(f32::INFINITY, f32::min);
+    let hi = dm.iter().copied().fold(0.0f32, f32::max);
+
+    let mut last_err = f32::INFINITY;
+    for iterations in [2, 4, 8, 12, 16] {
+        let search = ThresholdSearch { iterations, ..Default::default() };
+        let (threshold, colors) = compute_max_threshold_and_colors_from_pool(&pool, &labs, 3, &search, Some(&dm), &mut StdRng::seed_from_u64(1));
+        assert_eq!(colors.len(), 3);
+        let err = exact - threshold;
+        let epsilon = hi / (1u32 << iterations) as f32;
+        assert!((0.0..=epsilon + 1e-4).contains(&err), "{iterations} iterations: {threshold} vs exact {exact}");
+        assert!(err <= last_err, "{iterations} iterations moved away from the boundary");
+        last_err = err;
+    }
+    assert!(last_err < 0.01);
+}
+
+#[test]
+fn lab_and_oklab_rank_blue_pairs_differently() {
+    let distance = |space: ColorSpace, a: Rgb<u8>, b: Rgb<u8>| delta_e(space.coords(a), space.coords(b));
+    let blue = Rgb([0, 0, 255]);
+    let azure = Rgb([0, 80, 255]);
+    let dark_blue = Rgb([0, 0, 180]);
+    // CIE76 overstates the hue shift toward azure; Oklab finds darkening the bigger change
+    assert!(distance(ColorSpace::Lab, blue, azure) > distance(ColorSpace::Lab, blue, dark_blue));
+    assert!(distance(ColorSpace::Oklab, blue, azure) < distance(ColorSpace::Oklab, blue, dark_blue));
+}
+
+#[test]
+fn hue_wheel_order_has_monotonic_hue() {
+    let original = vec![
+        Rgb([40, 60, 220]), Rgb([230, 200, 40]), Rgb([200, 40, 40]), Rgb([60, 180, 70]),
+        Rgb([180, 60, 200]), Rgb([40, 190, 200]), Rgb([240, 140, 30]),
+    ];
+    let mut colors = original.clone();
+    reorder_by_hue(&mut colors);
+    let hues: Vec<f32> = colors.iter().map(|&c| lab_hue(srgb_u8_to_lab(c))).collect();
+    assert!(hues.windows(2).all(|w| w[0] <= w[1]), "{hues:?}");
+    // Only the order changes, never the colors
+    let (mut a, mut b) = (original, colors);
+    a.sort_by_key(|c| c.0);
+    b.sort_by_key(|c| c.0);
+    assert_eq!(a, b);
+}
+
+#[test]
+fn five_color_reorders_raise_min_adjacent_lightness_difference() {
+    // Grays in ascending lightness: neighbors differ by one small step
+    let unsorted: Vec<Rgb<u8>> = [30u8, 80, 130, 180, 230].iter().map(|&v| Rgb([v, v, v])).collect();
+    let before = min_adjacent_delta_l(&unsorted);
+
+    let mut odd = unsorted.clone();
+    reorder_bright_dark_odd(&mut odd);
+    assert!(min_adjacent_delta_l(&odd) > before, "{:?}", odd);
+
+    // On grays ΔE is the lightness difference, so the exact search must do at least as well
+    let mut exact = unsorted.clone();
+    reorder_max_adjacent(&mut exact, ColorSpace::Lab);
+    assert!(min_adjacent_delta_l(&exact) > before, "{:?}", exact);
+    assert!(min_adjacent_delta_l(&exact) >= min_adjacent_delta_l(&odd) - 1e-4);
+}
+
+#[test]
+fn pure_blue_maps_to_a_pantone_blue() {
+    let blue = srgb_u8_to_lab(Rgb([0, 0, 255]));
+    let (name, de) = nearest_pantone(blue);
+    let blues = ["PANTONE Blue 072 C", "PANTONE Reflex Blue C", "PANTONE 2728 C", "PANTONE 286 C", "PANTONE 293 C"];
+    assert!(blues.contains(&name), "pure blue matched {name}");
+    // sRGB blue is outside the coated gamut, so the match is real but not close
+    assert!(de.is_finite() && de > 0.0 && de < 60.0, "ΔE {de}");
+    // An exact table color matches itself
+    assert_eq!(nearest_pantone(srgb_u8_to_lab(Rgb([0, 0, 0]))).0, "PANTONE Black C");
+}
+
+#[test]
+fn farthest_point_order_finds_the_spread_set_random_orders_miss() {
+    // Four extremes hidden in a crowd of mid grays: any gray rules out black and white, so
+    // a greedy pick only reaches four colors at a high threshold when it starts on an extreme
+    let extremes = [Rgb([0, 0, 0]), Rgb([255, 255, 255]), Rgb([255, 0, 0]), Rgb([0, 0, 255])];
+    let mut pool: Vec<Rgb<u8>> = (0..200u32).map(|i| Rgb([100 + (i % 50) as u8, 110 + (i / 50) as u8, 120])).collect();
+    pool.extend(extremes);
+    let labs: Vec<Lab> = pool.iter().map(|&c| srgb_u8_to_lab(c)).collect();
+    let ext: Vec<Lab> = extremes.iter().map(|&c| srgb_u8_to_lab(c)).collect();
+    let best = (0..4).flat_map(|i| (i + 1..4).map(move |j| (i, j))).map(|(i, j)| delta_e(ext[i], ext[j])).fold(f32::INFINITY, f32::min);
+    let thr = best * 0.95;
+
+    assert_eq!(pick_distinct_strict(&labs, &farthest_point_order(&labs), thr, 4).len(), 4);
+    let hits = (0..200)
+        .filter(|&seed| {
+            let mut order: Vec<usize> = (0..pool.len()).collect();
+            order.shuffle(&mut StdRng::seed_from_u64(seed));
+            pick_distinct_strict(&labs, &order, thr, 4).len() == 4
+        })
+        .count();
+    assert!(hits < 20, "random orders found the set {hits} times out of 200");
+
+    let search = ThresholdSearch::default();
+    let (found, colors) = compute_max_threshold_and_colors_from_pool(&pool, &labs, 4, &search, None, &mut StdRng::seed_from_u64(7));
+    assert!(found >= thr, "threshold {found} below {thr}");
+    assert!(extremes.iter().all(|c| colors.contains(c)), "{colors:?}");
+}
+
+#[test]
+fn equal_lightness_ties_break_on_a_then_b() {
+    // A navy and an olive with bit-identical Lab L; the navy's larger a* puts it first
+    let (navy, olive) = (Rgb([10, 10, 190]), Rgb([25, 65, 5]));
+    assert_eq!(srgb_u8_to_lab(navy).l, srgb_u8_to_lab(olive).l);
+    for input in [vec![navy, olive], vec![olive, navy]] {
+        let mut colors = input;
+        reorder_bright_dark_alternating(&mut colors);
+        assert_eq!(colors, [navy, olive]);
+    }
+
+    let white = Rgb([255, 255, 255]);
+    let mut first = vec![white, navy, olive, Rgb([0, 0, 0])];
+    let mut second = vec![olive, Rgb([0, 0, 0]), navy, white];
+    reorder_bright_dark_alternating(&mut first);
+    reorder_bright_dark_alternating(&mut second);
+    assert_eq!(first, second);
+}
+
+#[test]
+fn built_in_palettes_have_their_documented_sizes() {
+    for (source, size) in [(PoolSource::FullGrid, 216), (PoolSource::WebSafe, 216), (PoolSource::Material, 190), (PoolSource::Grayscale, 256)] {
+        let colors = source.colors();
+        assert_eq!(colors.len(), size, "{}", source.label());
+        let distinct: std::collections::HashSet<[u8; 3]> = colors.iter().map(|c| c.0).collect();
+        assert_eq!(distinct.len(), size, "{} repeats a color", source.label());
+    }
+    assert!(PoolSource::WebSafe.colors().iter().all(|c| c.0.iter().all(|v| v % 0x33 == 0)));
+    assert!(PoolSource::Grayscale.colors().iter().all(|c| c[0] == c[1] && c[1] == c[2]));
+}
+
+#[test]
+fn prefer_vivid_raises_the_mean_chroma_of_the_selection() {
+    let pool = PoolSource::FullGrid.colors();
+    let labs: Vec<Lab> = pool.iter().map(|&c| srgb_u8_to_lab(c)).collect();
+    let mean_chroma = |prefer_vivid: bool| {
+        // Same fixed threshold both ways, so only the order candidates are tried in differs
+        let search = ThresholdSearch { iterations: 1, lo: Some(25.0), hi: Some(25.0), prefer_vivid };
+        let (thr, colors) = compute_max_threshold_and_colors_from_pool(&pool, &labs, 24, &search, None, &mut StdRng::seed_from_u64(3));
+        assert_eq!((thr, colors.len()), (25.0, 24));
+        colors.iter().map(|&c| { let l = srgb_u8_to_lab(c); l.a.hypot(l.b) }).sum::<f32>() / colors.len() as f32
+    };
+    let (vivid, plain) = (mean_chroma(true), mean_chroma(false));
+    assert!(vivid > plain + 10.0, "mean chroma {vivid} with prefer vivid, {plain} without");
+}
+
+#[test]
+fn known_srgb_values_map_to_d65_lab() {
+    let close = |lab: Lab, (l, a, b): (f32, f32, f32)| (lab.l - l).abs() < 0.05 && (lab.a - a).abs() < 0.05 && (lab.b - b).abs() < 0.05;
+    for (rgb, expected) in [
+        (Rgb([255, 0, 0]), (53.24, 80.09, 67.20)),
+        (Rgb([0, 255, 0]), (87.73, -86.18, 83.18)),
+        (Rgb([0, 0, 255]), (32.30, 79.19, -107.86)),
+        (Rgb([255, 255, 255]), (100.0, 0.0, 0.0)),
+        (Rgb([0, 0, 0]), (0.0, 0.0, 0.0)),
+    ] {
+        let lab = srgb_u8_to_lab(rgb);
+        assert!(close(lab, expected), "{rgb:?} gave {lab:?}, not {expected:?}");
+    }
+    // Linear input skips the transfer function: full-scale channels agree, while a mid value
+    // counts as half the light (L 76) rather than sRGB mid gray's 22% (L 54)
+    assert!(close(linear_u8_to_lab(Rgb([255, 0, 0])), (53.24, 80.09, 67.20)));
+    assert!((linear_u8_to_lab(Rgb([128; 3])).l - 76.2).abs() < 0.5);
+    assert!((srgb_u8_to_lab(Rgb([128; 3])).l - 53.6).abs() < 0.5);
+}
+
+#[test]
+fn merging_collapses_near_duplicates_but_keeps_distinct_colors() {
+    let gray = |v: u8| Rgb([v, v, v]);
+    let de = |a: u8, b: u8| delta_e(srgb_u8_to_lab(gray(a)), srgb_u8_to_lab(gray(b)));
+    let (near, far) = (gray(131), gray(141));
+    assert!((de(128, 131) - 1.0).abs() < 0.3, "{}", de(128, 131));
+    assert!((de(128, 141) - 5.0).abs() < 0.5, "{}", de(128, 141));
+
+    assert_eq!(merge_near_duplicates(&[gray(128), near], 2.0), [gray(128)]);
+    assert_eq!(merge_near_duplicates(&[gray(128), far], 2.0), [gray(128), far]);
+    assert_eq!(merge_near_duplicates(&[gray(128), gray(128), near, far], 2.0), [gray(128), far]);
+    // Exact repeats go even with merging off
+    assert_eq!(merge_near_duplicates(&[gray(128), gray(128), near], 0.0), [gray(128), near]);
+}
+
+#[test]
+fn lower_lightness_weight_spreads_the_selection_over_more_hues() {
+    let pool = PoolSource::FullGrid.colors();
+    // Over a few seeds: 15° hue bins holding a clearly chromatic color, and the mean
+    // standard deviation of L within the selection
+    let spread = |weight: f32| -> (usize, f32) {
+        let labs: Vec<Lab> = pool.iter().map(|&c| ColorSpace::Lab.weighted_coords(c, weight)).collect();
+        let dm = pairwise_delta_matrix(&labs);
+        (0..8).fold((0, 0.0), |(bins, l_sd), seed| {
+            let (_, colors) = compute_max_threshold_and_colors_from_pool(&pool, &labs, 24, &ThresholdSearch::default(), Some(&dm), &mut StdRng::seed_from_u64(seed));
+            let selected: Vec<Lab> = colors.iter().map(|&c| srgb_u8_to_lab(c)).collect();
+            let hues: std::collections::HashSet<u32> = selected
+                .iter()
+                .filter(|l| l.a.hypot(l.b) > 15.0)
+                .map(|&l| (lab_hue(l).to_degrees() / 15.0) as u32)
+                .collect();
+            let mean_l = selected.iter().map(|l| l.l).sum::<f32>() / selected.len() as f32;
+            let sd = (selected.iter().map(|l| (l.l - mean_l).powi(2)).sum::<f32>() / selected.len() as f32).sqrt();
+            (bins + hues.len(), l_sd + sd / 8.0)
+        })
+    };
+    let (full, quarter) = (spread(1.0), spread(0.25));
+    assert!(quarter.0 > full.0, "hue bins: {} at weight 0.25, {} at 1", quarter.0, full.0);
+    assert!(quarter.1 < full.1, "L deviation: {} at weight 0.25, {} at 1", quarter.1, full.1);
+}
+
+#[test]
+fn saved_delta_e_uses_the_lightness_weight() {
+    let gray = |v: u8| Rgb([v, v, v]);
+    let tag = [gray(60), gray(180)];
+    let full = min_pairwise_delta_e(&tag, ColorSpace::Lab, 1.0);
+    assert!((full - delta_e(srgb_u8_to_lab(tag[0]), srgb_u8_to_lab(tag[1]))).abs() < 1e-3);
+    assert!((min_pairwise_delta_e(&tag, ColorSpace::Lab, 0.5) - full * 0.5).abs() < 0.1);
+    assert_eq!(min_pairwise_delta_e(&tag[..1], ColorSpace::Lab, 1.0), f32::INFINITY);
+
+    // Two gray tags only differ in L, so a low weight pulls them under the distance
+    let tags = vec![vec![gray(60), gray(180)], vec![gray(70), gray(190)]];
+    assert!(find_similar_tags(&tags, 2.0, ColorSpace::Lab, 1.0).is_empty());
+    assert_eq!(find_similar_tags(&tags, 2.0, ColorSpace::Lab, 0.1).len(), 1);
+}
@@ -0,0 +1,47 @@
+//! Helpers shared by the integration tests
+
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+use polycue::color::ColorSpace;
+use polycue::io::{GenerationSettings, ImageFormat, ManifestFormat, SaveOptions};
+use polycue::render::MarkerStyle;
+
+/// Plain PNG output under `out_root`: default style, JSON manifest, no sheet extras
+pub fn options(out_root: &str) -> SaveOptions {
+    SaveOptions {
+        out_root: out_root.to_string(),
+        format: ImageFormat::Png,
+        style: MarkerStyle::default(),
+        marker_size: (64, 64),
+        similar_tag_distance: None,
+        color_space: ColorSpace::Lab,
+        lightness_weight: 1.0,
+        sixteen_bit: false,
+        gutter_px: 0,
+        tile_labels: false,
+        print_dpi: 300.0,
+        bleed_mm: 0.0,
+        trim_marks: false,
+        registration_dots: false,
+        seed: 0,
+        embed_metadata: false,
+        tag_rotations: Vec::new(),
+        unsnapped: Vec::new(),
+        wedge_layers: false,
+        categories: Vec::new(),
+        category_folders: false,
+        min_lightness_spread: 0.0,
+        manifest_format: ManifestFormat::Json,
+        settings: GenerationSettings::default(),
+    }
+}
+
+/// Fresh, empty output root under the system temp directory, one per test
+pub fn temp_root(test: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("polycue-test-{}-{}", std::process::id(), test));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    root
+}
@@ -0,0 +1,109 @@
+//! Full generate-and-save cycle through the library API, checked against the manifest
+
+use std::path::PathBuf;
+
+use image::{DynamicImage, Rgb};
+use palette::Lab;
+use polycue::color::{compute_max_threshold_and_colors_from_pool, delta_e, pairwise_delta_matrix, srgb_u8_to_lab, ColorSpace, PoolSource, ThresholdSearch};
+use polycue::io::{save_all, scan_output, GenerationSettings, ImageFormat, SaveOptions};
+use polycue::render::{draw_marker_polygon, group_colors_into_groups_monte_carlo, MarkerStyle};
+use rand::{rngs::StdRng, SeedableRng};
+
+mod common;
+
+const SEED: u64 = 7;
+const TAGS: usize = 6;
+const SIDES: usize = 4;
+const SIZE: u32 = 96;
+
+fn style() -> MarkerStyle {
+    MarkerStyle { center_dot: true, center_dot_size_pct: 20.0, gradient_dot_size_pct: 20.0, ..Default::default() }
+}
+
+fn options(out_root: &str) -> SaveOptions {
+    SaveOptions {
+        style: style(),
+        marker_size: (SIZE, SIZE),
+        seed: SEED,
+        settings: GenerationSettings { count: TAGS, wedges: SIDES, seed: SEED, ..Default::default() },
+        ..common::options(out_root)
+    }
+}
+
+#[test]
+fn generate_and_save_writes_a_matching_manifest() {
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let pool = PoolSource::default().colors();
+    let labs: Vec<Lab> = pool.iter().map(|&c| ColorSpace::Lab.coords(c)).collect();
+    let dm = pairwise_delta_matrix(&labs);
+
+    let needed = TAGS * SIDES;
+    let (threshold, colors) = compute_max_threshold_and_colors_from_pool(&pool, &labs, needed, &ThresholdSearch::default(), Some(&dm), &mut rng);
+    assert_eq!(colors.len(), needed);
+    assert!(threshold > 0.0);
+    for (i, &a) in colors.iter().enumerate() {
+        for &b in &colors[i + 1..] {
+            assert!(delta_e(srgb_u8_to_lab(a), srgb_u8_to_lab(b)) >= threshold - 1e-3);
+        }
+    }
+
+    let color_labs: Vec<Lab> = colors.iter().map(|&c| ColorSpace::Lab.coords(c)).collect();
+    let tags = group_colors_into_groups_monte_carlo(colors.clone(), color_labs, &[SIDES; TAGS], 200, 0.0, &mut rng);
+    assert_eq!(tags.len(), TAGS);
+    let images: Vec<DynamicImage> = tags
+        .iter()
+        .map(|t| DynamicImage::ImageRgb8(draw_marker_polygon(SIZE, SIZE, t.len(), t, &style(), None)))
+        .collect();
+
+    let root = common::temp_root("manifest");
+    let out_dir = save_all(&tags, threshold, &images, &options(root.to_str().unwrap()), &|| true).unwrap();
+
+    let manifest: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(PathBuf::from(&out_dir).join("manifest.json")).unwrap()).unwrap();
+    assert_eq!(manifest["seed"], SEED);
+    assert!((manifest["threshold"].as_f64().unwrap() - threshold as f64).abs() < 1e-3);
+    assert_eq!(manifest["settings"]["count"], TAGS);
+    let entries = manifest["tags"].as_array().unwrap();
+    assert_eq!(entries.len(), TAGS);
+    for (entry, tag) in entries.iter().zip(&tags) {
+        assert_eq!(entry["sides"], SIDES);
+        let rgb: Vec<(u8, u8, u8)> = serde_json::from_value(entry["colors_rgb"].clone()).unwrap();
+        assert_eq!(rgb, tag.iter().map(|c| (c[0], c[1], c[2])).collect::<Vec<_>>());
+        assert!(entry["min_pairwise_delta_e"].as_f64().unwrap() >= threshold as f64 - 1e-3);
+        let file = PathBuf::from(&out_dir).join(entry["filename"].as_str().unwrap());
+        assert_eq!(image::image_dimensions(&file).unwrap(), (SIZE, SIZE));
+    }
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn category_folders_group_saved_tags() {
+    let tags: Vec<Vec<Rgb<u8>>> = (0..3u8).map(|i| (0..SIDES as u8).map(|k| Rgb([i * 80, k * 60, 255 - k * 60])).collect()).collect();
+    let images: Vec<DynamicImage> = tags
+        .iter()
+        .map(|t| DynamicImage::ImageRgb8(draw_marker_polygon(SIZE, SIZE, t.len(), t, &style(), None)))
+        .collect();
+    let root = common::temp_root("categories");
+    let opts = SaveOptions {
+        categories: vec![Some("Shelf A".into()), None, Some("bin/3".into())],
+        category_folders: true,
+        ..options(root.to_str().unwrap())
+    };
+    let out_dir = save_all(&tags, 10.0, &images, &opts, &|| true).unwrap();
+
+    let manifest: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(PathBuf::from(&out_dir).join("manifest.json")).unwrap()).unwrap();
+    let entries = manifest["tags"].as_array().unwrap();
+    assert_eq!(entries[0]["filename"], "Shelf A/tag_01.png");
+    assert_eq!(entries[0]["category"], "Shelf A");
+    assert_eq!(entries[1]["filename"], "tag_02.png");
+    assert!(entries[1].get("category").is_none());
+    assert_eq!(entries[2]["filename"], "bin_3/tag_03.png");
+    for entry in entries {
+        assert!(PathBuf::from(&out_dir).join(entry["filename"].as_str().unwrap()).is_file());
+    }
+    // Resuming finds tags inside the category folders too
+    let scan = scan_output(&out_dir, ImageFormat::Png).unwrap();
+    assert_eq!(scan.existing.iter().map(|(i, _)| *i).collect::<Vec<_>>(), vec![0, 1, 2]);
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
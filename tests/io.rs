@@ -0,0 +1,350 @@
+//! Save-path helpers: combined sheet layout, embedded metadata, swatch and spec exports
+
+use std::path::PathBuf;
+
+use image::{DynamicImage, Rgb, RgbImage};
+use polycue::io::{build_combined_image, create_timestamped_dir, read_manifest, resume_save_to_dir, save_all, save_ase, verify_output, wedge_spec, GenerationSettings, Manifest, ManifestFormat, SaveOptions, PNG_METADATA_KEYWORD};
+use polycue::render::{draw_marker_polygon, MarkerShape, MarkerStyle};
+
+mod common;
+
+const TILE: u32 = 10;
+
+#[test]
+fn gutter_keeps_every_tile_apart() {
+    // Solid one-color tiles in distinct colors, none of them the white background
+    let colors: Vec<Rgb<u8>> = (0..6u8).map(|i| Rgb([i * 40, 100, 200 - i * 30])).collect();
+    let images: Vec<DynamicImage> = colors.iter().map(|&c| DynamicImage::ImageRgb8(RgbImage::from_pixel(TILE, TILE, c))).collect();
+    let opts = SaveOptions { gutter_px: 4, ..common::options("unused") };
+    let sheet = build_combined_image(&images, Some(3), &opts, &|| true).unwrap();
+    assert_eq!(sheet.image.dimensions(), (3 * TILE + 2 * 4, 2 * TILE + 4));
+
+    // Every pixel belongs to exactly one tile and has its color, or is background
+    for (x, y, &p) in sheet.image.enumerate_pixels() {
+        let inside: Vec<usize> = sheet
+            .origins
+            .iter()
+            .enumerate()
+            .filter(|(_, &(ox, oy))| (ox..ox + TILE).contains(&x) && (oy..oy + TILE).contains(&y))
+            .map(|(i, _)| i)
+            .collect();
+        match inside[..] {
+            [] => assert_eq!(p, opts.style.bg, "gutter pixel ({x}, {y})"),
+            [i] => assert_eq!(p, colors[i]),
+            _ => panic!("tiles {inside:?} overlap at ({x}, {y})"),
+        }
+    }
+    // No two tiles touch, even diagonally
+    for (i, &(ax, ay)) in sheet.origins.iter().enumerate() {
+        for &(bx, by) in &sheet.origins[i + 1..] {
+            let apart_x = ax.abs_diff(bx) >= TILE + opts.gutter_px;
+            let apart_y = ay.abs_diff(by) >= TILE + opts.gutter_px;
+            assert!(apart_x || apart_y, "tiles at {:?} and {:?} touch", (ax, ay), (bx, by));
+        }
+    }
+}
+
+#[test]
+fn embedded_metadata_round_trips_through_the_png() {
+    let tags = vec![vec![Rgb([220, 40, 40]), Rgb([40, 180, 60]), Rgb([50, 70, 200])], vec![Rgb([240, 200, 30]), Rgb([30, 30, 30]), Rgb([160, 60, 200])]];
+    let root = common::temp_root("itxt");
+    let opts = SaveOptions { embed_metadata: true, seed: 99, ..common::options(root.to_str().unwrap()) };
+    let images: Vec<DynamicImage> = tags
+        .iter()
+        .map(|t| DynamicImage::ImageRgb8(draw_marker_polygon(64, 64, t.len(), t, &opts.style, None)))
+        .collect();
+    let out_dir = PathBuf::from(save_all(&tags, 12.5, &images, &opts, &|| true).unwrap());
+    let manifest: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(out_dir.join("manifest.json")).unwrap()).unwrap();
+
+    for entry in manifest["tags"].as_array().unwrap() {
+        let file = std::fs::File::open(out_dir.join(entry["filename"].as_str().unwrap())).unwrap();
+        let reader = png::Decoder::new(file).read_info().unwrap();
+        let chunk = reader.info().utf8_text.iter().find(|c| c.keyword == PNG_METADATA_KEYWORD).expect("polycue iTXt chunk");
+        let embedded: serde_json::Value = serde_json::from_str(&chunk.get_text().unwrap()).unwrap();
+        assert_eq!(embedded["tag"], *entry);
+        assert_eq!(embedded["seed"], 99);
+        assert_eq!(embedded["sides"], 3);
+        assert!((embedded["threshold"].as_f64().unwrap() - 12.5).abs() < 1e-6);
+    }
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn dictionary_reuses_the_manifest_colors() {
+    let tags = vec![vec![Rgb([220, 40, 40]), Rgb([40, 180, 60]), Rgb([50, 70, 200])], vec![Rgb([240, 200, 30]), Rgb([30, 30, 30]), Rgb([160, 60, 200]), Rgb([90, 200, 220])]];
+    let root = common::temp_root("dictionary");
+    let opts = common::options(root.to_str().unwrap());
+    let out_dir = PathBuf::from(save_all(&tags, 12.5, &[], &opts, &|| true).unwrap());
+    let read = |name: &str| -> serde_json::Value { serde_json::from_str(&std::fs::read_to_string(out_dir.join(name)).unwrap()).unwrap() };
+    let (manifest, dictionary) = (read("manifest.json"), read("dictionary.json"));
+
+    // Mixed side counts: only per marker
+    assert!(dictionary.get("sides").is_none());
+    for (i, (marker, entry)) in dictionary["markers"].as_array().unwrap().iter().zip(manifest["tags"].as_array().unwrap()).enumerate() {
+        assert_eq!(marker["id"], i + 1);
+        assert_eq!(marker["sides"], entry["sides"]);
+        assert_eq!(marker["colors_rgb"], entry["colors_rgb"]);
+        assert_eq!(marker["colors_lab"], entry["colors_lab"]);
+    }
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+/// Big-endian reader over an .ase file
+struct AseReader<'a>(&'a [u8]);
+
+impl AseReader<'_> {
+    fn take(&mut self, n: usize) -> &[u8] {
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        head
+    }
+    fn u16(&mut self) -> u16 {
+        u16::from_be_bytes(self.take(2).try_into().unwrap())
+    }
+    fn u32(&mut self) -> u32 {
+        u32::from_be_bytes(self.take(4).try_into().unwrap())
+    }
+    fn f32(&mut self) -> f32 {
+        f32::from_be_bytes(self.take(4).try_into().unwrap())
+    }
+    /// Length-prefixed, zero-terminated UTF-16BE name
+    fn name(&mut self) -> String {
+        let units: Vec<u16> = (0..self.u16()).map(|_| self.u16()).collect();
+        assert_eq!(units.last(), Some(&0), "name is not zero-terminated");
+        String::from_utf16(&units[..units.len() - 1]).unwrap()
+    }
+}
+
+#[test]
+fn ase_file_has_the_swatch_exchange_layout() {
+    let tags = vec![vec![Rgb([255, 0, 51]), Rgb([0, 128, 255])], vec![Rgb([10, 20, 30]), Rgb([40, 50, 60]), Rgb([70, 80, 90])]];
+    let root = common::temp_root("ase");
+    let path = root.join("tags.ase");
+    save_ase(&tags, path.to_str().unwrap()).unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    let mut r = AseReader(&bytes);
+
+    assert_eq!(r.take(4), b"ASEF");
+    assert_eq!((r.u16(), r.u16()), (1, 0), "version 1.0");
+    let blocks = r.u32();
+    assert_eq!(blocks, 2 + 2 + 3 + 2, "a start and end block per group plus one per color");
+
+    let mut seen = 0;
+    for (t, colors) in tags.iter().enumerate() {
+        assert_eq!(r.u16(), 0xC001, "group start");
+        let len = r.u32() as usize;
+        let group = r.name();
+        assert_eq!(group, format!("Tag{:02}", t + 1));
+        assert_eq!(len, 2 + 2 * (group.len() + 1));
+        for (k, c) in colors.iter().enumerate() {
+            assert_eq!(r.u16(), 0x0001, "color entry");
+            let len = r.u32() as usize;
+            let before = r.0.len();
+            assert_eq!(r.name(), format!("Tag{:02}-{}", t + 1, k + 1));
+            assert_eq!(r.take(4), b"RGB ");
+            for v in c.0 {
+                assert_eq!(r.f32(), v as f32 / 255.0);
+            }
+            assert_eq!(r.u16(), 2, "normal color type");
+            assert_eq!(before - r.0.len(), len, "block length matches its body");
+        }
+        assert_eq!(r.u16(), 0xC002, "group end");
+        assert_eq!(r.u32(), 0);
+        seen += colors.len() + 2;
+    }
+    assert_eq!(seen, blocks as usize);
+    assert!(r.0.is_empty(), "{} trailing bytes", r.0.len());
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn verify_skips_images_shrunk_after_saving() {
+    let tags = vec![vec![Rgb([220, 40, 40]), Rgb([40, 180, 60]), Rgb([50, 70, 200])]; 2];
+    for (name, style) in [
+        ("verify-border", MarkerStyle { fiducial_border: true, ..Default::default() }),
+        ("verify-quiet", MarkerStyle { quiet_zone_pct: 0.1, ..Default::default() }),
+        ("verify-dither", MarkerStyle { dither_levels: Some(4), ..Default::default() }),
+    ] {
+        let root = common::temp_root(name);
+        let opts = SaveOptions { style, ..common::options(root.to_str().unwrap()) };
+        let images: Vec<DynamicImage> = tags.iter().map(|t| DynamicImage::ImageRgb8(draw_marker_polygon(64, 64, t.len(), t, &style, None))).collect();
+        let out_dir = PathBuf::from(save_all(&tags, 10.0, &images, &opts, &|| true).unwrap());
+        RgbImage::from_pixel(3, 3, Rgb([0, 0, 0])).save(out_dir.join("tag_01.png")).unwrap();
+
+        let report = verify_output(out_dir.to_str().unwrap()).unwrap();
+        assert_eq!(report.skipped.iter().map(|(t, _)| *t).collect::<Vec<_>>(), vec![1], "{name}: {:?}", report.skipped);
+        assert_eq!(report.tags_checked, 1, "{name}");
+        assert!(report.mismatches.is_empty(), "{name}: {} mismatches", report.mismatches.len());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
+
+#[test]
+fn verify_reads_every_manifest_format() {
+    let tags = vec![vec![Rgb([220, 40, 40]), Rgb([40, 180, 60]), Rgb([50, 70, 200])], vec![Rgb([240, 200, 30]), Rgb([30, 30, 30]), Rgb([160, 60, 200])]];
+    let style = MarkerStyle { fiducial_border: true, ..Default::default() };
+    let images: Vec<DynamicImage> = tags.iter().map(|t| DynamicImage::ImageRgb8(draw_marker_polygon(64, 64, t.len(), t, &style, None))).collect();
+    for format in ManifestFormat::ALL {
+        let root = common::temp_root(&format!("verify-{}", format.extension()));
+        let opts = SaveOptions { style, manifest_format: format, ..common::options(root.to_str().unwrap()) };
+        let out_dir = PathBuf::from(save_all(&tags, 10.0, &images, &opts, &|| true).unwrap());
+        let report = verify_output(out_dir.to_str().unwrap()).unwrap();
+        assert_eq!((report.tags_checked, report.wedges_checked), (2, 6), "{format:?}: {:?}", report.skipped);
+        assert!(report.mismatches.is_empty(), "{format:?}: {:?}", report.mismatches);
+
+        // Tag 2 redrawn in tag 1's colors no longer matches its entry
+        let swapped = draw_marker_polygon(64, 64, 3, &tags[0], &style, None);
+        DynamicImage::ImageRgb8(swapped).save(out_dir.join("tag_02.png")).unwrap();
+        let report = verify_output(out_dir.to_str().unwrap()).unwrap();
+        assert_eq!(report.mismatches.iter().map(|m| m.tag).collect::<Vec<_>>(), vec![2, 2, 2], "{format:?}");
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
+
+#[test]
+fn resume_merges_a_manifest_in_any_format() {
+    let tags: Vec<Vec<Rgb<u8>>> = (0..3u8).map(|i| vec![Rgb([i * 80, 40, 40]), Rgb([40, 180, i * 60]), Rgb([50, 70, 200])]).collect();
+    let images: Vec<DynamicImage> = tags.iter().map(|t| DynamicImage::ImageRgb8(draw_marker_polygon(64, 64, t.len(), t, &MarkerStyle::default(), None))).collect();
+    for (i, format) in ManifestFormat::ALL.into_iter().enumerate() {
+        let root = common::temp_root(&format!("resume-{}", format.extension()));
+        let opts = SaveOptions { manifest_format: format, ..common::options(root.to_str().unwrap()) };
+        let out_dir = save_all(&tags, 10.0, &images, &opts, &|| true).unwrap();
+        std::fs::remove_file(PathBuf::from(&out_dir).join("tag_02.png")).unwrap();
+
+        // Tag 1 changed since, but its file is still there, so its old entry is kept.
+        // The resumed save writes its manifest in the next format, which replaces the old one.
+        let mut current = tags.clone();
+        current[0][0] = Rgb([1, 2, 3]);
+        let missing: Vec<Option<DynamicImage>> = (0..3).map(|k| (k == 1).then(|| images[1].clone())).collect();
+        let next = ManifestFormat::ALL[(i + 1) % 3];
+        resume_save_to_dir(&out_dir, &current, 10.0, &missing, &SaveOptions { manifest_format: next, ..opts.clone() }, &|| true).unwrap();
+
+        let (manifest, found) = read_manifest(&out_dir).unwrap().expect("a manifest");
+        assert_eq!(found, next);
+        assert!(!PathBuf::from(&out_dir).join(format!("manifest.{}", format.extension())).exists(), "{format:?} left beside {next:?}");
+        assert_eq!(manifest.tags.iter().map(|e| e.filename.as_str()).collect::<Vec<_>>(), ["tag_01.png", "tag_02.png", "tag_03.png"]);
+        assert_eq!(manifest.tags[0].colors_rgb[0], (0, 40, 40), "{format:?}");
+        assert!(PathBuf::from(&out_dir).join("tag_02.png").is_file());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
+
+#[test]
+fn yaml_and_toml_manifests_match_the_json_tree() {
+    let tags = vec![vec![Rgb([220, 40, 40]), Rgb([40, 180, 60]), Rgb([50, 70, 200])], vec![Rgb([240, 200, 30]), Rgb([30, 30, 30]), Rgb([160, 60, 200])]];
+    let root = common::temp_root("manifest-formats");
+    // Nested objects, lists of objects, a string that needs quoting and a seed above i64::MAX
+    let style = MarkerStyle { fiducial_border: true, center_dot: true, ..Default::default() };
+    let images: Vec<DynamicImage> = tags.iter().map(|t| DynamicImage::ImageRgb8(draw_marker_polygon(64, 64, t.len(), t, &style, None))).collect();
+    let save = |format: ManifestFormat| {
+        let opts = SaveOptions {
+            style,
+            manifest_format: format,
+            wedge_layers: true,
+            similar_tag_distance: Some(100.0),
+            tag_rotations: vec![12.5, 40.0],
+            categories: vec![Some("Bin: \"A\" #1".into()), None],
+            seed: u64::MAX,
+            ..common::options(root.join(format.extension()).to_str().unwrap())
+        };
+        let out_dir = PathBuf::from(save_all(&tags, 12.5, &images, &opts, &|| true).unwrap());
+        std::fs::read_to_string(out_dir.join(format!("manifest.{}", format.extension()))).unwrap()
+    };
+
+    let json: serde_json::Value = serde_json::from_str(&save(ManifestFormat::Json)).unwrap();
+    assert!(json["tags"][0]["nearest_pantone"].is_array() && json["fiducial_border"].is_object());
+    // Read back into the manifest types, then compared as the JSON they write
+    let as_json = |manifest: Manifest| serde_json::from_str::<serde_json::Value>(&serde_json::to_string(&manifest).unwrap()).unwrap();
+    let yaml: Manifest = serde_yaml::from_str(&save(ManifestFormat::Yaml)).unwrap();
+    let toml: Manifest = toml::from_str(&save(ManifestFormat::Toml)).unwrap();
+    assert_eq!(toml.seed, u64::MAX);
+    assert_eq!(as_json(yaml), json);
+    assert_eq!(as_json(toml), json);
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn manifest_without_settings_still_parses() {
+    let tags = vec![vec![Rgb([220, 40, 40]), Rgb([40, 180, 60]), Rgb([50, 70, 200])]; 2];
+    let images: Vec<DynamicImage> = tags.iter().map(|t| DynamicImage::ImageRgb8(draw_marker_polygon(64, 64, t.len(), t, &MarkerStyle::default(), None))).collect();
+    let root = common::temp_root("no-settings");
+    let opts = SaveOptions { seed: 5, ..common::options(root.to_str().unwrap()) };
+    let out_dir = PathBuf::from(save_all(&tags, 12.5, &images, &opts, &|| true).unwrap());
+
+    // As written before generation settings were recorded
+    let mut tree: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(out_dir.join("manifest.json")).unwrap()).unwrap();
+    assert!(tree.as_object_mut().unwrap().remove("settings").is_some());
+    let manifest: Manifest = serde_json::from_value(tree).unwrap();
+    assert_eq!(manifest.seed, 5);
+    assert_eq!(manifest.settings.count, GenerationSettings::default().count);
+    assert_eq!(manifest.tags.len(), 2);
+    assert_eq!(manifest.tags[1].filename, "tag_02.png");
+    assert_eq!(manifest.tags[0].colors_rgb, vec![(220, 40, 40), (40, 180, 60), (50, 70, 200)]);
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn summary_reports_tag_count_sides_and_threshold() {
+    let tags: Vec<Vec<Rgb<u8>>> = (0..3u8)
+        .map(|i| vec![Rgb([i * 60, 0, 0]), Rgb([0, 200, i * 60]), Rgb([0, 0, 250]), Rgb([250, 250, i * 60])])
+        .collect();
+    let images: Vec<DynamicImage> = tags.iter().map(|t| DynamicImage::ImageRgb8(RgbImage::from_pixel(TILE, TILE, t[0]))).collect();
+    let root = common::temp_root("summary");
+    let out_dir = save_all(&tags, 12.5, &images, &common::options(root.to_str().unwrap()), &|| true).unwrap();
+
+    let summary = std::fs::read_to_string(PathBuf::from(&out_dir).join("summary.txt")).unwrap();
+    let line = |label: &str| summary.lines().find_map(|l| l.strip_prefix(label)).map(str::trim).unwrap_or_else(|| panic!("no {label} line in:\n{summary}"));
+    assert_eq!(line("Tags:"), "3");
+    assert_eq!(line("Sides:"), "4");
+    assert!(line("Threshold:").starts_with("ΔE 12.50 ("), "{}", line("Threshold:"));
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn wedge_spec_angles_cover_the_circle_from_the_top() {
+    let colors = [Rgb([200, 30, 30]), Rgb([30, 200, 30]), Rgb([30, 30, 200])];
+    for (shape, sides) in [(MarkerShape::Polygon, 3), (MarkerShape::Polygon, 7), (MarkerShape::Polygon, 12), (MarkerShape::Sectors { count: 16 }, 5)] {
+        for rotation in [0.0, 17.5] {
+            let spec = wedge_spec(&colors, shape, sides, rotation);
+            assert_eq!(spec.wedges.len(), shape.wedges(sides));
+            let total: f32 = spec.wedges.iter().map(|w| w.end_deg - w.start_deg).sum();
+            assert!((total - 360.0).abs() < 1e-3, "{shape:?}, {rotation}°: {total}");
+            // Wedge 0 starts straight up (-90° in image coordinates), turned by the rotation
+            assert_eq!(spec.wedges[0].start_deg, -90.0 + rotation);
+            for pair in spec.wedges.windows(2) {
+                assert!((pair[1].start_deg - pair[0].end_deg).abs() < 1e-3);
+            }
+            let (x, y) = spec.wedges[0].vertices[1];
+            assert!((x - (-90.0 + rotation).to_radians().cos()).abs() < 1e-5 && (y - (-90.0 + rotation).to_radians().sin()).abs() < 1e-5);
+            // Colors repeat when there are more wedges than colors
+            for (i, w) in spec.wedges.iter().enumerate() {
+                let c = colors[i % colors.len()];
+                assert_eq!(w.rgb, (c[0], c[1], c[2]));
+            }
+        }
+    }
+}
+
+#[test]
+fn saves_in_the_same_second_get_numbered_folders() {
+    let root = common::temp_root("timestamped");
+    // A fresh root per try, retrying if the clock ticks over between the two calls
+    let (first, second) = (0..5)
+        .map(|attempt| {
+            let out_root = root.join(attempt.to_string()).display().to_string();
+            (create_timestamped_dir(&out_root).unwrap(), create_timestamped_dir(&out_root).unwrap())
+        })
+        .find(|(first, second)| second.starts_with(first.as_str()))
+        .expect("two saves within one second");
+    assert_eq!(second, format!("{first}_2"));
+    assert!(PathBuf::from(&first).is_dir() && PathBuf::from(&second).is_dir());
+    std::fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,152 @@
+//! Marker rendering: wedge fills, gradients, dithering and small canvases
+
+use image::{Rgb, RgbImage};
+use polycue::render::{dither_color, downscale_linear, draw_marker_polygon, wedge_gradient_color, MarkerStyle, BAYER_4X4, MIN_WEDGE_CANVAS_PX, WEDGE_GRADIENT_LIGHTEN};
+
+fn style(min_wedge_px: u32, rotation_deg: f32) -> MarkerStyle {
+    MarkerStyle { min_wedge_px, rotation_deg, ..Default::default() }
+}
+
+fn palette(sides: usize) -> Vec<Rgb<u8>> {
+    (0..sides).map(|i| Rgb([10 + i as u8 * 19, 200 - i as u8 * 13, 40 + i as u8 * 7])).collect()
+}
+
+fn wedge_pixel_counts(size: u32, sides: usize, style: &MarkerStyle) -> Vec<usize> {
+    let colors = palette(sides);
+    let img = draw_marker_polygon(size, size, sides, &colors, style, None);
+    colors.iter().map(|c| img.pixels().filter(|p| *p == c).count()).collect()
+}
+
+#[test]
+fn six_sided_tag_at_8px_keeps_every_color() {
+    for step in 0..12 {
+        let rotation = step as f32 * 5.0;
+        let counts = wedge_pixel_counts(8, 6, &style(1, rotation));
+        assert!(counts.iter().all(|&n| n >= 1), "rotation {rotation}: {counts:?}");
+    }
+}
+
+#[test]
+fn many_sides_on_tiny_canvas_meet_minimum() {
+    for (size, sides, min_px) in [(4, 8, 1), (5, 9, 1), (6, 12, 1), (8, 12, 1), (9, 8, 2)] {
+        for step in 0..12 {
+            let rotation = step as f32 * 5.0;
+            let counts = wedge_pixel_counts(size, sides, &style(min_px, rotation));
+            assert!(counts.iter().all(|&n| n >= min_px as usize), "{size}px, {sides} sides, rotation {rotation}: {counts:?}");
+        }
+    }
+    // Below that every pixel shows a wedge color, and as many wedges as there are pixels
+    // get one, even with dots and a serial number asked for
+    let overlays = |rotation_deg: f32| MarkerStyle { center_dot: true, gradient_dot: true, rotation_deg, ..Default::default() };
+    for size in 1..=3u32 {
+        for sides in [3, 4, 6, 9, 12] {
+            for step in 0..12 {
+                let rotation = step as f32 * 5.0;
+                let counts = wedge_pixel_counts(size, sides, &style(1, rotation));
+                assert_eq!(counts.iter().sum::<usize>(), (size * size) as usize, "{size}px, {sides} sides, rotation {rotation}: {counts:?}");
+                let shown = counts.iter().filter(|&&n| n > 0).count();
+                assert!(shown >= sides.min((size * size) as usize), "{size}px, {sides} sides, rotation {rotation}: {counts:?}");
+                let img = draw_marker_polygon(size, size, sides, &palette(sides), &overlays(rotation), Some((1, 0.5, 0.5, Rgb([0, 0, 0]), false)));
+                assert!(img.pixels().all(|p| palette(sides).contains(p)), "{size}px, {sides} sides with overlays");
+            }
+        }
+    }
+}
+
+#[test]
+fn min_wedge_only_applies_to_small_canvases() {
+    // Past the limit the wedges are painted directly, the same with or without a minimum
+    let size = MIN_WEDGE_CANVAS_PX + 1;
+    for sides in [3, 8, 12] {
+        let with = draw_marker_polygon(size, size, sides, &palette(sides), &style(4, 10.0), None);
+        let without = draw_marker_polygon(size, size, sides, &palette(sides), &style(0, 10.0), None);
+        assert!(with == without, "{sides} sides");
+        assert!(wedge_pixel_counts(size, sides, &style(0, 10.0)).iter().all(|&n| n >= 4));
+    }
+}
+
+/// Largest per-channel difference between two colors
+fn channel_diff(a: Rgb<u8>, b: Rgb<u8>) -> u8 {
+    a.0.iter().zip(b.0).map(|(&x, y)| x.abs_diff(y)).max().unwrap_or(0)
+}
+
+#[test]
+fn wedge_gradient_is_lightest_at_the_centroid_and_base_at_the_rim() {
+    let base = Rgb([40, 100, 200]);
+    let lighten = |c: u8| (c as f32 + (255.0 - c as f32) * WEDGE_GRADIENT_LIGHTEN).round() as u8;
+    assert_eq!(wedge_gradient_color(base, 0.0), Rgb(base.0.map(lighten)));
+    assert_eq!(wedge_gradient_color(base, 1.0), base);
+
+    // Square tag: wedge 0 spans from the top vertex clockwise, its bisector pointing up-right
+    let size = 200;
+    let colors = [base, Rgb([200, 60, 40]), Rgb([60, 160, 60]), Rgb([120, 40, 140])];
+    let img = draw_marker_polygon(size, size, 4, &colors, &MarkerStyle { wedge_gradient: true, ..Default::default() }, None);
+    let c = size as f32 / 2.0;
+    let radius = c - MarkerStyle::default().margin_pct * size as f32;
+    let apothem = radius * std::f32::consts::FRAC_PI_4.cos();
+    let on_bisector = |d: f32| {
+        let a = -std::f32::consts::FRAC_PI_4;
+        *img.get_pixel((c + d * a.cos()) as u32, (c + d * a.sin()) as u32)
+    };
+    for d in [3.0, apothem / 2.0, apothem - 2.0] {
+        let expected = wedge_gradient_color(base, d / apothem);
+        assert!(channel_diff(on_bisector(d), expected) <= 2, "{d}px out: {:?} not {expected:?}", on_bisector(d));
+    }
+    assert!(channel_diff(on_bisector(3.0), wedge_gradient_color(base, 0.0)) <= 8);
+    assert!(channel_diff(on_bisector(apothem - 2.0), base) <= 4);
+}
+
+#[test]
+fn two_level_dither_follows_the_bayer_pattern() {
+    // A mid gray sits just above half way, so it turns white wherever the Bayer threshold
+    // is in the lower half of the matrix (entries 0-7) and black elsewhere
+    let gray = Rgb([128, 128, 128]);
+    let expected = |x: u32, y: u32| if BAYER_4X4[(y % 4) as usize][(x % 4) as usize] < 8 { Rgb([255; 3]) } else { Rgb([0; 3]) };
+    for y in 0..8 {
+        for x in 0..8 {
+            assert_eq!(dither_color(gray, 2, x, y), expected(x, y), "({x}, {y})");
+        }
+    }
+
+    let style = MarkerStyle { dither_levels: Some(2), ..Default::default() };
+    let img = draw_marker_polygon(64, 64, 6, &[gray; 6], &style, None);
+    assert!(img.pixels().all(|p| p.0.iter().all(|&v| v == 0 || v == 255)), "only the two quantized levels");
+    // Well inside the hexagon every pixel follows the pattern in image coordinates
+    for y in 24..40 {
+        for x in 24..40 {
+            assert_eq!(*img.get_pixel(x, y), expected(x, y), "({x}, {y})");
+        }
+    }
+}
+
+#[test]
+fn linear_downscale_matches_a_direct_small_render() {
+    let (big, small) = (256, 32);
+    let colors = palette(6);
+    let style = MarkerStyle::default();
+    let down = downscale_linear(&draw_marker_polygon(big, big, 6, &colors, &style, None), small, small);
+    let direct = draw_marker_polygon(small, small, 6, &colors, &style, None);
+
+    // Halfway out along each wedge's bisector both show the flat wedge color
+    let c = small as f32 / 2.0;
+    let radius = c - style.margin_pct * small as f32;
+    for (i, &color) in colors.iter().enumerate() {
+        let a = -std::f32::consts::FRAC_PI_2 + std::f32::consts::TAU / 6.0 * (i as f32 + 0.5);
+        let (x, y) = ((c + 0.5 * radius * a.cos()) as u32, (c + 0.5 * radius * a.sin()) as u32);
+        assert_eq!(*direct.get_pixel(x, y), color, "wedge {i} drawn directly");
+        assert!(channel_diff(*down.get_pixel(x, y), color) <= 2, "wedge {i} downscaled: {:?} not {color:?}", down.get_pixel(x, y));
+    }
+    // The background away from the marker is untouched
+    assert_eq!(*down.get_pixel(0, 0), *direct.get_pixel(0, 0));
+
+    // Fine black and white lines blend to half the light (sRGB 188), not to the sRGB mean 128.
+    // The filter is cut off at the left and right edges, so those columns lean to one side.
+    let stripes = RgbImage::from_fn(64, 64, |x, _| if x % 2 == 0 { Rgb([0; 3]) } else { Rgb([255; 3]) });
+    let blended = downscale_linear(&stripes, 16, 16);
+    let off: Vec<_> = blended
+        .enumerate_pixels()
+        .filter(|&(x, _, p)| (1..15).contains(&x) && p.0.iter().any(|&v| v.abs_diff(188) > 2))
+        .map(|(x, y, p)| (x, y, p[0]))
+        .collect();
+    assert!(off.is_empty(), "{off:?}");
+}